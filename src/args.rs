@@ -0,0 +1,94 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+pub const USAGE: &str = "\
+usage: wwm [options]
+
+  --config <path>   read the config from <path> instead of ~/.config/wwm/wwm.toml
+  --check-config    check the config file for mistakes without starting
+  --replace         take over from the window manager that is running
+  --version         print the version
+  --help            print this";
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ArgsError {
+    #[error("unknown argument `{0}`")]
+    Unknown(String),
+    #[error("`{0}` needs a value")]
+    MissingValue(String),
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct WArgs {
+    pub config: Option<PathBuf>,
+    pub check_config: bool,
+    // takes over from a wm that is already running instead of refusing to start
+    pub replace: bool,
+    // the session manager restarts us with the client id we had before
+    pub sm_client_id: Option<String>,
+    pub version: bool,
+    pub help: bool,
+}
+
+impl WArgs {
+    // the arguments after the program name. values go after their flag, either as the
+    // next argument or after a `=`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, ArgsError> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| ArgsError::MissingValue(flag.clone()))
+            };
+            match flag.as_str() {
+                "--config" => parsed.config = Some(value()?.into()),
+                "--sm-client-id" => parsed.sm_client_id = Some(value()?),
+                "--check-config" => parsed.check_config = true,
+                "--replace" => parsed.replace = true,
+                "--version" | "-V" => parsed.version = true,
+                "--help" | "-h" => parsed.help = true,
+                _ => return Err(ArgsError::Unknown(flag)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<WArgs, ArgsError> {
+        WArgs::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn flags_and_values() {
+        let args = parse(&["--config", "/tmp/wwm.toml", "--check-config"]).unwrap();
+        assert_eq!(args.config, Some(PathBuf::from("/tmp/wwm.toml")));
+        assert!(args.check_config && !args.replace);
+        let args = parse(&["--sm-client-id=10abc", "--replace"]).unwrap();
+        assert_eq!(args.sm_client_id.as_deref(), Some("10abc"));
+        assert!(args.replace);
+    }
+
+    #[test]
+    fn mistakes_are_reported() {
+        let missing = ArgsError::MissingValue("--config".into());
+        assert_eq!(parse(&["--config"]), Err(missing));
+        assert_eq!(
+            parse(&["--verbose"]),
+            Err(ArgsError::Unknown("--verbose".into()))
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{ConfigureWindowAux, ConnectionExt, Window},
+    rust_connection::ConnectionError,
+};
+
+// collects window configurations so that everything belonging to a single layout
+// change reaches the server at once, with every window configured only once
+#[derive(Default)]
+pub struct WConfigureBatch {
+    ops: Vec<(Window, ConfigureWindowAux)>,
+}
+
+impl WConfigureBatch {
+    pub fn configure(&mut self, win: Window, aux: ConfigureWindowAux) {
+        match self.ops.iter_mut().find(|(w, _)| *w == win) {
+            // later values win, values that weren't set again are kept
+            Some((_, prev)) => {
+                *prev = ConfigureWindowAux {
+                    x: aux.x.or(prev.x),
+                    y: aux.y.or(prev.y),
+                    width: aux.width.or(prev.width),
+                    height: aux.height.or(prev.height),
+                    border_width: aux.border_width.or(prev.border_width),
+                    sibling: aux.sibling.or(prev.sibling),
+                    stack_mode: aux.stack_mode.or(prev.stack_mode),
+                }
+            }
+            None => self.ops.push((win, aux)),
+        }
+    }
+
+    pub fn send<C: Connection>(self, conn: &C) -> Result<(), ConnectionError> {
+        for (win, aux) in &self.ops {
+            conn.configure_window(*win, aux)?;
+        }
+        conn.flush()
+    }
+}
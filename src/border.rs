@@ -0,0 +1,90 @@
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{
+        ChangeGCAux, ChangeWindowAttributesAux, ConnectionExt, CreateGCAux, Rectangle,
+    },
+    rust_connection::ReplyOrIdError,
+};
+
+use crate::{client::WClientState, config::theme::window, settings::WTheme};
+
+#[derive(Debug, Clone, Copy)]
+pub struct WBorderAccent {
+    // how much of the border, from the inside, is drawn in the accent color
+    pub width: u16,
+    pub focused: u32,
+    pub unfocused: u32,
+}
+
+// paints the border of the client. an accent takes a pixmap the size of the window,
+// which has to be painted again whenever the client is resized.
+pub fn paint<C: Connection>(
+    conn: &C,
+    theme: &WTheme,
+    c: &WClientState,
+    focused: bool,
+) -> Result<(), ReplyOrIdError> {
+    if c.no_border {
+        return Ok(());
+    }
+    let color = if focused {
+        theme.border_focused
+    } else {
+        theme.border_unfocused
+    };
+    // windows with an alpha channel would get a see-through border otherwise
+    let opaque = if c.depth == 32 { 0xff00_0000 } else { 0 };
+
+    let bw = c.bw;
+    let (accent, accent_color) = match window::BORDER_ACCENT {
+        Some(a) if focused => (a.width.min(bw), a.focused),
+        Some(a) => (a.width.min(bw), a.unfocused),
+        None => (0, color),
+    };
+    let (color, accent_color) = (color | opaque, accent_color | opaque);
+    if accent == 0 || accent == bw {
+        let pixel = if accent == 0 { color } else { accent_color };
+        let aux = ChangeWindowAttributesAux::new().border_pixel(pixel);
+        conn.change_window_attributes(c.window, &aux)?;
+        return Ok(());
+    }
+
+    let (w, h) = (c.rect.w, c.rect.h);
+    let (pw, ph) = (w + bw * 2, h + bw * 2);
+    let pixmap = conn.generate_id()?;
+    conn.create_pixmap(c.depth, pixmap, c.window, pw, ph)?;
+    let gc = conn.generate_id()?;
+    conn.create_gc(gc, pixmap, &CreateGCAux::new().foreground(color))?;
+    conn.poly_fill_rectangle(pixmap, gc, &[rect(0, 0, pw, ph)])?;
+
+    // the border is tiled starting from the top left corner inside of it,
+    // so what is left of or above the window comes from the far end of the pixmap
+    let (x, y) = (w as i16, h as i16);
+    let (far_x, far_y) = ((pw - accent) as i16, (ph - accent) as i16);
+    let accent_rects = [
+        rect(x, 0, accent, h + accent),
+        rect(far_x, 0, accent, h + accent),
+        rect(0, y, w + accent, accent),
+        rect(0, far_y, w + accent, accent),
+        rect(x, far_y, accent, accent),
+        rect(far_x, y, accent, accent),
+        rect(far_x, far_y, accent, accent),
+    ];
+    conn.change_gc(gc, &ChangeGCAux::new().foreground(accent_color))?;
+    conn.poly_fill_rectangle(pixmap, gc, &accent_rects)?;
+
+    let aux = ChangeWindowAttributesAux::new().border_pixmap(pixmap);
+    conn.change_window_attributes(c.window, &aux)?;
+    conn.free_gc(gc)?;
+    conn.free_pixmap(pixmap)?;
+    Ok(())
+}
+
+fn rect(x: i16, y: i16, width: u16, height: u16) -> Rectangle {
+    Rectangle {
+        x,
+        y,
+        width,
+        height,
+    }
+}
@@ -1,18 +1,31 @@
+use serde::Deserialize;
 use x11rb::{properties::WmSizeHints, protocol::xproto::Window};
 
-use crate::config::theme::window::BORDER_WIDTH;
-use wwm_core::util::primitives::{WRect, WSize};
+use crate::{
+    config::{ping, theme::window::BORDER_WIDTH},
+    pip::WCorner,
+};
+use wwm_core::{
+    icon::WIcon,
+    util::primitives::{WRect, WSize},
+};
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone)]
 pub struct WClientState {
     pub window: Window,
     pub rect: WRect,
     pub old_rect: WRect,
     pub is_floating: bool,
     pub is_fullscreen: bool,
+    // the monitors at the top, bottom, left and right edge of the area the client covers
+    // while fullscreen, if it asked to span several of them
+    pub fullscreen_monitors: Option<[usize; 4]>,
     pub is_fixed: bool,
     pub hints_valid: bool,
     pub bw: u16,
+    // set for clients that asked for no decorations with _MOTIF_WM_HINTS
+    pub no_border: bool,
+    pub depth: u8,
     pub base_size: Option<WSize>,
     pub min_size: Option<WSize>,
     pub max_size: Option<WSize>,
@@ -25,6 +38,45 @@ pub struct WClientState {
     pub old_bw: u16,
     pub prev: Option<usize>,
     pub next: Option<usize>,
+    pub title: String,
+    pub class: String,
+    // scaled for the bar of the monitor it was mapped on
+    pub icon: Option<WIcon>,
+    // the corner a picture-in-picture client is snapped to
+    pub pip: Option<WCorner>,
+    // set for clients that didn't take the focus when they were mapped, until focused
+    pub is_urgent: bool,
+    // the share of the height the client gets next to the others on its side of the
+    // main-stack layout, dwm's cfact
+    pub size_factor: f32,
+    pub stack_layer: WStackLayer,
+    // the icccm focus model of the client: whether it wants the input focus set on it
+    // (the input field of WM_HINTS) and whether it takes it itself on WM_TAKE_FOCUS
+    pub accepts_input: bool,
+    pub takes_focus: bool,
+    pub supports_ping: bool,
+    // set once the client doesn't answer a ping in time, until it does again
+    pub is_unresponsive: bool,
+}
+
+// where a client is kept in the stacking order, set by _NET_WM_STATE_ABOVE and
+// _NET_WM_STATE_BELOW. clients in the normal layer are raised as they are used.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WStackLayer {
+    Below,
+    #[default]
+    Normal,
+    Above,
+}
+
+// whether a newly mapped client takes the focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WFocusPolicy {
+    Always,
+    // only when it shows up on the shown tag of the focused monitor
+    VisibleTag,
+    Never,
 }
 
 impl WClientState {
@@ -43,9 +95,12 @@ impl WClientState {
             old_rect,
             is_floating,
             is_fullscreen,
+            fullscreen_monitors: None,
             is_fixed: false,
             hints_valid: false,
             bw: BORDER_WIDTH,
+            no_border: false,
+            depth: 0,
             base_size: None,
             min_size: None,
             max_size: None,
@@ -58,6 +113,44 @@ impl WClientState {
             old_bw: 0,
             prev: None,
             next: None,
+            title: String::new(),
+            class: String::new(),
+            icon: None,
+            pip: None,
+            is_urgent: false,
+            size_factor: 1.0,
+            stack_layer: WStackLayer::Normal,
+            accepts_input: true,
+            takes_focus: false,
+            supports_ping: false,
+            is_unresponsive: false,
+        }
+    }
+
+    // what the bar shows for the client, which is its class if it has no title
+    pub fn bar_title(&self) -> &str {
+        if self.title.is_empty() {
+            &self.class
+        } else {
+            &self.title
+        }
+    }
+
+    // the border width the client gets where others get `bw`
+    pub fn decorated_bw(&self, bw: u16) -> u16 {
+        if self.no_border {
+            0
+        } else {
+            bw
+        }
+    }
+
+    // the title section of the bar, which tells when the client stopped responding
+    pub fn bar_text(&self) -> String {
+        if self.is_unresponsive {
+            format!("{} {}", ping::INDICATOR, self.bar_title())
+        } else {
+            self.bar_title().to_string()
         }
     }
 
@@ -112,19 +205,18 @@ impl WClientState {
         // ICCCM 4.1.2.3
         let mut base_is_min = false;
         let mut base_exists = false;
-        let mut min_exists = false;
         if let Some(base_size) = self.base_size {
             base_exists = true;
             if let Some(min_size) = self.min_size {
-                min_exists = true;
                 base_is_min = base_size.w == min_size.w && base_size.h == min_size.h;
             }
         }
 
-        if base_is_min && base_exists {
+        // the base size is left out of the aspect ratio, unless it is the minimum size
+        if !base_is_min && base_exists {
             let base = self.base_size.unwrap();
-            w -= base.w;
-            h -= base.h;
+            w = w.saturating_sub(base.w);
+            h = h.saturating_sub(base.h);
         }
 
         if let Some(maxa) = self.maxa {
@@ -139,28 +231,80 @@ impl WClientState {
             }
         }
 
+        // the increments count from the base size
         if base_is_min && base_exists {
             let base = self.base_size.unwrap();
-            w -= base.w;
-            h -= base.h;
+            w = w.saturating_sub(base.w);
+            h = h.saturating_sub(base.h);
         }
 
         if let Some(inc_size) = self.inc_size {
-            w -= w % inc_size.w;
-            h -= h % inc_size.h;
+            if inc_size.w > 0 {
+                w -= w % inc_size.w;
+            }
+            if inc_size.h > 0 {
+                h -= h % inc_size.h;
+            }
         }
 
-        if min_exists && base_exists {
-            let base = self.base_size.unwrap();
-            let min = self.min_size.unwrap();
-            w = min.w.max(w + base.w);
-            h = min.h.max(h + base.h);
+        if let Some(base) = self.base_size {
+            w += base.w;
+            h += base.h;
+        }
+        if let Some(min) = self.min_size {
+            w = w.max(min.w);
+            h = h.max(min.h);
         }
 
+        // a maximum of 0 means there is none
         if let Some(max_size) = self.max_size {
-            w = w.min(max_size.w);
-            h = h.min(max_size.h);
+            if max_size.w > 0 {
+                w = w.min(max_size.w);
+            }
+            if max_size.h > 0 {
+                h = h.min(max_size.h);
+            }
         }
         (w, h)
     }
 }
+
+// whether _MOTIF_WM_HINTS asks for no decorations. its fields are the flags, functions,
+// decorations, input mode and status, where the decorations only count if flagged.
+pub fn motif_no_decorations(hints: &[u32]) -> bool {
+    const DECORATIONS_FLAG: u32 = 1 << 1;
+    // all decorations, the border or the title
+    const BORDER_DECORATIONS: u32 = 1 | 1 << 1 | 1 << 3;
+    match hints {
+        [flags, _, decorations, ..] => {
+            flags & DECORATIONS_FLAG != 0 && decorations & BORDER_DECORATIONS == 0
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_flagged_decorations_count() {
+        assert!(motif_no_decorations(&[2, 0, 0, 0, 0]));
+        assert!(!motif_no_decorations(&[2, 0, 1, 0, 0]));
+        assert!(!motif_no_decorations(&[1, 0, 0, 0, 0]));
+        assert!(!motif_no_decorations(&[2]));
+    }
+
+    #[test]
+    fn sizes_snap_to_the_increments_from_the_base_size() {
+        let c = WClientState {
+            base_size: Some(WSize { w: 2, h: 2 }),
+            min_size: Some(WSize { w: 11, h: 19 }),
+            inc_size: Some(WSize { w: 9, h: 17 }),
+            max_size: Some(WSize { w: 0, h: 0 }),
+            ..Default::default()
+        };
+        assert_eq!(c.adjust_aspect_ratio(500, 300), (497, 291));
+        assert_eq!(c.adjust_aspect_ratio(5, 5), (11, 19));
+    }
+}
@@ -0,0 +1,284 @@
+use std::{
+    collections::HashMap,
+    ops::{Index, IndexMut},
+    slice::Iter,
+};
+
+use x11rb::protocol::xproto::Window;
+
+use crate::{client::WClientState, config::tags::TAG_CAP};
+
+// the clients of a monitor along with the bookkeeping that links the clients
+// of each tag into a ring. none of it touches the x connection.
+#[derive(Default, Debug)]
+pub struct WClientList {
+    clients: Vec<WClientState>,
+    // maps windows to their index in `clients`, kept in sync whenever clients move
+    index: HashMap<Window, usize>,
+}
+
+impl WClientList {
+    pub fn iter(&self) -> Iter<'_, WClientState> {
+        self.clients.iter()
+    }
+
+    pub fn idx_of(&self, win: Window) -> Option<usize> {
+        self.index.get(&win).copied()
+    }
+
+    pub fn in_tag(&self, tag: usize) -> Vec<usize> {
+        (0..self.clients.len())
+            .filter(|i| self.clients[*i].tag == tag)
+            .collect()
+    }
+
+    // keeps `preferred` if it is in `tag` and falls back to the last client of the tag otherwise
+    pub fn selection_in_tag(&self, preferred: Option<usize>, tag: usize) -> Option<usize> {
+        preferred
+            .filter(|i| self.clients.get(*i).is_some_and(|c| c.tag == tag))
+            .or_else(|| self.in_tag(tag).last().copied())
+    }
+
+    // appends the client to the end of its tag and returns its index
+    pub fn push(&mut self, client: WClientState) -> usize {
+        let idx = self.clients.len();
+        let tag = client.tag;
+        self.index.insert(client.window, idx);
+        self.clients.push(client);
+        self.relink(tag);
+        idx
+    }
+
+    pub fn remove(&mut self, idx: usize) -> WClientState {
+        let mut c = self.clients.remove(idx);
+        c.prev = None;
+        c.next = None;
+
+        self.index.remove(&c.window);
+        // every client after the removed one shifted down by one
+        for (i, c) in self.clients.iter().enumerate().skip(idx) {
+            self.index.insert(c.window, i);
+        }
+
+        // indices changed for every tag, not only the one the client was in
+        for t in 0..TAG_CAP {
+            self.relink(t);
+        }
+        c
+    }
+
+    // swaps the positions of two clients sharing a tag
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.clients.swap(a, b);
+        self.index.insert(self.clients[a].window, a);
+        self.index.insert(self.clients[b].window, b);
+        self.relink(self.clients[a].tag);
+    }
+
+    pub fn set_tag(&mut self, idx: usize, tag: usize) {
+        let old_tag = self.clients[idx].tag;
+        self.clients[idx].tag = tag;
+        self.relink(old_tag);
+        self.relink(tag);
+    }
+
+    // moves the clients of tag `from` over to `to`. the tags in between shift over by one
+    // so that the others stay in the same order.
+    pub fn move_tag(&mut self, from: usize, to: usize) {
+        for c in self.clients.iter_mut() {
+            c.tag = moved_tag(c.tag, from, to);
+        }
+        for t in 0..TAG_CAP {
+            self.relink(t);
+        }
+    }
+
+    fn relink(&mut self, tag: usize) {
+        let tag_clients = self.in_tag(tag);
+        let n = tag_clients.len();
+
+        if n == 1 {
+            self.clients[tag_clients[0]].prev = None;
+            self.clients[tag_clients[0]].next = None;
+            return;
+        }
+
+        for (i, client_idx) in tag_clients.iter().enumerate() {
+            self.clients[*client_idx].prev = Some(tag_clients[(i + n - 1) % n]);
+            self.clients[*client_idx].next = Some(tag_clients[(i + 1) % n]);
+        }
+    }
+}
+
+// where `tag` ends up when tag `from` is moved to `to`
+pub fn moved_tag(tag: usize, from: usize, to: usize) -> usize {
+    if tag == from {
+        to
+    } else if from < tag && tag <= to {
+        tag - 1
+    } else if to <= tag && tag < from {
+        tag + 1
+    } else {
+        tag
+    }
+}
+
+impl Index<usize> for WClientList {
+    type Output = WClientState;
+
+    fn index(&self, idx: usize) -> &Self::Output {
+        &self.clients[idx]
+    }
+}
+
+// the window and tag of a client must not be changed through here
+// since the index and links would go stale
+impl IndexMut<usize> for WClientList {
+    fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+        &mut self.clients[idx]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(window: Window, tag: usize) -> WClientState {
+        WClientState {
+            window,
+            tag,
+            ..Default::default()
+        }
+    }
+
+    fn list(clients: &[(Window, usize)]) -> WClientList {
+        let mut list = WClientList::default();
+        for (win, tag) in clients {
+            list.push(client(*win, *tag));
+        }
+        assert_consistent(&list);
+        list
+    }
+
+    fn windows(list: &WClientList, tag: usize) -> Vec<Window> {
+        list.in_tag(tag).iter().map(|i| list[*i].window).collect()
+    }
+
+    // the index matches every position and each tag forms a ring in list order
+    fn assert_consistent(list: &WClientList) {
+        assert_eq!(list.index.len(), list.clients.len());
+        for (i, c) in list.iter().enumerate() {
+            assert_eq!(list.idx_of(c.window), Some(i));
+        }
+
+        for tag in 0..TAG_CAP {
+            let clients = list.in_tag(tag);
+            let n = clients.len();
+            for (i, ci) in clients.iter().enumerate() {
+                let c = &list[*ci];
+                if n == 1 {
+                    assert_eq!((c.prev, c.next), (None, None));
+                } else {
+                    assert_eq!(c.prev, Some(clients[(i + n - 1) % n]));
+                    assert_eq!(c.next, Some(clients[(i + 1) % n]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn push_links_clients_of_the_same_tag() {
+        let list = list(&[(10, 0), (11, 1), (12, 0), (13, 0)]);
+
+        assert_eq!(windows(&list, 0), vec![10, 12, 13]);
+        assert_eq!(windows(&list, 1), vec![11]);
+        assert_eq!(list[0].prev, Some(3));
+        assert_eq!(list[3].next, Some(0));
+    }
+
+    #[test]
+    fn remove_reindexes_following_clients() {
+        let mut list = list(&[(10, 0), (11, 1), (12, 0), (13, 1)]);
+
+        let removed = list.remove(0);
+
+        assert_eq!(removed.window, 10);
+        assert_eq!((removed.prev, removed.next), (None, None));
+        assert_eq!(list.idx_of(10), None);
+        assert_eq!(list.idx_of(13), Some(2));
+        assert_eq!(windows(&list, 0), vec![12]);
+        assert_eq!(windows(&list, 1), vec![11, 13]);
+        assert_consistent(&list);
+    }
+
+    #[test]
+    fn move_tag_shifts_the_tags_in_between() {
+        let mut list = list(&[(10, 0), (11, 1), (12, 2), (13, 3), (14, 0)]);
+
+        list.move_tag(0, 2);
+
+        assert_eq!(windows(&list, 0), vec![11]);
+        assert_eq!(windows(&list, 1), vec![12]);
+        assert_eq!(windows(&list, 2), vec![10, 14]);
+        assert_eq!(windows(&list, 3), vec![13]);
+        assert_consistent(&list);
+
+        list.move_tag(2, 0);
+        assert_eq!(windows(&list, 0), vec![10, 14]);
+        assert_eq!(windows(&list, 2), vec![12]);
+    }
+
+    #[test]
+    fn remove_last_client() {
+        let mut list = list(&[(10, 0)]);
+        list.remove(0);
+
+        assert!(list.clients.is_empty());
+        assert_eq!(list.idx_of(10), None);
+    }
+
+    #[test]
+    fn swap_keeps_index_and_ring() {
+        let mut list = list(&[(10, 0), (11, 1), (12, 0)]);
+
+        list.swap(0, 2);
+
+        assert_eq!(windows(&list, 0), vec![12, 10]);
+        assert_eq!(list.idx_of(12), Some(0));
+        assert_eq!(list.idx_of(10), Some(2));
+        assert_consistent(&list);
+    }
+
+    #[test]
+    fn set_tag_relinks_both_tags() {
+        let mut list = list(&[(10, 0), (11, 0), (12, 0), (13, 2)]);
+
+        list.set_tag(1, 2);
+
+        assert_eq!(windows(&list, 0), vec![10, 12]);
+        assert_eq!(windows(&list, 2), vec![11, 13]);
+        assert_consistent(&list);
+    }
+
+    #[test]
+    fn set_tag_to_empty_tag() {
+        let mut list = list(&[(10, 0), (11, 0)]);
+
+        list.set_tag(0, 3);
+
+        assert_eq!(windows(&list, 0), vec![11]);
+        assert_eq!(windows(&list, 3), vec![10]);
+        assert_consistent(&list);
+    }
+
+    #[test]
+    fn selection_stays_in_tag() {
+        let list = list(&[(10, 0), (11, 1), (12, 0), (13, 1)]);
+
+        assert_eq!(list.selection_in_tag(Some(0), 0), Some(0));
+        assert_eq!(list.selection_in_tag(Some(1), 0), Some(2));
+        assert_eq!(list.selection_in_tag(None, 1), Some(3));
+        assert_eq!(list.selection_in_tag(Some(42), 1), Some(3));
+        assert_eq!(list.selection_in_tag(Some(0), 5), None);
+    }
+}
@@ -7,26 +7,83 @@ pub enum WDirection {
 }
 
 #[derive(Debug, Clone, Copy)]
+pub enum WAxis {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum WMediaCommand {
+    VolumeUp,
+    VolumeDown,
+    ToggleMute,
+    BrightnessUp,
+    BrightnessDown,
+    PlayPause,
+    Next,
+    Prev,
+}
+
+#[derive(Debug, Clone)]
 pub enum WKeyCommand {
     Destroy,
     Exit,
     FocusClient(WDirection),
     MoveClient(WDirection),
+    Zoom,
+    FocusUrgent,
     FocusMonitor(WDirection),
     Idle,
     AdjustMainWidth(WDirection),
+    IncNMaster(WDirection),
+    AdjustClientFactor(WDirection),
+    AdjustGaps(WDirection),
     Layout(WLayout),
     SelectTag(usize),
-    Spawn(&'static [&'static str]),
+    Spawn(Vec<String>),
     MoveClientToTag(usize),
     MoveClientToMonitor(WDirection),
     UnFloat,
     Fullscreen,
+    ResizeMode,
+    ExitMode,
+    MoveFloat(WAxis, WDirection),
+    ResizeFloat(WAxis, WDirection),
+    NextKbLayout,
+    HintMode,
+    Overview,
+    ToggleTitleBars,
+    TagPreview(usize),
+    Media(WMediaCommand),
+    CycleTag(WDirection),
+    ResizePip(WDirection),
+    MovePip(WDirection),
+}
+
+impl WKeyCommand {
+    pub fn spawn(cmd: &[&str]) -> Self {
+        Self::Spawn(cmd.iter().map(|a| a.to_string()).collect())
+    }
+
+    // commands that keep being executed for as long as their key is held
+    pub fn repeats(&self) -> bool {
+        matches!(
+            self,
+            Self::AdjustMainWidth(_)
+                | Self::MoveFloat(..)
+                | Self::ResizeFloat(..)
+                | Self::ResizePip(_)
+        )
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum WMouseCommand {
     DragClient,
     ResizeClient,
+    CycleTag(WDirection),
+    AdjustMainWidth(WDirection),
+    Run(&'static [&'static str]),
+    Gesture,
     Idle,
 }
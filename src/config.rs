@@ -1,26 +1,61 @@
 pub mod theme {
     pub mod window {
+        use wwm_core::util::WLayout;
+
+        use crate::border::WBorderAccent;
+
         // border color for focused windows
         pub const BORDER_FOCUSED: u32 = 0xca9ee6;
         // border color for unfocused windows
         pub const BORDER_UNFOCUSED: u32 = 0x51576d;
         // the width of the window border
         pub const BORDER_WIDTH: u16 = 1;
+        // pixels between tiled clients and between them and the edges of the monitor
+        pub const INNER_GAP: u16 = 0;
+        pub const OUTER_GAP: u16 = 0;
+        // how many pixels `AdjustGaps` grows or shrinks both gaps by
+        pub const GAP_STEP: u16 = 2;
+        // the largest gaps the config file can set
+        pub const MAX_GAP: u16 = 100;
+        // a tiled client alone on its tag gets neither gaps nor a border
+        pub const SMART_GAPS: bool = false;
+        // tiled clients are sized to what their size hints allow, like terminals to whole
+        // character cells, and centered in their space with the rest left as extra gap
+        pub const RESPECT_SIZE_HINTS: bool = false;
+        // an inner part of the border drawn in its own colors, for a more visible focus
+        // without a wider border. e.g. with a `BORDER_WIDTH` of 3:
+        // Some(WBorderAccent { width: 1, focused: 0xe5c890, unfocused: 0x51576d })
+        pub const BORDER_ACCENT: Option<WBorderAccent> = None;
+        // layouts in which tiled clients get a title bar with their title and a close button.
+        // `ToggleTitleBars` switches them on or off for the current layout of a monitor.
+        pub const TITLE_BAR_LAYOUTS: &[WLayout] = &[];
     }
 
     pub mod bar {
-        use lazy_static::lazy_static;
-        use wwm_bar::status_module::WBarModMask;
+        use wwm_bar::{
+            status_module::{WModuleKind, WModuleSpec, WMusicSource},
+            status_worker::WStatusSource,
+        };
+        use wwm_core::text::{
+            Antialiasing, FontStyle, TextDecorations, TextRenderOptions, TextStyle,
+        };
 
         pub const TAG_WIDTH: u16 = 30;
+        // how large the preview shown when hovering a tag is compared to the monitor
+        pub const TAG_PREVIEW_SCALE: f32 = 0.15;
         // these selected colors are used for workspace tags in the bar
         pub const BG_SELECTED: u32 = 0xca9ee6;
         pub const FG_SELECTED: u32 = 0x232634;
+        // the background of tags with a client that wants attention
+        pub const BG_URGENT: u32 = 0xe78284;
         // these colors are the default fore-/background colors used across the entire bar
         pub const BG: u32 = 0x232634;
         pub const FG: u32 = 0xc6d0f5;
-        // font size in pixels
+        // font size in pixels on a 96 dpi display
         pub const FONT_SIZE: f32 = 15.0;
+        // scales the text and the bar on each monitor by how its dpi compares to 96.
+        // relies on the monitor reporting its physical size.
+        pub const SCALE_BY_DPI: bool = true;
         // padding between sections in the bar in pixels.
         // so: tags and layout indicator will have 10px between them
         pub const SECTION_PADDING: i16 = 10;
@@ -29,70 +64,196 @@ pub mod theme {
         // Only the name of the font family is required. Wwm uses fontconfig to discover
         // a monospaced font in that family and uses that for drawing text.
         pub const FONT: &str = "";
+        // the style the selected tag of the focused monitor is labeled in.
+        // the bold and italic faces of `FONT` are only loaded once something uses them.
+        pub const FOCUSED_TAG_STYLE: TextStyle = TextStyle {
+            font: FontStyle::Bold,
+            decorations: TextDecorations::NONE,
+        };
+        // switching antialiasing off gives crisper text on low dpi displays
+        pub const TEXT_RENDERING: TextRenderOptions = TextRenderOptions {
+            antialiasing: Antialiasing::Grayscale,
+            gamma: 1.0,
+        };
+        // font families that characters missing from `FONT` are looked up in, in order.
+        // they are only loaded once a character needs them. color emoji fonts
+        // (CBDT or sbix) are drawn in color.
+        pub const FALLBACK_FONTS: &[&str] = &[
+            "Noto Sans CJK JP",
+            "Noto Color Emoji",
+            "Noto Sans Symbols",
+            "DejaVu Sans",
+        ];
 
-        // the interval at which status modules update their content
+        // how often a status module from the config file is updated in milliseconds,
+        // unless it is in `MODULES` with an interval of its own
         pub const STATUS_INTERVAL: u64 = 1000;
+        // `WStatusSource::RootName` shows the name of the root window instead of the
+        // modules, for feeding the status with `xsetroot -name` or slstatus
+        pub const STATUS_SOURCE: WStatusSource = WStatusSource::Modules;
 
-        lazy_static! {
-            // bit mask representing each status module that should be enabled
-            pub static ref MODULE_MASK: WBarModMask = WBarModMask::VOL
-                | WBarModMask::RAM
-                | WBarModMask::CPU
-                | WBarModMask::DATE
-                | WBarModMask::TIME;
-        }
+        // the status modules, from left to right, and how often they are updated in
+        // milliseconds. scripts showing the first line a command prints come after them,
+        // from the `[[module]]` tables of the config file.
+        pub const MODULES: &[WModuleSpec] = &[
+            // also updated as soon as the volume changes
+            WModuleSpec::new(WModuleKind::Vol, 60_000),
+            WModuleSpec::new(WModuleKind::Ram, 5_000),
+            WModuleSpec::new(WModuleKind::Cpu, 2_000),
+            WModuleSpec::new(WModuleKind::Date, 60_000),
+            WModuleSpec::new(WModuleKind::Time, 1_000),
+        ];
+        // the temperature module is shown in `BG_URGENT` from this many degrees celsius on
+        pub const TEMP_WARNING: f32 = 80.0;
+        // the music module (`WModuleKind::Music`) asks players through mpris, or mpd at
+        // `MPD_ADDRESS`, and cuts songs off after `MUSIC_WIDTH` characters
+        pub const MUSIC_SOURCE: WMusicSource = WMusicSource::Mpris;
+        pub const MPD_ADDRESS: &str = "127.0.0.1:6600";
+        pub const MUSIC_WIDTH: usize = 40;
     }
 }
 
-#[inline]
-pub const fn bar_height() -> u16 {
-    theme::bar::FONT_SIZE as u16 + (theme::bar::PADDING * 2)
-}
-
 // configuration related to workspaces/virtual desktops/tags
 // (which are all different names referring to the same thing)
 pub mod tags {
-    // amount of workspace tags per monitor
+    // amount of workspace tags per monitor, and the most the config file can set
     // NOTE: if you change this you should also modify
     //       the keybinds below to reflect the change
     pub const TAG_CAP: usize = 9;
+    // names shown in the bar instead of the numbers, like &["web", "code", "chat"].
+    // tags without one keep their number.
+    pub const TAG_NAMES: &[&str] = &[];
     // how much of the monitor width the main client occupies in the main-stack layout
     pub const MAIN_CLIENT_WIDTH_PERCENTAGE: f32 = 0.55;
+    // the range the main client's width stays in, wherever it is set from
+    pub const WIDTH_FACTOR_RANGE: (f32, f32) = (0.05, 0.95);
+    // how many clients share the main area in the main-stack layout, stacked vertically
+    pub const MAIN_CLIENT_COUNT: usize = 1;
     // how much the main client's width is adjusted when resizing with keybinds
     pub const WIDTH_ADJUSTMENT_FACTOR: f32 = 0.02;
+    // the range the config file can set `WIDTH_ADJUSTMENT_FACTOR` in
+    pub const WIDTH_STEP_RANGE: (f32, f32) = (0.01, 0.25);
+    // how much `AdjustClientFactor` changes the share of the height the focused client
+    // gets next to the others on its side of the main-stack layout
+    pub const CLIENT_FACTOR_STEP: f32 = 0.25;
+}
+
+// configuration for the modal resize/move mode
+pub mod resize_mode {
+    // how many pixels the focused client is moved or resized per key press
+    pub const STEP: u16 = 20;
+}
+
+// configuration for hint mode, where each visible client is labeled with a key
+pub mod hints {
+    // keys used to label clients, in the order they are assigned.
+    // only lowercase ascii letters are supported.
+    pub const KEYS: &str = "asdfghjklqwertyuiopzxcvbnm";
+}
+
+// configuration for repeating commands while their key is held
+pub mod key_repeat {
+    // milliseconds a key has to be held before the command starts repeating
+    pub const DELAY: u64 = 300;
+    // milliseconds between each repeat
+    pub const INTERVAL: u64 = 40;
+}
+
+// configuration for pinging the focused client to tell when it stopped responding
+pub mod ping {
+    // milliseconds between each ping
+    pub const INTERVAL: u64 = 5000;
+    // milliseconds a client that is being closed has to answer
+    pub const TIMEOUT: u64 = 2000;
+    // shown in front of the title of a client that didn't answer, which closing again kills
+    pub const INDICATOR: &str = "[not responding]";
+}
+
+// configuration for tapping the bare modifier key twice in a row
+pub mod double_tap {
+    use x11rb::protocol::xproto::ModMask;
+
+    use crate::command::WKeyCommand;
+
+    // the modifier that has to be tapped on its own
+    pub const MOD: ModMask = ModMask::M1;
+    // milliseconds allowed between releasing the modifier the first and second time
+    pub const TIMEOUT: u64 = 300;
+    // the command run on a double tap, or `None` to disable it
+    // like `Some(WKeyCommand::HintMode)`
+    pub const ACTION: Option<WKeyCommand> = None;
 }
 
 pub mod mouse {
     use x11rb::protocol::xproto::{ButtonIndex, ModMask};
 
-    use crate::{command::WMouseCommand, mouse::WMouseBind};
+    use super::commands::{MOD, TERM_CMD};
+    use crate::{
+        command::{WDirection, WKeyCommand, WMouseCommand},
+        mouse::{WFocusModel, WGesture, WMouseBind},
+    };
+
+    const SHIFT: ModMask = ModMask::SHIFT;
+
+    // whether clients are focused when the pointer enters them or when they are clicked
+    pub const FOCUS_MODEL: WFocusModel = WFocusModel::FollowsMouse;
 
-    const MOD: ModMask = ModMask::M1;
+    // the minimum time between moves and resizes while dragging with the mouse, in milliseconds
+    pub const MOTION_INTERVAL: u32 = 1000 / 60;
 
     pub const DRAG_BUTTON: ButtonIndex = ButtonIndex::M1; // left mouse button
     pub const RESIZE_BUTTON: ButtonIndex = ButtonIndex::M3; // right mouse button
+    pub const SCROLL_UP_BUTTON: ButtonIndex = ButtonIndex::M4;
+    pub const SCROLL_DOWN_BUTTON: ButtonIndex = ButtonIndex::M5;
+    const TERM_BUTTON: ButtonIndex = ButtonIndex::M2; // middle mouse button
 
+    #[rustfmt::skip]
     pub fn setup_mousebinds() -> Vec<WMouseBind> {
         vec![
-            WMouseBind::new(MOD, DRAG_BUTTON, WMouseCommand::DragClient),
-            WMouseBind::new(MOD, RESIZE_BUTTON, WMouseCommand::ResizeClient),
+            WMouseBind::new(MOD,         DRAG_BUTTON,        WMouseCommand::DragClient),
+            WMouseBind::new(MOD,         RESIZE_BUTTON,      WMouseCommand::ResizeClient),
+            WMouseBind::new(MOD,         SCROLL_UP_BUTTON,   WMouseCommand::CycleTag(WDirection::Prev)),
+            WMouseBind::new(MOD,         SCROLL_DOWN_BUTTON, WMouseCommand::CycleTag(WDirection::Next)),
+            WMouseBind::new(MOD | SHIFT, SCROLL_UP_BUTTON,   WMouseCommand::AdjustMainWidth(WDirection::Next)),
+            WMouseBind::new(MOD | SHIFT, SCROLL_DOWN_BUTTON, WMouseCommand::AdjustMainWidth(WDirection::Prev)),
+            // BEGIN: root window only mousebinds
+            WMouseBind::new_root(MOD,    TERM_BUTTON,        WMouseCommand::Run(TERM_CMD)),
+            WMouseBind::new_root(MOD,    DRAG_BUTTON,        WMouseCommand::Gesture),
+            // END: root window only mousebinds
+        ]
+    }
+
+    // how many pixels the pointer has to travel for a drag to count as a gesture
+    pub const GESTURE_THRESHOLD: i16 = 100;
+
+    // commands run by dragging on empty root window space with the gesture mousebind
+    #[rustfmt::skip]
+    pub fn setup_gestures() -> Vec<(WGesture, WKeyCommand)> {
+        vec![
+            (WGesture::Left,  WKeyCommand::CycleTag(WDirection::Prev)),
+            (WGesture::Right, WKeyCommand::CycleTag(WDirection::Next)),
+            (WGesture::Up,    WKeyCommand::FocusMonitor(WDirection::Prev)),
+            (WGesture::Down,  WKeyCommand::FocusMonitor(WDirection::Next)),
         ]
     }
 }
 
 pub mod commands {
-    use crate::command::{WDirection, WKeyCommand};
-    use crate::keyboard::keybind::WKeybind;
+    use crate::command::{WAxis, WDirection, WKeyCommand, WMediaCommand};
+    use crate::keyboard::keybind::{WKeybind, WKeybindContext};
     use wwm_core::util::WLayout;
     use x11rb::protocol::xproto::ModMask;
     use xkbcommon::xkb::keysyms as ks;
 
-    const MOD: ModMask = ModMask::M1;
+    // the modifier of most keybinds and mousebinds, and `Mod` in the keybinds of the
+    // config file. the `modkey` setting of the config file swaps it for another one.
+    pub const MOD: ModMask = ModMask::M1;
     const SHIFT: ModMask = ModMask::SHIFT;
+    const CTRL: ModMask = ModMask::CONTROL;
     const NONE: u16 = 0;
 
     // spawn commands
-    static TERM_CMD: &[&str] = &["alacritty"];
+    pub static TERM_CMD: &[&str] = &["alacritty"];
     static CHATTERINO_CMD: &[&str] = &["chatterino"];
     static FLAMESHOT_CMD: &[&str] = &["flameshot", "gui"];
     static STEAM_CMD: &[&str] = &["steam"];
@@ -101,18 +262,37 @@ pub mod commands {
     #[rustfmt::skip]
     pub fn setup_keybinds() -> Vec<WKeybind> {
         vec![
-            WKeybind::new(MOD | SHIFT, ks::KEY_Return, WKeyCommand::Spawn(TERM_CMD)),
-            WKeybind::new(MOD,         ks::KEY_c,      WKeyCommand::Spawn(CHATTERINO_CMD)),
-            WKeybind::new(NONE,        ks::KEY_Print,  WKeyCommand::Spawn(FLAMESHOT_CMD)),
-            WKeybind::new(MOD,         ks::KEY_s,      WKeyCommand::Spawn(STEAM_CMD)),
-            WKeybind::new(MOD,         ks::KEY_p,      WKeyCommand::Spawn(ROFI_CMD)),
+            WKeybind::new(MOD | SHIFT, ks::KEY_Return, WKeyCommand::spawn(TERM_CMD)),
+            WKeybind::new(MOD,         ks::KEY_c,      WKeyCommand::spawn(CHATTERINO_CMD)),
+            WKeybind::new(NONE,        ks::KEY_Print,  WKeyCommand::spawn(FLAMESHOT_CMD)),
+            WKeybind::new(MOD,         ks::KEY_s,      WKeyCommand::spawn(STEAM_CMD)),
+            WKeybind::new(MOD,         ks::KEY_p,      WKeyCommand::spawn(ROFI_CMD)),
             WKeybind::new(MOD | SHIFT, ks::KEY_k,      WKeyCommand::MoveClient(WDirection::Prev)),
             WKeybind::new(MOD | SHIFT, ks::KEY_j,      WKeyCommand::MoveClient(WDirection::Next)),
+            WKeybind::new(MOD,         ks::KEY_Return, WKeyCommand::Zoom),
+            WKeybind::new(MOD,         ks::KEY_u,      WKeyCommand::FocusUrgent),
             WKeybind::new(MOD | SHIFT, ks::KEY_q,      WKeyCommand::Destroy),
             WKeybind::new(MOD | SHIFT, ks::KEY_h,      WKeyCommand::AdjustMainWidth(WDirection::Prev)),
             WKeybind::new(MOD | SHIFT, ks::KEY_l,      WKeyCommand::AdjustMainWidth(WDirection::Next)),
+            WKeybind::new(MOD,         ks::KEY_i,      WKeyCommand::IncNMaster(WDirection::Next)),
+            WKeybind::new(MOD,         ks::KEY_d,      WKeyCommand::IncNMaster(WDirection::Prev)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_k,      WKeyCommand::AdjustGaps(WDirection::Next)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_j,      WKeyCommand::AdjustGaps(WDirection::Prev)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_h,      WKeyCommand::AdjustClientFactor(WDirection::Prev)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_l,      WKeyCommand::AdjustClientFactor(WDirection::Next)),
+            // BEGIN: context keybinds
+            WKeybind::new(MOD | SHIFT, ks::KEY_h,      WKeyCommand::MoveFloat(WAxis::Horizontal, WDirection::Prev)).when(WKeybindContext::Floating),
+            WKeybind::new(MOD | SHIFT, ks::KEY_l,      WKeyCommand::MoveFloat(WAxis::Horizontal, WDirection::Next)).when(WKeybindContext::Floating),
+            // columns are laid out horizontally so moving clients left/right makes more sense there.
+            // size factors have no effect in the column layout, which leaves their keys free.
+            WKeybind::new(MOD | CTRL,  ks::KEY_h,      WKeyCommand::MoveClient(WDirection::Prev)).when(WKeybindContext::Layout(WLayout::Column)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_l,      WKeyCommand::MoveClient(WDirection::Next)).when(WKeybindContext::Layout(WLayout::Column)),
+            // END: context keybinds
             WKeybind::new(MOD | SHIFT, ks::KEY_t,      WKeyCommand::Layout(WLayout::MainStack)),
+            WKeybind::new(MOD | SHIFT, ks::KEY_b,      WKeyCommand::Layout(WLayout::BottomStack)),
             WKeybind::new(MOD | SHIFT, ks::KEY_c,      WKeyCommand::Layout(WLayout::Column)),
+            WKeybind::new(MOD | SHIFT, ks::KEY_w,      WKeyCommand::Layout(WLayout::Tabbed)),
+            WKeybind::new(MOD | SHIFT, ks::KEY_f,      WKeyCommand::Layout(WLayout::Floating)),
             WKeybind::new(MOD | SHIFT, ks::KEY_comma,  WKeyCommand::MoveClientToMonitor(WDirection::Prev)),
             WKeybind::new(MOD | SHIFT, ks::KEY_period, WKeyCommand::MoveClientToMonitor(WDirection::Next)),
             WKeybind::new(MOD,         ks::KEY_j,      WKeyCommand::FocusClient(WDirection::Next)),
@@ -121,7 +301,25 @@ pub mod commands {
             WKeybind::new(MOD,         ks::KEY_l,      WKeyCommand::FocusMonitor(WDirection::Next)),
             WKeybind::new(MOD | SHIFT, ks::KEY_space,  WKeyCommand::UnFloat),
             WKeybind::new(MOD,         ks::KEY_F11,    WKeyCommand::Fullscreen),
+            WKeybind::new(MOD,         ks::KEY_r,      WKeyCommand::ResizeMode),
+            WKeybind::new(MOD,         ks::KEY_space,  WKeyCommand::NextKbLayout),
+            WKeybind::new(MOD,         ks::KEY_f,      WKeyCommand::HintMode),
+            WKeybind::new(MOD,         ks::KEY_Tab,    WKeyCommand::Overview),
+            WKeybind::new(MOD,         ks::KEY_t,      WKeyCommand::ToggleTitleBars),
+            WKeybind::new(MOD | CTRL,  ks::KEY_minus,  WKeyCommand::ResizePip(WDirection::Prev)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_equal,  WKeyCommand::ResizePip(WDirection::Next)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_p,      WKeyCommand::MovePip(WDirection::Next)),
             WKeybind::new(MOD,         ks::KEY_q,      WKeyCommand::Exit),
+            // BEGIN: media keybinds
+            WKeybind::new(NONE,        ks::KEY_XF86AudioRaiseVolume,  WKeyCommand::Media(WMediaCommand::VolumeUp)),
+            WKeybind::new(NONE,        ks::KEY_XF86AudioLowerVolume,  WKeyCommand::Media(WMediaCommand::VolumeDown)),
+            WKeybind::new(NONE,        ks::KEY_XF86AudioMute,         WKeyCommand::Media(WMediaCommand::ToggleMute)),
+            WKeybind::new(NONE,        ks::KEY_XF86MonBrightnessUp,   WKeyCommand::Media(WMediaCommand::BrightnessUp)),
+            WKeybind::new(NONE,        ks::KEY_XF86MonBrightnessDown, WKeyCommand::Media(WMediaCommand::BrightnessDown)),
+            WKeybind::new(NONE,        ks::KEY_XF86AudioPlay,         WKeyCommand::Media(WMediaCommand::PlayPause)),
+            WKeybind::new(NONE,        ks::KEY_XF86AudioNext,         WKeyCommand::Media(WMediaCommand::Next)),
+            WKeybind::new(NONE,        ks::KEY_XF86AudioPrev,         WKeyCommand::Media(WMediaCommand::Prev)),
+            // END: media keybinds
             // BEGIN: tag keybinds
             WKeybind::new(MOD,         ks::KEY_1,      WKeyCommand::SelectTag(0)),
             WKeybind::new(MOD,         ks::KEY_2,      WKeyCommand::SelectTag(1)),
@@ -141,14 +339,118 @@ pub mod commands {
             WKeybind::new(MOD | SHIFT, ks::KEY_7,      WKeyCommand::MoveClientToTag(6)),
             WKeybind::new(MOD | SHIFT, ks::KEY_8,      WKeyCommand::MoveClientToTag(7)),
             WKeybind::new(MOD | SHIFT, ks::KEY_9,      WKeyCommand::MoveClientToTag(8)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_1,      WKeyCommand::TagPreview(0)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_2,      WKeyCommand::TagPreview(1)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_3,      WKeyCommand::TagPreview(2)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_4,      WKeyCommand::TagPreview(3)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_5,      WKeyCommand::TagPreview(4)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_6,      WKeyCommand::TagPreview(5)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_7,      WKeyCommand::TagPreview(6)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_8,      WKeyCommand::TagPreview(7)),
+            WKeybind::new(MOD | CTRL,  ks::KEY_9,      WKeyCommand::TagPreview(8)),
             // END: tag keybinds
         ]
     }
+
+    // keybinds that are active while in resize mode.
+    // the keyboard is grabbed in this mode so these do not need a modifier.
+    #[rustfmt::skip]
+    pub fn setup_resize_mode_keybinds() -> Vec<WKeybind> {
+        vec![
+            WKeybind::new(NONE,  ks::KEY_h,      WKeyCommand::ResizeFloat(WAxis::Horizontal, WDirection::Prev)),
+            WKeybind::new(NONE,  ks::KEY_l,      WKeyCommand::ResizeFloat(WAxis::Horizontal, WDirection::Next)),
+            WKeybind::new(NONE,  ks::KEY_k,      WKeyCommand::ResizeFloat(WAxis::Vertical, WDirection::Prev)),
+            WKeybind::new(NONE,  ks::KEY_j,      WKeyCommand::ResizeFloat(WAxis::Vertical, WDirection::Next)),
+            WKeybind::new(SHIFT, ks::KEY_h,      WKeyCommand::MoveFloat(WAxis::Horizontal, WDirection::Prev)),
+            WKeybind::new(SHIFT, ks::KEY_l,      WKeyCommand::MoveFloat(WAxis::Horizontal, WDirection::Next)),
+            WKeybind::new(SHIFT, ks::KEY_k,      WKeyCommand::MoveFloat(WAxis::Vertical, WDirection::Prev)),
+            WKeybind::new(SHIFT, ks::KEY_j,      WKeyCommand::MoveFloat(WAxis::Vertical, WDirection::Next)),
+            WKeybind::new(NONE,  ks::KEY_Escape, WKeyCommand::ExitMode),
+        ]
+    }
+}
+
+// commands run by the media keys.
+// the bar status is refreshed as soon as a volume or brightness command finishes.
+pub mod media {
+    pub static VOLUME_UP_CMD: &[&str] = &["amixer", "-q", "sset", "Master", "5%+"];
+    pub static VOLUME_DOWN_CMD: &[&str] = &["amixer", "-q", "sset", "Master", "5%-"];
+    pub static TOGGLE_MUTE_CMD: &[&str] = &["amixer", "-q", "sset", "Master", "toggle"];
+    pub static BRIGHTNESS_UP_CMD: &[&str] = &["brightnessctl", "-q", "set", "+5%"];
+    pub static BRIGHTNESS_DOWN_CMD: &[&str] = &["brightnessctl", "-q", "set", "5%-"];
+    pub static PLAY_PAUSE_CMD: &[&str] = &["playerctl", "play-pause"];
+    pub static NEXT_CMD: &[&str] = &["playerctl", "next"];
+    pub static PREV_CMD: &[&str] = &["playerctl", "previous"];
+}
+
+// which newly mapped clients take the focus. those that don't are marked urgent,
+// which shows on their tag in the bar until they are focused.
+pub mod focus {
+    use crate::client::WFocusPolicy;
+
+    pub const POLICY: WFocusPolicy = WFocusPolicy::Always;
+    // policies for the clients of a class, taking precedence over `POLICY`
+    pub static RULES: &[(&str, WFocusPolicy)] = &[
+        // its update and login windows pop up while doing something else
+        // ("steam", WFocusPolicy::Never),
+        // ("firefox", WFocusPolicy::VisibleTag),
+    ];
+}
+
+// picture-in-picture videos, which float without a border in a corner of the monitor
+// and stay there whichever tag is shown
+pub mod pip {
+    use crate::pip::WCorner;
+
+    // `(class, title)` pairs of the windows treated as picture-in-picture.
+    // the class has to match and the title has to contain the given one.
+    pub static RULES: &[(&str, &str)] = &[("firefox", "Picture-in-Picture")];
+    pub const CORNER: WCorner = WCorner::BottomRight;
+    // the initial width as a part of the monitor width, the height keeps the aspect ratio
+    pub const WIDTH: f32 = 0.25;
+    // how much the width changes with the resize keybinds, as a part of the monitor width
+    pub const STEP: f32 = 0.02;
+    // pixels kept between the client and the edges of the monitor
+    pub const MARGIN: u16 = 20;
+}
+
+// the on-screen display shown in the middle of the monitor after switching tags or layouts
+// and changing the volume or brightness
+pub mod osd {
+    pub const ENABLED: bool = true;
+    // milliseconds the osd is shown before it starts fading out
+    pub const TIMEOUT: u64 = 1000;
+    // milliseconds the fade out takes
+    pub const FADE: u64 = 300;
+    // commands printing the current volume and brightness
+    #[rustfmt::skip]
+    pub static VOLUME_CMD: &[&str] = &["sh", "-c", "amixer sget Master | awk -F '[][]' '/Left:/ { print ($4 == \"off\" ? \"muted\" : $2); exit }'"];
+    pub static BRIGHTNESS_CMD: &[&str] = &["sh", "-c", "brightnessctl -m | cut -d, -f4"];
+}
+
+// actions taken once the keyboard and pointer have been left alone for a while
+pub mod idle {
+    use crate::idle::WIdleAction;
+
+    // seconds without input after which each action is taken, in ascending order
+    pub fn actions() -> Vec<(u64, WIdleAction)> {
+        vec![
+            // (300, WIdleAction::Run(vec!["slock".into()])),
+            // (600, WIdleAction::DpmsOff),
+        ]
+    }
+    // whether a fullscreen client on a shown tag, like a video or a game, holds off
+    // every action and keeps the screen on
+    pub const INHIBIT_FULLSCREEN: bool = true;
 }
 
 pub mod auto_start {
+    use crate::supervisor::WRestart;
+
+    // programs started along with the wm. daemons like a compositor can be restarted
+    // when they exit with `WRestart::OnFailure` or `WRestart::Always`.
     #[rustfmt::skip]
-    pub static AUTO_START_COMMANDS: &[&[&str]] = &[
-        &["feh", "--bg-scale", "/usr/share/wwm/wallpaper.png"]
+    pub static AUTO_START_COMMANDS: &[(&[&str], WRestart)] = &[
+        (&["feh", "--bg-scale", "/usr/share/wwm/wallpaper.png"], WRestart::Never),
     ];
 }
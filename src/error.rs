@@ -0,0 +1,23 @@
+use std::io;
+
+use thiserror::Error;
+use wwm_core::text::FontError;
+use x11rb::rust_connection::{ConnectionError, ReplyError, ReplyOrIdError};
+
+use crate::monitor::StateError;
+
+#[derive(Error, Debug)]
+pub enum WwmError {
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+    #[error("Reply error: {0}")]
+    Reply(#[from] ReplyError),
+    #[error("Reply or ID error: {0}")]
+    ReplyOrId(#[from] ReplyOrIdError),
+    #[error("Font error: {0}")]
+    Font(#[from] FontError),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("State error: {0}")]
+    State(#[from] StateError),
+}
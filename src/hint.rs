@@ -0,0 +1,9 @@
+use wwm_core::overlay::WOverlay;
+
+// a letter drawn on top of a visible client in hint mode
+pub struct WHint {
+    pub key: char,
+    pub overlay: WOverlay,
+    pub mon_idx: usize,
+    pub client_idx: usize,
+}
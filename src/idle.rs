@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WIdleAction {
+    // runs a program, like a screen locker
+    Run(Vec<String>),
+    // turns the monitors off until the next input
+    DpmsOff,
+}
+
+// keeps track of which idle actions were taken since the last input
+#[derive(Default)]
+pub struct WIdle {
+    taken: usize,
+    last_idle_ms: u64,
+}
+
+impl WIdle {
+    // takes how long there has been no input and returns the actions that became due
+    // since the last update along with how long to wait before the next one.
+    // `actions` holds the timeout of each action in seconds, in ascending order.
+    pub fn update(
+        &mut self,
+        idle_ms: u64,
+        actions: &[(u64, WIdleAction)],
+    ) -> (Vec<WIdleAction>, Option<Duration>) {
+        // there was input since the last update
+        if idle_ms < self.last_idle_ms {
+            self.taken = 0;
+        }
+        self.last_idle_ms = idle_ms;
+
+        let due_count = actions
+            .iter()
+            .take_while(|(secs, _)| secs * 1000 <= idle_ms)
+            .count();
+        let due = actions[self.taken.min(due_count)..due_count]
+            .iter()
+            .map(|(_, a)| a.clone())
+            .collect();
+        self.taken = self.taken.max(due_count);
+
+        // the next action is due once the rest of its timeout has passed. input after
+        // the first one was taken only shows up as less idle time when it is checked for
+        // before the first timeout could pass again, so it is never waited on for longer.
+        let until_next = actions
+            .get(self.taken)
+            .map(|(secs, _)| secs * 1000 - idle_ms);
+        let first = actions.first().map(|(secs, _)| (secs * 1000).max(1000));
+        let next = until_next.into_iter().chain(first).min();
+        (due, next.map(Duration::from_millis))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slock() -> WIdleAction {
+        WIdleAction::Run(vec!["slock".into()])
+    }
+
+    fn actions() -> Vec<(u64, WIdleAction)> {
+        vec![(60, slock()), (120, WIdleAction::DpmsOff)]
+    }
+
+    #[test]
+    fn actions_are_taken_once_each() {
+        let mut idle = WIdle::default();
+        assert_eq!(
+            idle.update(10_000, &actions()),
+            (vec![], Some(Duration::from_secs(50)))
+        );
+        assert_eq!(
+            idle.update(130_000, &actions()),
+            (
+                vec![slock(), WIdleAction::DpmsOff],
+                Some(Duration::from_secs(60))
+            )
+        );
+        assert_eq!(idle.update(190_000, &actions()).0, vec![]);
+    }
+
+    #[test]
+    fn input_starts_over() {
+        let mut idle = WIdle::default();
+        idle.update(60_000, &actions());
+        assert_eq!(
+            idle.update(5_000, &actions()),
+            (vec![], Some(Duration::from_secs(55)))
+        );
+        assert_eq!(idle.update(60_000, &actions()).0, vec![slock()]);
+    }
+
+    #[test]
+    fn input_between_sparse_checks_is_noticed() {
+        let mut idle = WIdle::default();
+        // the next check comes before a second lock could be due, not at the dpms timeout
+        assert_eq!(
+            idle.update(60_000, &actions()),
+            (vec![slock()], Some(Duration::from_secs(60)))
+        );
+        // the screen was unlocked 10 seconds after the lock
+        assert_eq!(
+            idle.update(50_000, &actions()),
+            (vec![], Some(Duration::from_secs(10)))
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use serde_json::{json, Value};
+use wwm_core::util::WLayout;
+use x11rb::protocol::xproto::Window;
+
+// what a client can subscribe to with `{"subscribe": ["focus", "tag"]}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WIpcEvent {
+    Focus,
+    Tag,
+    Client,
+    Layout,
+    Title,
+}
+
+impl WIpcEvent {
+    pub const ALL: [Self; 5] = [
+        Self::Focus,
+        Self::Tag,
+        Self::Client,
+        Self::Layout,
+        Self::Title,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Focus => "focus",
+            Self::Tag => "tag",
+            Self::Client => "client",
+            Self::Layout => "layout",
+            Self::Title => "title",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| e.name() == name)
+    }
+}
+
+// the focused window changed, where no window means only the root is focused
+pub fn focus(window: Option<Window>, title: &str) -> (WIpcEvent, Value) {
+    let event = json!({ "event": "focus", "window": window, "title": title });
+    (WIpcEvent::Focus, event)
+}
+
+pub fn tag(monitor: usize, tag: usize, name: &str) -> (WIpcEvent, Value) {
+    let event = json!({ "event": "tag", "monitor": monitor, "tag": tag, "name": name });
+    (WIpcEvent::Tag, event)
+}
+
+// `change` is either "mapped" or "unmapped"
+pub fn client(change: &str, window: Window) -> (WIpcEvent, Value) {
+    let event = json!({ "event": "client", "change": change, "window": window });
+    (WIpcEvent::Client, event)
+}
+
+pub fn layout(monitor: usize, layout: WLayout) -> (WIpcEvent, Value) {
+    let event = json!({ "event": "layout", "monitor": monitor, "layout": layout.name() });
+    (WIpcEvent::Layout, event)
+}
+
+pub fn title(window: Window, title: &str) -> (WIpcEvent, Value) {
+    let event = json!({ "event": "title", "window": window, "title": title });
+    (WIpcEvent::Title, event)
+}
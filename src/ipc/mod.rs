@@ -0,0 +1,176 @@
+pub mod event;
+pub mod protocol;
+pub mod query;
+
+use std::{
+    fs,
+    io::{self, Read, Write},
+    os::unix::{
+        fs::MetadataExt,
+        io::{AsRawFd, RawFd},
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+};
+
+use serde_json::Value;
+use wwm_core::util::ipc::socket_path;
+
+use self::{event::WIpcEvent, protocol::WIpcRequest};
+
+// a line longer than this is never going to be a request
+const MAX_LINE: usize = 64 * 1024;
+
+struct WIpcClient {
+    id: u64,
+    stream: UnixStream,
+    buf: Vec<u8>,
+    // the events pushed to it
+    subscriptions: Vec<WIpcEvent>,
+    // hung up or broke the protocol. it is dropped once its requests are answered.
+    closed: bool,
+}
+
+impl WIpcClient {
+    // reads everything there is, returning whether the client is still there
+    fn read(&mut self) -> bool {
+        let mut chunk = [0u8; 4096];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return false,
+                Ok(n) => self.buf.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return true,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => return false,
+            }
+            if self.buf.len() > MAX_LINE && !self.buf.contains(&b'\n') {
+                return false;
+            }
+        }
+    }
+
+    fn send(&mut self, value: &Value) {
+        let mut line = value.to_string();
+        line.push('\n');
+        // a client that doesn't read what is sent to it isn't waited for
+        if self.stream.write_all(line.as_bytes()).is_err() {
+            self.closed = true;
+        }
+    }
+
+    fn next_line(&mut self) -> Option<String> {
+        loop {
+            let end = self.buf.iter().position(|b| *b == b'\n')?;
+            let line: Vec<u8> = self.buf.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line).trim().to_string();
+            if !line.is_empty() {
+                return Some(line);
+            }
+        }
+    }
+}
+
+// scripts and tools like `wwm-msg` talk to us through a unix socket, with one json
+// object on each line both ways
+pub struct WIpcServer {
+    path: PathBuf,
+    // tells our socket apart from one created by a wwm that replaced us
+    inode: u64,
+    listener: UnixListener,
+    clients: Vec<WIpcClient>,
+    next_id: u64,
+    // the last event sent of each kind, so that the same one isn't sent twice in a row
+    last: Vec<(WIpcEvent, Value)>,
+}
+
+impl WIpcServer {
+    pub fn bind() -> io::Result<Self> {
+        let path = socket_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "$XDG_RUNTIME_DIR isn't set"))?;
+        // either left behind or belonging to the wm we are replacing on this display
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            inode: fs::metadata(&path)?.ino(),
+            path,
+            listener,
+            clients: Vec::new(),
+            next_id: 0,
+            last: Vec::new(),
+        })
+    }
+
+    // the listening socket followed by every client
+    pub fn fds(&self) -> Vec<RawFd> {
+        let clients = self.clients.iter().map(|c| c.stream.as_raw_fd());
+        [self.listener.as_raw_fd()]
+            .into_iter()
+            .chain(clients)
+            .collect()
+    }
+
+    // accepts new clients and returns the requests that came in along with the id of the
+    // client to reply to
+    pub fn take_requests(&mut self) -> Vec<(u64, Result<WIpcRequest, String>)> {
+        self.clients.retain(|c| !c.closed);
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.clients.push(WIpcClient {
+                    id: self.next_id,
+                    stream,
+                    buf: Vec::new(),
+                    subscriptions: Vec::new(),
+                    closed: false,
+                });
+                self.next_id += 1;
+            }
+        }
+
+        let mut requests = Vec::new();
+        for client in &mut self.clients {
+            client.closed = !client.read();
+            while let Some(line) = client.next_line() {
+                requests.push((client.id, protocol::parse(&line)));
+            }
+        }
+        requests
+    }
+
+    pub fn reply(&mut self, id: u64, response: &Value) {
+        if let Some(client) = self.clients.iter_mut().find(|c| c.id == id) {
+            client.send(response);
+        }
+    }
+
+    pub fn subscribe(&mut self, id: u64, events: Vec<WIpcEvent>) {
+        if let Some(client) = self.clients.iter_mut().find(|c| c.id == id) {
+            client.subscriptions.extend(events);
+        }
+    }
+
+    // pushes the event to the clients subscribed to its kind
+    pub fn broadcast(&mut self, kind: WIpcEvent, event: Value) {
+        match self.last.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, last)) if *last == event => return,
+            Some((_, last)) => *last = event.clone(),
+            None => self.last.push((kind, event.clone())),
+        }
+        for client in &mut self.clients {
+            if !client.closed && client.subscriptions.contains(&kind) {
+                client.send(&event);
+            }
+        }
+    }
+}
+
+impl Drop for WIpcServer {
+    fn drop(&mut self) {
+        if fs::metadata(&self.path).is_ok_and(|m| m.ino() == self.inode) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
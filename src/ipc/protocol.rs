@@ -0,0 +1,135 @@
+use serde_json::{json, Value};
+
+use super::{event::WIpcEvent, query::WIpcQuery};
+use crate::{command::WKeyCommand, settings::keybind};
+
+// raised when a request or a reply changes in a way that older clients can't handle
+pub const VERSION: u32 = 1;
+
+// what a client asks for, as one json object on a line
+pub enum WIpcRequest {
+    // `{"command": "select-tag", "args": ["3"]}`, where the arguments can also follow
+    // the name as in `{"command": "select-tag 3"}`. the commands are the ones of the
+    // keybinds in the config file.
+    Command(WKeyCommand),
+    // `{"query": "clients"}`, answered with the state as `data`
+    Query(WIpcQuery),
+    // `{"subscribe": ["focus", "tag"]}`, after which the events are pushed to the client
+    // as they happen, one on each line
+    Subscribe(Vec<WIpcEvent>),
+}
+
+pub fn parse(line: &str) -> Result<WIpcRequest, String> {
+    let request: Value = serde_json::from_str(line).map_err(|e| format!("invalid json: {e}"))?;
+    if let Some(query) = request.get("query") {
+        let query = query.as_str().unwrap_or_default();
+        return WIpcQuery::from_name(query)
+            .map(WIpcRequest::Query)
+            .ok_or_else(|| format!("unknown query `{query}`"));
+    }
+    if let Some(events) = request.get("subscribe") {
+        return subscriptions(events).map(WIpcRequest::Subscribe);
+    }
+    let words = command_words(&request)?;
+    let args: Vec<&str> = words[1..].iter().map(String::as_str).collect();
+    keybind::command(&words[0], &args)
+        .map(WIpcRequest::Command)
+        .ok_or_else(|| format!("unknown command `{}`", words.join(" ")))
+}
+
+// the name of the requested command followed by its arguments
+fn command_words(request: &Value) -> Result<Vec<String>, String> {
+    let Some(command) = request.get("command").and_then(Value::as_str) else {
+        return Err("expected a `command`".into());
+    };
+    let mut words: Vec<String> = command.split_whitespace().map(String::from).collect();
+    match request.get("args") {
+        None => {}
+        Some(Value::Array(args)) => {
+            for arg in args {
+                words.push(match arg {
+                    Value::String(s) => s.clone(),
+                    Value::Number(n) => n.to_string(),
+                    _ => return Err("`args` should be strings".into()),
+                });
+            }
+        }
+        Some(_) => return Err("`args` should be an array".into()),
+    }
+    if words.is_empty() {
+        return Err("the command is empty".into());
+    }
+    Ok(words)
+}
+
+fn subscriptions(events: &Value) -> Result<Vec<WIpcEvent>, String> {
+    let Some(events) = events.as_array() else {
+        return Err("`subscribe` should be an array".into());
+    };
+    events
+        .iter()
+        .map(|e| {
+            let name = e.as_str().unwrap_or_default();
+            WIpcEvent::from_name(name).ok_or_else(|| format!("unknown event `{name}`"))
+        })
+        .collect()
+}
+
+pub fn success() -> Value {
+    json!({ "success": true })
+}
+
+pub fn data(data: Value) -> Value {
+    json!({ "success": true, "data": data })
+}
+
+pub fn failure(error: &str) -> Value {
+    json!({ "success": false, "error": error })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &str) -> Result<Vec<String>, String> {
+        command_words(&serde_json::from_str(line).unwrap())
+    }
+
+    #[test]
+    fn arguments_follow_the_name_or_come_separately() {
+        let tag = words(r#"{"command": "select-tag 3"}"#).unwrap();
+        assert_eq!(tag, ["select-tag", "3"]);
+        let spawn = words(r#"{"command": "spawn", "args": ["st", "-e", "a b"]}"#);
+        assert_eq!(spawn.unwrap(), ["spawn", "st", "-e", "a b"]);
+        let tag = words(r#"{"command": "select-tag", "args": [3]}"#).unwrap();
+        assert_eq!(tag, ["select-tag", "3"]);
+    }
+
+    #[test]
+    fn malformed_requests() {
+        assert!(parse("select-tag 3").is_err());
+        assert!(words(r#"{"cmd": "exit"}"#).is_err());
+        assert!(words(r#"{"command": " "}"#).is_err());
+        assert!(words(r#"{"command": "spawn", "args": "st"}"#).is_err());
+    }
+
+    #[test]
+    fn queries() {
+        let query = parse(r#"{"query": "clients"}"#);
+        assert!(matches!(query, Ok(WIpcRequest::Query(WIpcQuery::Clients))));
+        let query = parse(r#"{"query": "version"}"#);
+        assert!(matches!(query, Ok(WIpcRequest::Query(WIpcQuery::Version))));
+        assert!(parse(r#"{"query": "windows"}"#).is_err());
+    }
+
+    #[test]
+    fn subscriptions() {
+        let events = parse(r#"{"subscribe": ["focus", "title"]}"#);
+        let Ok(WIpcRequest::Subscribe(events)) = events else {
+            panic!("expected a subscription");
+        };
+        assert_eq!(events, [WIpcEvent::Focus, WIpcEvent::Title]);
+        assert!(parse(r#"{"subscribe": ["focus", "urgent"]}"#).is_err());
+        assert!(parse(r#"{"subscribe": "focus"}"#).is_err());
+    }
+}
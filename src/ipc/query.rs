@@ -0,0 +1,95 @@
+use serde_json::{json, Value};
+use wwm_core::util::primitives::WRect;
+use x11rb::connection::Connection;
+
+use super::{event::WIpcEvent, protocol};
+use crate::{
+    client::WClientState,
+    monitor::WMonitor,
+    settings::{keybind, WConfig},
+};
+
+// the parts of the state a `{"query": ...}` request can ask for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WIpcQuery {
+    Monitors,
+    Tags,
+    Clients,
+    // what this wwm understands, for scripts to check before relying on it
+    Version,
+}
+
+impl WIpcQuery {
+    pub const ALL: [Self; 4] = [Self::Monitors, Self::Tags, Self::Clients, Self::Version];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Monitors => "monitors",
+            Self::Tags => "tags",
+            Self::Clients => "clients",
+            Self::Version => "version",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|q| q.name() == name)
+    }
+}
+
+pub fn version() -> Value {
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "protocol": protocol::VERSION,
+        "commands": keybind::COMMANDS,
+        "queries": WIpcQuery::ALL.map(WIpcQuery::name),
+        "events": WIpcEvent::ALL.map(WIpcEvent::name),
+    })
+}
+
+pub fn monitor<C: Connection>(m: &WMonitor<C>, idx: usize, focused: bool) -> Value {
+    json!({
+        "index": idx,
+        "rect": rect(m.rect),
+        "bar": rect(m.bar.rect()),
+        "primary": m.primary,
+        "focused": focused,
+        "tag": m.tag,
+        "layout": m.layout.name(),
+    })
+}
+
+// every tag of the monitor
+pub fn tags<C: Connection>(m: &WMonitor<C>, idx: usize, config: &WConfig) -> Vec<Value> {
+    (0..m.tag_count)
+        .map(|tag| {
+            let mut clients = m.clients.iter().filter(|c| c.tag == tag);
+            json!({
+                "monitor": idx,
+                "index": tag,
+                "name": config.tag_name(tag),
+                "selected": m.tag == tag,
+                "occupied": clients.clone().next().is_some(),
+                "urgent": clients.any(|c| c.is_urgent),
+            })
+        })
+        .collect()
+}
+
+pub fn client(c: &WClientState, monitor: usize, focused: bool) -> Value {
+    json!({
+        "window": c.window,
+        "title": c.title,
+        "class": c.class,
+        "monitor": monitor,
+        "tag": c.tag,
+        "rect": rect(c.rect),
+        "floating": c.is_floating,
+        "fullscreen": c.is_fullscreen,
+        "urgent": c.is_urgent,
+        "focused": focused,
+    })
+}
+
+fn rect(r: WRect) -> Value {
+    json!({ "x": r.x, "y": r.y, "width": r.w, "height": r.h })
+}
@@ -1,12 +1,23 @@
+use wwm_core::util::WLayout;
 use x11rb::protocol::xproto::{KeyButMask, ModMask};
 
 use crate::command::WKeyCommand;
 
-#[derive(Debug)]
+// restricts when a keybind is active. binds with a context take precedence
+// over unrestricted binds on the same key.
+#[derive(Debug, Clone, Copy)]
+pub enum WKeybindContext {
+    Always,
+    Layout(WLayout),
+    Floating,
+}
+
+#[derive(Debug, Clone)]
 pub struct WKeybind {
     pub mods: ModMask,
     pub keysym: u32,
     pub action: WKeyCommand,
+    pub context: WKeybindContext,
 }
 
 impl WKeybind {
@@ -15,9 +26,15 @@ impl WKeybind {
             mods: mods.into(),
             keysym,
             action,
+            context: WKeybindContext::Always,
         }
     }
 
+    pub fn when(mut self, context: WKeybindContext) -> Self {
+        self.context = context;
+        self
+    }
+
     pub fn mods_as_key_but_mask(&self) -> KeyButMask {
         KeyButMask::from(u16::from(self.mods))
     }
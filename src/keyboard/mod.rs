@@ -1,27 +1,46 @@
 pub mod keybind;
 
+use wwm_core::util::keysym;
 use x11rb::connection::RequestConnection;
+use x11rb::protocol::xinput::{self, ConnectionExt as _};
 use x11rb::protocol::xkb::{self, ConnectionExt as _};
-use x11rb::protocol::xproto::{ConnectionExt, GrabMode, ModMask, Screen};
+use x11rb::protocol::xproto::{
+    ConnectionExt, GrabMode, ModMask, Screen, Window, KEY_PRESS_EVENT, KEY_RELEASE_EVENT,
+};
+use x11rb::rust_connection::{ReplyError, ReplyOrIdError};
 use xcb::x::{Keysym, GRAB_ANY};
 use xkbcommon::xkb::State as KBState;
-use xkbcommon::xkb::{self as xkbc, KEY_Num_Lock};
+use xkbcommon::xkb::{self as xkbc, keysyms as ks, KEY_Num_Lock};
 
-use crate::config::commands;
+use crate::command::WKeyCommand;
+use crate::config::{commands, double_tap};
+use crate::settings::WConfig;
 
 use self::keybind::WKeybind;
 
-pub struct WKeyboard {
+pub struct WKeyboard<'a> {
+    xcb_conn: &'a xcb::Connection,
+    context: xkbc::Context,
     state: KBState,
     pub device_id: i32,
     pub keybinds: Vec<WKeybind>,
+    pub resize_mode_keybinds: Vec<WKeybind>,
+    repeat: Option<WKeyRepeat>,
+    // whether the double tap modifier is currently held without any other input
+    mod_tap_pressed: bool,
 }
 
-impl WKeyboard {
-    pub fn new<'a, RC: RequestConnection>(
-        conn: &'a RC,
+struct WKeyRepeat {
+    keycode: u8,
+    action: WKeyCommand,
+}
+
+impl<'a> WKeyboard<'a> {
+    pub fn new<RC: RequestConnection>(
+        conn: &RC,
         xcb_conn: &'a xcb::Connection,
         screen: &Screen,
+        config: &WConfig,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         conn.prefetch_extension_information(xkb::X11_EXTENSION_NAME)?;
 
@@ -49,20 +68,101 @@ impl WKeyboard {
             &xkb::SelectEventsAux::new(),
         )?;
 
+        // only report key presses while a key is held so that we can drive our own
+        // repeats for continuous actions (see `WKeyCommand::repeats`)
+        conn.xkb_per_client_flags(
+            xkb::ID::USE_CORE_KBD.into(),
+            xkb::PerClientFlag::DETECTABLE_AUTO_REPEAT,
+            xkb::PerClientFlag::DETECTABLE_AUTO_REPEAT,
+            0u32.into(),
+            0u32.into(),
+            0u32.into(),
+        )?
+        .reply()?;
+
         let context = xkbc::Context::new(xkbc::CONTEXT_NO_FLAGS);
         let device_id = xkbc::x11::get_core_keyboard_device_id(xcb_conn);
+        let state = Self::state_from_device(&context, xcb_conn, device_id);
+
+        let keyboard = Self {
+            xcb_conn,
+            context,
+            state,
+            device_id,
+            keybinds: config.keybinds.clone(),
+            resize_mode_keybinds: commands::setup_resize_mode_keybinds(),
+            repeat: None,
+            mod_tap_pressed: false,
+        };
+
+        keyboard.grab_keys(conn, screen.root)?;
+        if let Err(e) = Self::select_raw_key_presses(conn, screen.root) {
+            eprintln!("ERROR: Failed to select raw key presses for the double tap: {e}");
+        }
+
+        Ok(keyboard)
+    }
+
+    // xkb only reports the keys that change the modifiers, while raw key presses come
+    // in for every key no matter which window has the focus
+    fn select_raw_key_presses<RC: RequestConnection>(
+        conn: &RC,
+        root: Window,
+    ) -> Result<(), ReplyError> {
+        conn.xinput_xi_query_version(2, 1)?.reply()?;
+        let mask = xinput::EventMask {
+            deviceid: xinput::Device::ALL_MASTER.into(),
+            mask: vec![xinput::XIEventMask::RAW_KEY_PRESS],
+        };
+        conn.xinput_xi_select_events(root, &[mask])?;
+        Ok(())
+    }
+
+    fn state_from_device(
+        context: &xkbc::Context,
+        xcb_conn: &xcb::Connection,
+        device_id: i32,
+    ) -> KBState {
         let keymap = xkbc::x11::keymap_new_from_device(
-            &context,
+            context,
             xcb_conn,
             device_id,
             xkbc::KEYMAP_COMPILE_NO_FLAGS,
         );
 
-        let state = xkbc::x11::state_new_from_device(&keymap, xcb_conn, device_id);
+        xkbc::x11::state_new_from_device(&keymap, xcb_conn, device_id)
+    }
+
+    // rebuilds the keymap and state from the core keyboard device and grabs
+    // the keybinds again since their keycodes might have changed.
+    // this happens when a different keyboard is plugged in or `setxkbmap` is run.
+    pub fn update_keymap<RC: RequestConnection>(
+        &mut self,
+        conn: &RC,
+        root: Window,
+    ) -> Result<(), ReplyOrIdError> {
+        self.device_id = xkbc::x11::get_core_keyboard_device_id(self.xcb_conn);
+        self.state = Self::state_from_device(&self.context, self.xcb_conn, self.device_id);
+        self.grab_keys(conn, root)
+    }
 
-        // grab all keybinds
-        let keybinds = commands::setup_keybinds();
+    // swaps the keybinds for those of a reloaded config and grabs their keys instead
+    pub fn set_keybinds<RC: RequestConnection>(
+        &mut self,
+        conn: &RC,
+        root: Window,
+        keybinds: Vec<WKeybind>,
+    ) -> Result<(), ReplyOrIdError> {
+        self.keybinds = keybinds;
+        self.repeat = None;
+        self.grab_keys(conn, root)
+    }
 
+    fn grab_keys<RC: RequestConnection>(
+        &self,
+        conn: &RC,
+        root: Window,
+    ) -> Result<(), ReplyOrIdError> {
         let numlockmask = {
             let mut nlm: u16 = 0;
             let modmap = conn.get_modifier_mapping()?.reply()?;
@@ -84,23 +184,26 @@ impl WKeyboard {
             numlockmask | ModMask::LOCK,
         ];
 
-        conn.ungrab_key(GRAB_ANY, screen.root, ModMask::ANY)?;
+        conn.ungrab_key(GRAB_ANY, root, ModMask::ANY)?;
 
+        let keymap = self.state.get_keymap();
         let (start, end) = (keymap.min_keycode(), keymap.max_keycode());
 
         for k in start..end {
-            let syms = state.key_get_syms(k);
+            let syms = self.state.key_get_syms(k);
 
             if syms.is_empty() {
                 continue;
             }
 
-            for keybind in &keybinds {
-                if syms.contains(&keybind.keysym) {
+            let alias = self.keypad_alias(k);
+
+            for keybind in &self.keybinds {
+                if syms.contains(&keybind.keysym) || alias == Some(keybind.keysym) {
                     for m in &modifiers {
                         conn.grab_key(
                             true,
-                            screen.root,
+                            root,
                             keybind.mods | *m,
                             k as u8,
                             GrabMode::ASYNC,
@@ -111,14 +214,121 @@ impl WKeyboard {
             }
         }
 
-        Ok(Self {
-            state,
-            device_id,
-            keybinds,
-        })
+        Ok(())
     }
 
     pub fn key_sym(&mut self, detail: u32) -> Keysym {
-        self.state.key_get_one_sym(detail)
+        self.keypad_alias(detail)
+            .unwrap_or_else(|| self.state.key_get_one_sym(detail))
+    }
+
+    // keypad keys only produce digits with num lock enabled, so every level
+    // of the key is checked to make them work as digits regardless
+    fn keypad_alias(&self, keycode: u32) -> Option<Keysym> {
+        let keymap = self.state.get_keymap();
+        let layout = self.state.key_get_layout(keycode);
+        (0..keymap.num_levels_for_key(keycode, layout))
+            .flat_map(|level| {
+                keymap
+                    .key_get_syms_by_level(keycode, layout, level)
+                    .to_vec()
+            })
+            .find(|sym| (ks::KEY_KP_0..=ks::KEY_KP_9).contains(sym))
+            .map(keysym::alias)
+    }
+
+    // returns the name of the active layout, or nothing if there is only one to choose from
+    pub fn layout_name(&self) -> Option<String> {
+        let keymap = self.state.get_keymap();
+        if keymap.num_layouts() < 2 {
+            return None;
+        }
+        let idx = self.state.serialize_layout(xkbc::STATE_LAYOUT_EFFECTIVE);
+        Some(keymap.layout_get_name(idx).to_string())
+    }
+
+    // locks the next keyboard group on the server. our state is updated
+    // once the server notifies us about the change.
+    pub fn next_layout<RC: RequestConnection>(&self, conn: &RC) -> Result<(), ReplyOrIdError> {
+        let layouts = self.state.get_keymap().num_layouts();
+        if layouts < 2 {
+            return Ok(());
+        }
+
+        let current = self.state.serialize_layout(xkbc::STATE_LAYOUT_EFFECTIVE);
+        let next = (current + 1) % layouts;
+
+        conn.xkb_latch_lock_state(
+            xkb::ID::USE_CORE_KBD.into(),
+            ModMask::from(0u16),
+            ModMask::from(0u16),
+            true,
+            xkb::Group::from(next as u8),
+            ModMask::from(0u16),
+            false,
+            0,
+        )?;
+        Ok(())
+    }
+
+    // returns whether the effective layout changed
+    pub fn update_state(&mut self, evt: &xkb::StateNotifyEvent) -> bool {
+        let prev = self.state.serialize_layout(xkbc::STATE_LAYOUT_EFFECTIVE);
+
+        // only the layout is tracked, the modifiers are left alone so that
+        // looking up keysyms always yields the unmodified symbol keybinds are defined with
+        self.state.update_mask(
+            0,
+            0,
+            0,
+            evt.base_group as u32,
+            evt.latched_group as u32,
+            u8::from(evt.locked_group) as u32,
+        );
+
+        prev != self.state.serialize_layout(xkbc::STATE_LAYOUT_EFFECTIVE)
+    }
+
+    // tracks presses of the bare double tap modifier and returns whether it was just tapped.
+    // modifier changes are reported by xkb no matter which window has focus, and other
+    // keys interrupt a tap through their raw key presses.
+    pub fn track_mod_tap(&mut self, evt: &xkb::StateNotifyEvent) -> bool {
+        if u16::from(evt.changed) & u16::from(xkb::StatePart::MODIFIER_BASE) == 0 {
+            return false;
+        }
+
+        let held = u16::from(evt.base_mods);
+        let tapped = evt.event_type == KEY_RELEASE_EVENT && held == 0 && self.mod_tap_pressed;
+
+        self.mod_tap_pressed =
+            evt.event_type == KEY_PRESS_EVENT && held == u16::from(double_tap::MOD);
+
+        tapped
+    }
+
+    pub fn interrupt_mod_tap(&mut self) {
+        self.mod_tap_pressed = false;
+    }
+
+    pub fn is_repeating(&self, keycode: u8) -> bool {
+        matches!(self.repeat, Some(ref r) if r.keycode == keycode)
+    }
+
+    pub fn start_repeat(&mut self, keycode: u8, action: WKeyCommand) {
+        self.repeat = Some(WKeyRepeat { keycode, action });
+    }
+
+    // returns whether the key was the one being repeated
+    pub fn stop_repeat(&mut self, keycode: u8) -> bool {
+        let repeating = self.is_repeating(keycode);
+        if repeating {
+            self.repeat = None;
+        }
+        repeating
+    }
+
+    // the command of the held key, if any
+    pub fn repeat_action(&self) -> Option<WKeyCommand> {
+        self.repeat.as_ref().map(|r| r.action.clone())
     }
 }
@@ -1,93 +1,70 @@
-use crate::{config::bar_height, config::theme::window::BORDER_WIDTH, monitor::WMonitor};
-use std::cmp::Ordering;
-use wwm_core::util::{primitives::WRect, WLayout};
+use crate::{
+    client::WClientState,
+    config::theme::window::{BORDER_WIDTH, RESPECT_SIZE_HINTS, SMART_GAPS},
+    monitor::WMonitor,
+};
+use wwm_core::{
+    layout::{self, WGaps},
+    util::{primitives::WRect, WLayout},
+};
 use x11rb::connection::Connection;
 
 pub fn layout_clients<C: Connection>(
     layout: &WLayout,
     width_factor: f32,
     monitor: &WMonitor<C>,
-    clients: usize,
+    clients: &[usize],
 ) -> Option<Vec<WRect>> {
-    if clients == 0 {
-        return None;
-    }
-
-    let rects = match layout {
-        WLayout::MainStack => tile(monitor, width_factor, clients),
-        WLayout::Column => col(monitor, clients),
+    let factors: Vec<f32> = match layout {
+        WLayout::Column => (0..clients.len())
+            .map(|i| monitor.column_weights.get(i).copied().unwrap_or(1.0))
+            .collect(),
+        _ => clients
+            .iter()
+            .map(|i| monitor.clients[*i].size_factor)
+            .collect(),
     };
-
-    Some(rects)
+    let gaps = if is_alone(clients.len()) {
+        WGaps::default()
+    } else {
+        monitor.gaps
+    };
+    layout::layout_clients(
+        layout,
+        monitor.rect,
+        width_factor,
+        monitor.main_count,
+        gaps,
+        border_width(clients.len()),
+        &factors,
+    )
 }
 
-fn tile<C: Connection>(mon: &WMonitor<C>, width_factor: f32, clients: usize) -> Vec<WRect> {
-    if clients == 1 {
-        return single_client(mon);
+// the border width of each of the tiled clients of a tag
+pub fn border_width(clients: usize) -> u16 {
+    if is_alone(clients) {
+        0
+    } else {
+        BORDER_WIDTH
     }
-
-    let main_width = mon.width_from_percentage(width_factor);
-
-    let mut rects = vec![];
-
-    rects.push(WRect::new(
-        mon.rect.x,
-        mon.rect.y,
-        main_width - BORDER_WIDTH * 2,
-        mon.rect.h - BORDER_WIDTH * 2,
-    ));
-
-    let non_main_window_count = clients - 1;
-    let non_main_height = mon.rect.h / non_main_window_count as u16;
-
-    for (i, _) in (0..clients).skip(1).enumerate() {
-        let cy = mon.rect.y + (i as u16 * non_main_height) as i16;
-        let mut ch = non_main_height;
-
-        if i == non_main_window_count - 1 {
-            let ctot = cy + ch as i16 - bar_height() as i16;
-            let mtot = mon.rect.y + mon.rect.h as i16 - bar_height() as i16;
-
-            match ctot.cmp(&mtot) {
-                Ordering::Less => ch += ctot.abs_diff(mtot),
-                Ordering::Greater => ch -= ctot.abs_diff(mtot),
-                _ => {}
-            }
-        }
-
-        rects.push(WRect::new(
-            mon.rect.x + main_width as i16,
-            cy,
-            mon.rect.w - main_width - (BORDER_WIDTH * 2),
-            ch - (BORDER_WIDTH * 2),
-        ));
-    }
-
-    rects
 }
 
-fn col<C: Connection>(mon: &WMonitor<C>, clients: usize) -> Vec<WRect> {
-    if clients == 1 {
-        return single_client(mon);
-    }
-    let mut rects = vec![];
-    let client_width = mon.rect.w / clients as u16;
-    for i in 0..clients {
-        rects.push(WRect::new(
-            mon.rect.x + (i as i16 * client_width as i16),
-            mon.rect.y,
-            client_width - (BORDER_WIDTH * 2),
-            mon.rect.h - (BORDER_WIDTH * 2),
-        ));
+// shrinks `rect` to a size the size hints of the client allow, centered in the space
+// it had so that the edges of its neighbours still line up
+pub fn fit_size_hints(c: &WClientState, rect: WRect) -> WRect {
+    if !RESPECT_SIZE_HINTS || !c.hints_valid {
+        return rect;
     }
-    rects
+    let (w, h) = c.adjust_aspect_ratio(rect.w, rect.h);
+    let (w, h) = (w.min(rect.w), h.min(rect.h));
+    WRect::new(
+        rect.x + ((rect.w - w) / 2) as i16,
+        rect.y + ((rect.h - h) / 2) as i16,
+        w,
+        h,
+    )
 }
 
-fn single_client<C: Connection>(mon: &WMonitor<C>) -> Vec<WRect> {
-    vec![WRect::new(
-        mon.rect.x,
-        mon.rect.y,
-        mon.rect.w - BORDER_WIDTH * 2,
-        mon.rect.h - BORDER_WIDTH * 2,
-    )]
+fn is_alone(clients: usize) -> bool {
+    SMART_GAPS && clients == 1
 }
@@ -1,14 +1,39 @@
+mod args;
+mod batch;
+mod border;
 mod client;
+mod client_list;
 mod command;
 mod config;
+mod error;
+mod hint;
+mod idle;
+mod ipc;
 mod keyboard;
 mod layouts;
 mod monitor;
 mod mouse;
+mod osd;
+mod overview;
+mod pip;
+mod selection;
+mod session;
+mod settings;
+mod signal;
+mod strut;
+mod supervisor;
+mod tab_strip;
+mod tag_preview;
+mod timer;
+mod title_bar;
 mod wwm;
 
+use std::process;
+
+use args::{WArgs, USAGE};
 use keyboard::WKeyboard;
-use mouse::WMouse;
+use session::WSession;
+use settings::WConfig;
 use wwm::WinMan;
 use x11rb::atom_manager;
 use x11rb::connection::Connection;
@@ -17,6 +42,7 @@ use x11rb::xcb_ffi::XCBConnection;
 atom_manager! {
     pub AtomCollection: AtomCollectionsCookie {
         UTF8_STRING,
+        MANAGER,
         WM_PROTOCOLS,
         WM_DELETE_WINDOW,
         WM_STATE,
@@ -35,17 +61,66 @@ atom_manager! {
         _NET_CLIENT_LIST,
         _NET_CLIENT_INFO,
         _NET_ACTIVE_WINDOW,
+        _NET_CURRENT_DESKTOP,
+        _NET_NUMBER_OF_DESKTOPS,
+        _NET_DESKTOP_NAMES,
+        _NET_DESKTOP_VIEWPORT,
+        _NET_WORKAREA,
+        _NET_FRAME_EXTENTS,
+        _NET_REQUEST_FRAME_EXTENTS,
+        _NET_WM_DESKTOP,
         _NET_SUPPORTING_WM_CHECK,
         _NET_WM_STATE,
-        _NET_WM_STATE_ADD,
-        _NET_WM_STATE_TOGGLE,
+        _NET_WM_PING,
+        _NET_WM_ICON,
+        _MOTIF_WM_HINTS,
         _NET_WM_STATE_FULLSCREEN,
+        _NET_WM_FULLSCREEN_MONITORS,
+        _NET_WM_STATE_DEMANDS_ATTENTION,
+        _NET_WM_STATE_ABOVE,
+        _NET_WM_STATE_BELOW,
         _NET_WM_WINDOW_TYPE,
         _NET_WM_WINDOW_TYPE_DIALOG,
+        _NET_WM_WINDOW_TYPE_DOCK,
+        _NET_WM_WINDOW_TYPE_NOTIFICATION,
+        _NET_WM_WINDOW_TYPE_TOOLTIP,
+        _NET_WM_WINDOW_TYPE_SPLASH,
+        _NET_WM_WINDOW_TYPE_UTILITY,
+        _NET_WM_STRUT,
+        _NET_WM_STRUT_PARTIAL,
+        _NET_WM_WINDOW_OPACITY,
     }
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = match WArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("ERROR: {e}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    if args.version {
+        println!("wwm {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+    let config_path = args.config.or_else(settings::path);
+    // checking the config doesn't need an X server
+    if args.check_config {
+        let ok = match &config_path {
+            Some(path) => settings::check(path),
+            None => {
+                eprintln!("ERROR: There is no config file to check, $HOME isn't set");
+                false
+            }
+        };
+        process::exit(if ok { 0 } else { 1 });
+    }
+
     let (xcb_conn, screen_num) = xcb::Connection::connect(None)?;
     let screen_num = usize::try_from(screen_num)?;
 
@@ -57,11 +132,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let atoms = AtomCollection::new(&conn)?;
     let atoms = atoms.reply()?;
 
+    let config = WConfig::load(&conn, config_path);
+
     let screen = &conn.setup().roots[screen_num];
-    let keyboard = WKeyboard::new(&conn, &xcb_conn, screen)?;
-    let mouse = WMouse::new(&conn, screen_num);
+    let keyboard = WKeyboard::new(&conn, &xcb_conn, screen, &config)?;
+
+    let session = WSession::connect(args.sm_client_id.as_deref());
 
-    let mut wwm = WinMan::init(&conn, screen_num, keyboard, mouse, atoms)?;
+    let mut wwm = WinMan::init(
+        &conn,
+        screen_num,
+        config,
+        keyboard,
+        atoms,
+        session,
+        args.replace,
+    )?;
     wwm.run()?;
     Ok(())
 }
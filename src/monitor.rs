@@ -1,9 +1,7 @@
-use std::rc::Rc;
-
 use thiserror::Error;
 use wwm_bar::WBar;
 use wwm_core::{
-    text::TextRenderer,
+    layout::{resize_column, WGaps},
     util::{
         bar::{WBarColors, WBarOptions},
         WLayout,
@@ -13,19 +11,16 @@ use x11rb::{
     connection::Connection,
     protocol::{
         randr::MonitorInfo,
-        xproto::{ConfigureWindowAux, ConnectionExt},
+        xproto::{ConfigureWindowAux, Window},
     },
-    xcb_ffi::ReplyOrIdError,
+    rust_connection::ReplyOrIdError,
 };
 
+use crate::batch::WConfigureBatch;
+use crate::client_list::{moved_tag, WClientList};
 use crate::command::WDirection;
-use crate::{
-    client::WClientState,
-    config::{
-        tags::{MAIN_CLIENT_WIDTH_PERCENTAGE, TAG_CAP},
-        theme,
-    },
-};
+use crate::tab_strip::WTabStrip;
+use crate::{client::WClientState, config::theme, settings::WConfig};
 use wwm_core::util::primitives::{WPos, WRect};
 
 #[derive(Error, Debug)]
@@ -34,69 +29,102 @@ pub enum StateError {
     Bounds(usize),
 }
 
+// the layout state a tag keeps while another one is shown
+#[derive(Clone, Copy)]
+pub struct WTagState {
+    pub layout: WLayout,
+    pub width_factor: f32,
+    pub main_count: usize,
+}
+
 pub struct WMonitor<'a, C: Connection> {
     pub conn: &'a C,
     pub bar: WBar<'a, C>,
     pub primary: bool,
     pub rect: WRect,
-    pub clients: Vec<WClientState>,
+    // the area below the bar before docks and panels take their space off of `rect`
+    pub full_rect: WRect,
+    // how much larger text and the bar are drawn than on a 96 dpi display
+    pub scale: f32,
+    pub bar_height: u16,
+    pub clients: WClientList,
     pub client: Option<usize>,
     pub layout: WLayout,
+    // the layouts tiled clients are shown with title bars in
+    pub title_bar_layouts: Vec<WLayout>,
     pub tag: usize,
+    pub tag_count: usize,
     pub width_factor: f32,
+    // how much `AdjustMainWidth` changes the width factor by
+    pub width_step: f32,
+    // how many clients share the main area, dwm's nmaster
+    pub main_count: usize,
+    pub gaps: WGaps,
+    // how wide each column of the column layout is compared to the others, from the left.
+    // columns without one are as wide as a column with a weight of 1.
+    pub column_weights: Vec<f32>,
+    // shown while the tabbed layout has clients to show tabs for
+    pub tab_strip: Option<WTabStrip>,
+    // the layout state of every tag, which for the shown one lives in the fields above
+    tag_states: Vec<WTagState>,
 }
 
 impl<'a, C: Connection> WMonitor<'a, C> {
-    pub fn new(mi: &MonitorInfo, conn: &'a C, text_renderer: Rc<TextRenderer<'a, C>>) -> Self {
-        let layout = WLayout::MainStack;
-
-        let bar_rect = WRect {
-            x: mi.x,
-            y: mi.y,
-            w: mi.width,
-            h: theme::bar::FONT_SIZE as u16 + (theme::bar::PADDING * 2),
+    pub fn new(mi: &MonitorInfo, conn: &'a C, config: &WConfig) -> Self {
+        let tag_state = WTagState {
+            layout: WLayout::MainStack,
+            width_factor: config.width_factor,
+            main_count: config.main_count,
         };
 
+        let scale = dpi_scale(mi);
+        let bar_options = bar_options(WRect::new(mi.x, mi.y, mi.width, 0), scale, config);
+        let bar_rect = bar_options.rect;
+
         let y = bar_rect.y + bar_rect.h as i16;
         let height = mi.height - bar_rect.h;
 
-        let colors = WBarColors::new(
-            theme::bar::FG,
-            theme::bar::BG,
-            theme::bar::FG_SELECTED,
-            theme::bar::BG_SELECTED,
-        );
-
-        let bar_options = WBarOptions {
-            rect: bar_rect,
-            padding: theme::bar::PADDING,
-            section_padding: theme::bar::SECTION_PADDING,
-            tag_count: TAG_CAP,
-            tag_width: theme::bar::TAG_WIDTH,
-            colors,
-        };
-
-        let bar = WBar::new(
-            conn,
-            text_renderer,
-            bar_options,
-            *theme::bar::MODULE_MASK,
-            theme::bar::STATUS_INTERVAL,
-        );
+        let bar = WBar::new(bar_options);
 
         Self {
             conn,
             bar,
             primary: mi.primary,
             rect: WRect::new(mi.x, y, mi.width, height),
-            clients: Vec::new(),
+            full_rect: WRect::new(mi.x, y, mi.width, height),
+            scale,
+            bar_height: bar_rect.h,
+            clients: WClientList::default(),
             client: None,
-            layout,
+            layout: tag_state.layout,
+            title_bar_layouts: theme::window::TITLE_BAR_LAYOUTS.to_vec(),
             tag: 0,
-            width_factor: MAIN_CLIENT_WIDTH_PERCENTAGE,
+            tag_count: config.tag_count,
+            width_factor: tag_state.width_factor,
+            width_step: config.width_step,
+            main_count: tag_state.main_count,
+            gaps: config.gaps,
+            column_weights: vec![],
+            tab_strip: None,
+            tag_states: vec![tag_state; config.tag_count],
         }
     }
 
+    // takes on the settings of a reloaded config. the bar can change its height, which
+    // moves `full_rect`, so the struts have to be applied again afterwards.
+    pub fn apply_config(&mut self, config: &WConfig) -> Result<(), ReplyOrIdError> {
+        self.gaps = config.gaps;
+        self.width_step = config.width_step;
+
+        let bar = self.bar.rect();
+        let bar_options = bar_options(WRect::new(bar.x, bar.y, bar.w, 0), self.scale, config);
+        let bottom = self.full_rect.y + self.full_rect.h as i16;
+        self.bar_height = bar_options.rect.h;
+        self.full_rect.y = bar.y + self.bar_height as i16;
+        self.full_rect.h = (bottom - self.full_rect.y).max(0) as u16;
+        self.bar.reconfigure(self.conn, bar_options)
+    }
+
     pub fn has_pos(&self, p: WPos) -> bool {
         let has_x = p.x >= self.rect.x && p.x <= self.rect.x + self.rect.w as i16;
         let has_y = p.y >= self.rect.y && p.y <= self.rect.y + self.rect.h as i16;
@@ -113,45 +141,46 @@ impl<'a, C: Connection> WMonitor<'a, C> {
     }
 
     pub fn set_tag(&mut self, new_tag: usize) -> Result<(), StateError> {
-        if new_tag > TAG_CAP - 1 {
+        if new_tag >= self.tag_count {
             return Err(StateError::Bounds(new_tag));
         }
-        let clients = self.clients_in_tag(new_tag);
-        if clients.is_empty() {
-            self.client = None;
-        } else if let Some(i) = clients.last() {
-            self.client = Some(*i);
-        }
+        self.client = self.clients.selection_in_tag(None, new_tag);
+        self.tag_states[self.tag] = self.tag_state();
+
+        let state = self.tag_states[new_tag];
+        self.layout = state.layout;
+        self.width_factor = state.width_factor;
+        self.main_count = state.main_count;
         self.tag = new_tag;
         Ok(())
     }
 
+    fn tag_state(&self) -> WTagState {
+        WTagState {
+            layout: self.layout,
+            width_factor: self.width_factor,
+            main_count: self.main_count,
+        }
+    }
+
     pub fn select_adjacent(&mut self, dir: WDirection) {
         if let Some(i) = self.client {
-            match dir {
-                WDirection::Prev => {
-                    if let Some(i) = self.clients[i].prev {
-                        self.client = Some(i);
-                    }
-                }
-                WDirection::Next => {
-                    if let Some(i) = self.clients[i].next {
-                        self.client = Some(i);
-                    }
-                }
+            let adj = match dir {
+                WDirection::Prev => self.clients[i].prev,
+                WDirection::Next => self.clients[i].next,
+            };
+            if adj.is_some() {
+                self.client = adj;
             }
         }
     }
 
-    pub fn hide_clients(&self, conn: &C, tag: usize) -> Result<(), ReplyOrIdError> {
+    pub fn hide_clients(&self, batch: &mut WConfigureBatch, tag: usize) {
         let clients = self.clients_in_tag(tag);
         for i in clients.iter() {
-            let c = self.clients[*i];
-            let aux = ConfigureWindowAux::new().x(c.rect.w as i32 * -2);
-            conn.configure_window(c.window, &aux)?;
+            let c = &self.clients[*i];
+            batch.configure(c.window, ConfigureWindowAux::new().x(c.rect.w as i32 * -2));
         }
-        conn.flush()?;
-        Ok(())
     }
 
     pub fn set_layout(&mut self, layout: WLayout) -> bool {
@@ -162,132 +191,212 @@ impl<'a, C: Connection> WMonitor<'a, C> {
         true
     }
 
+    pub fn has_title_bars(&self) -> bool {
+        self.title_bar_layouts.contains(&self.layout)
+    }
+
+    pub fn toggle_title_bars(&mut self) {
+        if self.has_title_bars() {
+            self.title_bar_layouts.retain(|l| *l != self.layout);
+        } else {
+            self.title_bar_layouts.push(self.layout);
+        }
+    }
+
+    // picture-in-picture clients stay in view, whichever tag is shown
+    pub fn move_pip_clients(&mut self, from: usize, to: usize) {
+        for i in self.clients_in_tag(from) {
+            if self.clients[i].pip.is_some() {
+                self.clients.set_tag(i, to);
+            }
+        }
+        self.update_tag_indicators(from);
+        self.update_tag_indicators(to);
+    }
+
+    // reorders the tags by moving the clients of `from` over to `to`. the names stay at
+    // their places, since every monitor shares them.
+    pub fn move_tag(&mut self, from: usize, to: usize) {
+        self.clients.move_tag(from, to);
+        self.tag_states[self.tag] = self.tag_state();
+        let state = self.tag_states.remove(from);
+        self.tag_states.insert(to, state);
+        self.tag = moved_tag(self.tag, from, to);
+
+        for t in 0..self.tag_count {
+            self.update_tag_indicators(t);
+        }
+        self.bar.update_tags(self.tag);
+    }
+
     pub fn clients_in_tag(&self, tag: usize) -> Vec<usize> {
-        (0..self.clients.len())
+        self.clients.in_tag(tag)
+    }
+
+    // resizes the column of the selected client in the column layout
+    pub fn adjust_column_width(&mut self, dir: WDirection) {
+        let tiled = self.tiled_clients();
+        let Some(col) = self
+            .client
+            .and_then(|ci| tiled.iter().position(|i| *i == ci))
+        else {
+            return;
+        };
+        if self.column_weights.len() < tiled.len() {
+            self.column_weights.resize(tiled.len(), 1.0);
+        }
+        let delta = match dir {
+            WDirection::Prev => -self.width_step,
+            WDirection::Next => self.width_step,
+        };
+        resize_column(&mut self.column_weights[..tiled.len()], col, delta);
+    }
+
+    // the clients of the shown tag that are part of the layout
+    pub fn tiled_clients(&self) -> Vec<usize> {
+        self.clients_in_tag(self.tag)
             .into_iter()
-            .filter(|i| self.clients[*i].tag == tag)
+            .filter(|i| !self.clients[*i].is_floating)
             .collect()
     }
 
     pub fn swap_clients(&mut self, dir: WDirection) {
         if let Some(ci) = self.client {
-            let adj_idx = match dir {
-                WDirection::Prev => {
-                    let curr = &mut self.clients[ci];
-                    // early return since we have nothing to update
-                    if curr.prev.is_none() {
-                        return;
-                    }
-                    curr.prev
-                }
-                WDirection::Next => {
-                    let curr = &mut self.clients[ci];
-                    // early return since we have nothing to update
-                    if curr.next.is_none() {
-                        return;
-                    }
-                    curr.next
-                }
+            let adj = match dir {
+                WDirection::Prev => self.clients[ci].prev,
+                WDirection::Next => self.clients[ci].next,
             };
+            // nothing to swap with when the client is alone in its tag
+            let Some(adj_idx) = adj else { return };
 
-            let adj_idx = adj_idx.unwrap();
             self.clients.swap(adj_idx, ci);
-            self.relink_clients_in_tag(self.tag);
             self.client = Some(adj_idx);
         }
     }
 
-    pub fn client_to_tag(&mut self, conn: &C, tag: usize) -> Result<(), ReplyOrIdError> {
-        if let Some(curr_idx) = self.client {
-            self.clients[curr_idx].tag = tag;
-            self.relink_clients_in_tag(self.tag);
-            self.relink_clients_in_tag(tag);
-
-            self.bar
-                .set_has_clients(self.tag, !self.clients_in_tag(self.tag).is_empty());
-            self.bar.set_has_clients(tag, true);
+    // swaps the selected client with the first tiled client of the tag, or with the
+    // next one when it is the first already, like dwm's zoom. returns whether it moved.
+    pub fn zoom(&mut self) -> bool {
+        let Some(ci) = self.client.filter(|i| !self.clients[*i].is_floating) else {
+            return false;
+        };
+        let tiled: Vec<usize> = self
+            .clients_in_tag(self.tag)
+            .into_iter()
+            .filter(|i| !self.clients[*i].is_floating)
+            .collect();
+        let other = match tiled.first() {
+            Some(first) if *first == ci => tiled.get(1),
+            first => first,
+        };
+        let Some(&other) = other else { return false };
 
-            self.hide_clients(conn, tag)?;
-        }
-        Ok(())
+        self.clients.swap(ci, other);
+        self.client = Some(other);
+        true
     }
 
-    pub fn push_client(&mut self, mut client: WClientState) {
-        let clients = self.clients_in_tag(self.tag);
+    pub fn client_to_tag(&mut self, batch: &mut WConfigureBatch, idx: usize, tag: usize) {
+        let old_tag = self.clients[idx].tag;
+        self.clients.set_tag(idx, tag);
+        // the selection only moves on when it is the selected client that leaves
+        let preferred = self.client.filter(|i| *i != idx);
+        self.client = self.clients.selection_in_tag(preferred, self.tag);
 
-        if !clients.is_empty() {
-            if let Some(i) = clients.last() {
-                self.clients[*i].next = Some(self.clients.len());
-                client.prev = Some(*i);
-            }
+        self.update_tag_indicators(old_tag);
+        self.update_tag_indicators(tag);
 
-            if let Some(i) = clients.first() {
-                self.clients[*i].prev = Some(self.clients.len());
-                client.next = Some(*i);
-            }
+        if tag != self.tag {
+            self.hide_clients(batch, tag);
         }
-
-        self.clients.push(client);
-        self.bar.set_has_clients(self.tag, true);
-        self.client = Some(self.clients.len() - 1);
     }
 
-    fn relink_clients_in_tag(&mut self, tag: usize) {
-        let tag_clients = self.clients_in_tag(tag);
-
-        if tag_clients.is_empty() {
-            return;
+    // `select` is whether the client takes the selection, which it never does when
+    // it ends up on another tag, like clients of a restored session
+    pub fn push_client(&mut self, client: WClientState, select: bool) {
+        let tag = client.tag;
+        let idx = self.clients.push(client);
+        if select && tag == self.tag {
+            self.client = Some(idx);
         }
+        self.update_tag_indicators(tag);
+    }
 
-        if tag_clients.len() == 1 {
-            self.clients[tag_clients[0]].prev = None;
-            self.clients[tag_clients[0]].next = None;
-            self.client = if tag == self.tag { Some(0) } else { None };
-            return;
-        }
-
-        let first_idx = *tag_clients.first().unwrap();
-        let last_idx = *tag_clients.last().unwrap();
-
-        for (i, client_idx) in tag_clients.iter().enumerate() {
-            let prev = if *client_idx == first_idx {
-                last_idx
-            } else {
-                tag_clients[i - 1]
-            };
-
-            let next = if *client_idx == last_idx {
-                first_idx
-            } else {
-                tag_clients[i + 1]
-            };
+    // the user has seen the client once it is focused
+    pub fn clear_urgency(&mut self, idx: usize) {
+        self.set_urgent(idx, false);
+    }
 
-            self.clients[*client_idx].prev = Some(prev);
-            self.clients[*client_idx].next = Some(next);
+    pub fn set_urgent(&mut self, idx: usize, urgent: bool) {
+        let c = &mut self.clients[idx];
+        if c.is_urgent != urgent {
+            c.is_urgent = urgent;
+            let tag = c.tag;
+            self.update_tag_indicators(tag);
         }
+    }
 
-        if tag == self.tag {
-            self.client = Some(self.client.unwrap().min(last_idx));
-        }
+    // shows in the bar whether the tag has clients and whether any of them is urgent
+    fn update_tag_indicators(&mut self, tag: usize) {
+        let clients = self.clients_in_tag(tag);
+        self.bar.set_has_clients(tag, !clients.is_empty());
+        let urgent = clients.iter().any(|i| self.clients[*i].is_urgent);
+        self.bar.set_urgent(tag, urgent);
     }
 
     pub fn remove_client(&mut self, idx: usize) -> WClientState {
         let c = self.clients.remove(idx);
-        let clients_in_current_tag = self.clients_in_tag(self.tag);
-        if clients_in_current_tag.is_empty() {
-            self.client = None;
-        } else {
-            for t in 0..TAG_CAP {
-                self.relink_clients_in_tag(t);
-            }
-        }
 
-        self.bar
-            .set_has_clients(self.tag, !clients_in_current_tag.is_empty());
+        // the selection shifts along with the clients after the removed one
+        let selected = match self.client {
+            Some(ci) if ci > idx => Some(ci - 1),
+            Some(ci) if ci < idx => Some(ci),
+            _ => None,
+        };
+        self.client = self.clients.selection_in_tag(selected, self.tag);
+
+        self.update_tag_indicators(c.tag);
         c
     }
 
-    pub fn width_from_percentage(&self, p: f32) -> u16 {
-        (self.rect.w as f32 * p) as u16
+    pub fn client_idx(&self, win: Window) -> Option<usize> {
+        self.clients.idx_of(win)
+    }
+}
+
+// the options of a bar along the top of `rect`, which it gets the height of from the font
+fn bar_options(rect: WRect, scale: f32, config: &WConfig) -> WBarOptions {
+    let scaled = |px: f32| (px * scale).round();
+    let padding = scaled(config.theme.padding as f32) as u16;
+    let colors = WBarColors::new(
+        config.theme.fg,
+        config.theme.bg,
+        config.theme.fg_selected,
+        config.theme.bg_selected,
+        config.theme.bg_urgent,
+    );
+    WBarOptions {
+        rect: WRect {
+            h: scaled(config.font_size) as u16 + (padding * 2),
+            ..rect
+        },
+        padding,
+        section_padding: scaled(config.section_padding as f32) as i16,
+        tag_count: config.tag_count,
+        tag_width: scaled(config.tag_width as f32) as u16,
+        tag_names: config.tag_names.clone(),
+        colors,
+        focused_tag_style: theme::bar::FOCUSED_TAG_STYLE,
+    }
+}
+
+// how the monitor's dpi compares to 96, in quarter steps so that monitors of similar
+// density share a rasterized font. monitors that don't report their physical size,
+// like most virtual ones, are left unscaled.
+fn dpi_scale(mi: &MonitorInfo) -> f32 {
+    if !theme::bar::SCALE_BY_DPI || mi.width_in_millimeters == 0 {
+        return 1.0;
     }
+    let dpi = mi.width as f32 / (mi.width_in_millimeters as f32 / 25.4);
+    ((dpi / 96.0 * 4.0).round() / 4.0).max(1.0)
 }
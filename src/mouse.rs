@@ -1,11 +1,27 @@
-use crate::{command::WMouseCommand, config};
+use crate::{
+    command::{WKeyCommand, WMouseCommand},
+    config,
+};
+use wwm_core::util::keysym;
 use x11rb::{
     connection::Connection,
     cursor::Handle as CursorHandle,
-    protocol::xproto::{ButtonIndex, ConnectionExt, EventMask, GrabMode, KeyButMask, ModMask},
+    protocol::xproto::{
+        ButtonIndex, ConnectionExt, EventMask, GrabMode, KeyButMask, ModMask, Window,
+    },
     resource_manager::new_from_default,
+    rust_connection::{ConnectionError, ReplyOrIdError},
+    NONE,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WFocusModel {
+    // focus follows the pointer when it enters a client
+    FollowsMouse,
+    // clients are focused and raised when clicked
+    ClickToFocus,
+}
+
 #[derive(Debug)]
 pub struct WCursors {
     pub normal: u32,
@@ -14,40 +30,88 @@ pub struct WCursors {
 }
 
 impl WCursors {
-    pub fn new<C: Connection>(conn: &C, screen_num: usize) -> Self {
-        let resource_db = new_from_default(conn).unwrap();
-        let cursor_handle = CursorHandle::new(conn, screen_num, &resource_db).unwrap();
-        let cursor_handle = cursor_handle.reply().unwrap();
-        Self {
-            normal: cursor_handle.load_cursor(conn, "left_ptr").unwrap(),
-            resize: cursor_handle.load_cursor(conn, "sizing").unwrap(),
-            r#move: cursor_handle.load_cursor(conn, "fleur").unwrap(),
+    pub fn new<C: Connection>(conn: &C, screen_num: usize) -> Result<Self, ReplyOrIdError> {
+        let resource_db = new_from_default(conn)?;
+        let cursor_handle = CursorHandle::new(conn, screen_num, &resource_db)?;
+        let cursor_handle = cursor_handle.reply()?;
+        Ok(Self {
+            normal: cursor_handle.load_cursor(conn, "left_ptr")?,
+            resize: cursor_handle.load_cursor(conn, "sizing")?,
+            r#move: cursor_handle.load_cursor(conn, "fleur")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WGesture {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl WGesture {
+    // the dominant direction of a pointer movement if it went far enough
+    pub fn from_delta(dx: i16, dy: i16, threshold: i16) -> Option<Self> {
+        if dx.abs() >= dy.abs() && dx.abs() >= threshold {
+            Some(if dx < 0 { Self::Left } else { Self::Right })
+        } else if dy.abs() > dx.abs() && dy.abs() >= threshold {
+            Some(if dy < 0 { Self::Up } else { Self::Down })
+        } else {
+            None
         }
     }
 }
 
 pub struct WMouse {
     pub binds: Vec<WMouseBind>,
+    pub gestures: Vec<(WGesture, WKeyCommand)>,
     pub cursors: WCursors,
+    // the modifier that stands in for `MOD` in the binds
+    modkey: ModMask,
 }
 
 impl WMouse {
-    pub fn new<'a, C: Connection>(conn: &'a C, screen_num: usize) -> Self {
+    pub fn new<C: Connection>(
+        conn: &C,
+        screen_num: usize,
+        modkey: ModMask,
+    ) -> Result<Self, ReplyOrIdError> {
         let screen = &conn.setup().roots[screen_num];
 
-        let cursors = WCursors::new(conn, screen_num);
+        let mut mouse = Self {
+            binds: config::mouse::setup_mousebinds(),
+            gestures: config::mouse::setup_gestures(),
+            cursors: WCursors::new(conn, screen_num)?,
+            modkey: config::commands::MOD,
+        };
+        mouse.set_modkey(conn, screen.root, modkey)?;
+        Ok(mouse)
+    }
+
+    // moves the binds over to a new modifier and grabs their buttons again
+    pub fn set_modkey<C: Connection>(
+        &mut self,
+        conn: &C,
+        root: Window,
+        modkey: ModMask,
+    ) -> Result<(), ConnectionError> {
+        for bind in &mut self.binds {
+            bind.mods = keysym::replace_mod(bind.mods, self.modkey, modkey);
+        }
+        self.modkey = modkey;
 
-        let binds = config::mouse::setup_mousebinds();
-        for bind in &binds {
+        conn.ungrab_button(ButtonIndex::ANY, root, ModMask::ANY)?;
+        for bind in &self.binds {
             let cur = match bind.action {
-                WMouseCommand::DragClient => cursors.r#move,
-                WMouseCommand::ResizeClient => cursors.resize,
-                _ => cursors.normal,
+                WMouseCommand::DragClient => self.cursors.r#move,
+                WMouseCommand::ResizeClient => self.cursors.resize,
+                _ => self.cursors.normal,
             };
 
             conn.grab_button(
                 true,
-                screen.root,
+                root,
                 EventMask::BUTTON_PRESS | EventMask::BUTTON_RELEASE | EventMask::POINTER_MOTION,
                 GrabMode::ASYNC,
                 GrabMode::ASYNC,
@@ -55,11 +119,38 @@ impl WMouse {
                 cur,
                 bind.button,
                 bind.mods,
-            )
-            .unwrap();
+            )?;
+        }
+        Ok(())
+    }
+
+    // with click-to-focus every unfocused client has its buttons grabbed so
+    // that a click can focus it before the click is replayed to the client
+    pub fn update_client_grab<C: Connection>(
+        &self,
+        conn: &C,
+        win: Window,
+        focused: bool,
+    ) -> Result<(), ConnectionError> {
+        if config::mouse::FOCUS_MODEL != WFocusModel::ClickToFocus {
+            return Ok(());
         }
 
-        Self { binds, cursors }
+        conn.ungrab_button(ButtonIndex::ANY, win, ModMask::ANY)?;
+        if !focused {
+            conn.grab_button(
+                false,
+                win,
+                EventMask::BUTTON_PRESS,
+                GrabMode::SYNC,
+                GrabMode::ASYNC,
+                NONE,
+                NONE,
+                ButtonIndex::ANY,
+                ModMask::ANY,
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -68,6 +159,7 @@ pub struct WMouseBind {
     pub mods: ModMask,
     pub button: ButtonIndex,
     pub action: WMouseCommand,
+    pub root_only: bool,
 }
 
 impl WMouseBind {
@@ -76,6 +168,16 @@ impl WMouseBind {
             mods: mods.into(),
             button,
             action,
+            root_only: false,
+        }
+    }
+
+    // a bind that only triggers when clicking on the root window itself
+    // and not on any of the clients
+    pub fn new_root<M: Into<ModMask>>(mods: M, button: ButtonIndex, action: WMouseCommand) -> Self {
+        Self {
+            root_only: true,
+            ..Self::new(mods, button, action)
         }
     }
 
@@ -0,0 +1,182 @@
+use std::{
+    io::Read,
+    process::{Child, Command, Stdio},
+};
+
+use wwm_core::{
+    overlay::WOverlay,
+    text::{FontError, TextRenderer},
+    util::{color::hex_to_rgba, primitives::WRect},
+};
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{Atom, AtomEnum, ConfigureWindowAux, ConnectionExt, PropMode, StackMode},
+    rust_connection::{ConnectionError, ReplyOrIdError},
+    wrapper::ConnectionExt as _,
+};
+
+use crate::settings::WTheme;
+
+// how many steps the osd takes to fade out
+pub const FADE_STEPS: u32 = 10;
+
+// a short message centered on a monitor after a wm action, like switching tags.
+// it fades out by lowering its opacity, which only shows with a compositor running.
+pub struct WOsd {
+    pub overlay: WOverlay,
+    pub mon_idx: usize,
+    text: String,
+    // steps left until it is fully faded out
+    fade_steps: u32,
+}
+
+impl WOsd {
+    pub fn new<C: Connection>(
+        conn: &C,
+        text_renderer: &TextRenderer<C>,
+        theme: &WTheme,
+        area: WRect,
+        mon_idx: usize,
+        text: String,
+    ) -> Result<Self, ReplyOrIdError> {
+        let rect = centered(area, size(text_renderer, theme, &text));
+        let overlay = WOverlay::new(conn, text_renderer, rect, theme.bg_selected)?;
+        Ok(Self {
+            overlay,
+            mon_idx,
+            text,
+            fade_steps: FADE_STEPS,
+        })
+    }
+
+    // shows a new message in place of the current one, at full opacity again
+    pub fn update<C: Connection>(
+        &mut self,
+        conn: &C,
+        text_renderer: &TextRenderer<C>,
+        theme: &WTheme,
+        area: WRect,
+        text: String,
+        opacity_atom: Atom,
+    ) -> Result<(), ConnectionError> {
+        let rect = centered(area, size(text_renderer, theme, &text));
+        self.overlay.configure(conn, rect)?;
+        conn.configure_window(
+            self.overlay.window,
+            &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+        )?;
+        conn.delete_property(self.overlay.window, opacity_atom)?;
+        self.text = text;
+        self.fade_steps = FADE_STEPS;
+        Ok(())
+    }
+
+    pub fn draw<C: Connection>(
+        &self,
+        text_renderer: &TextRenderer<C>,
+        theme: &WTheme,
+    ) -> Result<(), FontError> {
+        self.overlay.draw(
+            text_renderer,
+            &self.text,
+            theme.padding,
+            hex_to_rgba(theme.fg_selected),
+            hex_to_rgba(theme.bg_selected),
+        )
+    }
+
+    // lowers the opacity by a step and returns whether there is anything left of the osd
+    pub fn fade<C: Connection>(
+        &mut self,
+        conn: &C,
+        opacity_atom: Atom,
+    ) -> Result<bool, ConnectionError> {
+        self.fade_steps = self.fade_steps.saturating_sub(1);
+        let opacity = (u32::MAX as u64 * self.fade_steps as u64 / FADE_STEPS as u64) as u32;
+        conn.change_property32(
+            PropMode::REPLACE,
+            self.overlay.window,
+            opacity_atom,
+            AtomEnum::CARDINAL,
+            &[opacity],
+        )?;
+        Ok(self.fade_steps > 0)
+    }
+}
+
+// a media command changing a value to show, like the current volume, which is
+// queried with `query` once the command exits
+pub struct WMediaChange {
+    pub pid: libc::pid_t,
+    pub label: &'static str,
+    pub query: &'static [&'static str],
+}
+
+// a running command printing a value to show, which is read once it exits so that the
+// event loop doesn't wait on it
+pub struct WOsdQuery {
+    label: &'static str,
+    child: Child,
+}
+
+impl WOsdQuery {
+    pub fn spawn(label: &'static str, cmd: &'static [&'static str]) -> Option<Self> {
+        let (bin, args) = wwm_core::util::cmd::format(cmd)?;
+        let child = Command::new(bin)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        match child {
+            Ok(child) => Some(Self { label, child }),
+            Err(e) => {
+                eprintln!("ERROR: failed to run {bin}: {e}");
+                None
+            }
+        }
+    }
+
+    pub fn exited(&mut self) -> bool {
+        !matches!(self.child.try_wait(), Ok(None))
+    }
+
+    // for a query that is replaced by a newer one before it is done
+    pub fn cancel(mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+
+    // what the command printed along with the label, once it exited
+    pub fn text(mut self) -> Option<String> {
+        let mut output = String::new();
+        self.child.stdout.take()?.read_to_string(&mut output).ok()?;
+        let value = output.trim();
+        (!value.is_empty()).then(|| format!("{} {value}", self.label))
+    }
+}
+
+fn size<C: Connection>(text_renderer: &TextRenderer<C>, theme: &WTheme, text: &str) -> (u16, u16) {
+    let metrics = text_renderer.measure(text);
+    let padding = theme.padding * 4;
+    (metrics.width + padding, metrics.height + padding)
+}
+
+fn centered(area: WRect, (w, h): (u16, u16)) -> WRect {
+    WRect::new(
+        area.x + (area.w.saturating_sub(w) / 2) as i16,
+        area.y + (area.h.saturating_sub(h) / 2) as i16,
+        w,
+        h,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn centered_within_the_monitor() {
+        let rect = centered(WRect::new(1920, 0, 1920, 1080), (200, 80));
+        assert_eq!((rect.x, rect.y, rect.w, rect.h), (2780, 500, 200, 80));
+    }
+}
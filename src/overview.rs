@@ -0,0 +1,130 @@
+use wwm_core::{
+    overlay::WOverlay,
+    text::{FontError, TextRenderer},
+    util::{color::hex_to_rgba, primitives::WRect},
+};
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{ChangeWindowAttributesAux, ConnectionExt, EventMask},
+    rust_connection::ReplyOrIdError,
+};
+
+use crate::settings::WTheme;
+
+// space between the tags in the grid, in pixels
+const GAP: u16 = 10;
+
+// covers a monitor with a grid of all its tags, each showing a miniature of its clients
+pub struct WOverview {
+    pub overlay: WOverlay,
+    pub mon_idx: usize,
+    // where each tag is drawn, relative to the overlay
+    cells: Vec<WRect>,
+    label_height: u16,
+}
+
+impl WOverview {
+    pub fn new<C: Connection>(
+        conn: &C,
+        text_renderer: &TextRenderer<C>,
+        theme: &WTheme,
+        rect: WRect,
+        mon_idx: usize,
+        tag_count: usize,
+        label_height: u16,
+    ) -> Result<Self, ReplyOrIdError> {
+        let overlay = WOverlay::new(conn, text_renderer, rect, theme.bg)?;
+        conn.change_window_attributes(
+            overlay.window,
+            &ChangeWindowAttributesAux::new()
+                .event_mask(EventMask::EXPOSURE | EventMask::BUTTON_PRESS),
+        )?;
+
+        Ok(Self {
+            overlay,
+            mon_idx,
+            cells: grid(rect.w, rect.h, tag_count),
+            label_height,
+        })
+    }
+
+    pub fn tag_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn tag_at(&self, x: i16, y: i16) -> Option<usize> {
+        self.cells.iter().position(|c| c.has_pointer(x, y))
+    }
+
+    // the part of the tag's cell below its label, where its clients are drawn
+    pub fn body(&self, tag: usize) -> WRect {
+        let cell = self.cells[tag];
+        WRect::new(
+            cell.x,
+            cell.y + self.label_height as i16,
+            cell.w,
+            cell.h.saturating_sub(self.label_height),
+        )
+    }
+
+    pub fn draw_label<C: Connection>(
+        &self,
+        text_renderer: &TextRenderer<C>,
+        theme: &WTheme,
+        tag: usize,
+        label: &str,
+        selected: bool,
+    ) -> Result<(), FontError> {
+        let colors = if selected {
+            (theme.fg_selected, theme.bg_selected)
+        } else {
+            (theme.fg, theme.bg)
+        };
+        let colors = (hex_to_rgba(colors.0), hex_to_rgba(colors.1));
+        let cell = self.cells[tag];
+        let rect = WRect::new(cell.x, cell.y, cell.w, self.label_height);
+        self.overlay
+            .draw_in(text_renderer, rect, label, theme.padding, colors, true)
+    }
+}
+
+// splits a `w` by `h` area into a grid of `count` cells that is as close to square
+// as it gets, filled row by row
+fn grid(w: u16, h: u16, count: usize) -> Vec<WRect> {
+    let cols = (1..=count).find(|c| c * c >= count).unwrap_or(1);
+    let rows = count.div_ceil(cols).max(1);
+    let cell_w = w.saturating_sub(GAP * (cols as u16 + 1)) / cols as u16;
+    let cell_h = h.saturating_sub(GAP * (rows as u16 + 1)) / rows as u16;
+
+    (0..count)
+        .map(|i| {
+            let (col, row) = ((i % cols) as u16, (i / cols) as u16);
+            WRect::new(
+                (GAP + col * (cell_w + GAP)) as i16,
+                (GAP + row * (cell_h + GAP)) as i16,
+                cell_w,
+                cell_h,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_of_nine_tags_is_three_by_three() {
+        let cells = grid(640, 340, 9);
+        assert_eq!(cells.len(), 9);
+        let last = cells[8];
+        assert_eq!((last.x, last.y, last.w, last.h), (430, 230, 200, 100));
+    }
+
+    #[test]
+    fn grid_of_two_tags_is_one_row() {
+        let cells = grid(640, 340, 2);
+        let last = cells[1];
+        assert_eq!((last.x, last.y, last.w, last.h), (325, 10, 305, 320));
+    }
+}
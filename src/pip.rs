@@ -0,0 +1,82 @@
+use wwm_core::util::primitives::WRect;
+
+use crate::command::WDirection;
+
+// the corners of a monitor a picture-in-picture client can be snapped to, clockwise
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WCorner {
+    TopLeft,
+    TopRight,
+    #[default]
+    BottomRight,
+    BottomLeft,
+}
+
+impl WCorner {
+    const CLOCKWISE: [Self; 4] = [
+        Self::TopLeft,
+        Self::TopRight,
+        Self::BottomRight,
+        Self::BottomLeft,
+    ];
+
+    // the adjacent corner, going clockwise for `Next`
+    pub fn adjacent(self, dir: WDirection) -> Self {
+        let i = Self::CLOCKWISE.iter().position(|c| *c == self).unwrap_or(0);
+        let i = match dir {
+            WDirection::Prev => i + 3,
+            WDirection::Next => i + 1,
+        };
+        Self::CLOCKWISE[i % 4]
+    }
+}
+
+// whether the client is a picture-in-picture video by the `(class, title)` rules,
+// which match the class exactly and any title containing theirs
+pub fn matches(rules: &[(&str, &str)], class: &str, title: &str) -> bool {
+    rules
+        .iter()
+        .any(|(c, t)| class.eq_ignore_ascii_case(c) && title.contains(t))
+}
+
+// the size a `w` by `h` client has at `width` with its aspect ratio kept
+pub fn scaled((w, h): (u16, u16), width: u16) -> (u16, u16) {
+    if w == 0 {
+        return (width, h);
+    }
+    (width, (h as f32 * width as f32 / w as f32).round() as u16)
+}
+
+// where a `w` by `h` client goes when snapped into the corner of `area`
+pub fn place(area: WRect, corner: WCorner, (w, h): (u16, u16), margin: u16) -> WRect {
+    let left = area.x + margin as i16;
+    let top = area.y + margin as i16;
+    let right = area.x + area.w as i16 - (w + margin) as i16;
+    let bottom = area.y + area.h as i16 - (h + margin) as i16;
+    let (x, y) = match corner {
+        WCorner::TopLeft => (left, top),
+        WCorner::TopRight => (right, top),
+        WCorner::BottomRight => (right, bottom),
+        WCorner::BottomLeft => (left, bottom),
+    };
+    WRect::new(x, y, w, h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn place_keeps_the_margin_to_the_corner() {
+        let area = WRect::new(1920, 30, 1920, 1050);
+        let rect = place(area, WCorner::BottomRight, scaled((640, 360), 480), 20);
+        assert_eq!((rect.x, rect.y, rect.w, rect.h), (3340, 790, 480, 270));
+    }
+
+    #[test]
+    fn corners_cycle_both_ways() {
+        let corner = WCorner::TopLeft.adjacent(WDirection::Prev);
+        assert_eq!(corner, WCorner::BottomLeft);
+        assert_eq!(corner.adjacent(WDirection::Next), WCorner::TopLeft);
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xproto::{
+            Atom, AtomEnum, ChangeWindowAttributesAux, ClientMessageEvent, ConnectionExt,
+            CreateWindowAux, EventMask, PropMode, Screen, Timestamp, Window, WindowClass,
+        },
+        Event,
+    },
+    rust_connection::ReplyOrIdError,
+    NONE,
+};
+
+// how long a replaced wm gets to let go of the screen
+const REPLACE_TIMEOUT: Duration = Duration::from_secs(3);
+
+// the WM_Sn manager selection, which is owned by the wm of screen n. see section 2.8
+// of the icccm.
+pub struct WSelection {
+    pub atom: Atom,
+    // an unmapped window owning the selection for us
+    pub window: Window,
+}
+
+impl WSelection {
+    // returns `None` if another wm owns the selection and `replace` isn't set.
+    // otherwise the other wm is asked to exit by taking the selection over from it.
+    pub fn acquire<C: Connection>(
+        conn: &C,
+        screen: &Screen,
+        screen_num: usize,
+        manager_atom: Atom,
+        replace: bool,
+    ) -> Result<Option<Self>, ReplyOrIdError> {
+        let name = format!("WM_S{screen_num}");
+        let atom = conn.intern_atom(false, name.as_bytes())?.reply()?.atom;
+
+        let old_owner = conn.get_selection_owner(atom)?.reply()?.owner;
+        if old_owner != NONE {
+            if !replace {
+                return Ok(None);
+            }
+            // to find out when it is gone
+            conn.change_window_attributes(
+                old_owner,
+                &ChangeWindowAttributesAux::new().event_mask(EventMask::STRUCTURE_NOTIFY),
+            )?;
+        }
+
+        let window = conn.generate_id()?;
+        conn.create_window(
+            0,
+            window,
+            screen.root,
+            -1,
+            -1,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            0,
+            &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )?;
+        let time = Self::server_time(conn, window, atom)?;
+        conn.set_selection_owner(window, atom, time)?;
+
+        if old_owner != NONE {
+            Self::wait_for_destroy(conn, old_owner)?;
+        }
+
+        // let clients interested in the wm know that there is a new one
+        let event =
+            ClientMessageEvent::new(32, screen.root, manager_atom, [time, atom, window, 0, 0]);
+        conn.send_event(false, screen.root, EventMask::STRUCTURE_NOTIFY, event)?;
+
+        Ok(Some(Self { atom, window }))
+    }
+
+    // the selection has to be taken with a real timestamp, which is gotten from the
+    // property notify of a change to one of our own windows
+    fn server_time<C: Connection>(
+        conn: &C,
+        window: Window,
+        prop: Atom,
+    ) -> Result<Timestamp, ReplyOrIdError> {
+        conn.change_property(PropMode::APPEND, window, prop, AtomEnum::STRING, 8, 0, &[])?;
+        conn.flush()?;
+        loop {
+            if let Event::PropertyNotify(e) = conn.wait_for_event()? {
+                if e.window == window {
+                    return Ok(e.time);
+                }
+            }
+        }
+    }
+
+    // the replaced wm destroys the window that owned the selection once it has let go
+    fn wait_for_destroy<C: Connection>(conn: &C, window: Window) -> Result<(), ReplyOrIdError> {
+        conn.flush()?;
+        let started = Instant::now();
+        while started.elapsed() < REPLACE_TIMEOUT {
+            match conn.poll_for_event()? {
+                Some(Event::DestroyNotify(e)) if e.window == window => return Ok(()),
+                Some(_) => {}
+                None => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        eprintln!("ERROR: The running WM didn't exit in time");
+        Ok(())
+    }
+}
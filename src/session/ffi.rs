@@ -0,0 +1,88 @@
+// the parts of libSM and libICE needed to be a session management client, see SMlib.h
+
+use libc::{c_char, c_int, c_ulong, c_void};
+
+pub type SmPointer = *mut c_void;
+pub type SmcConn = *mut c_void;
+pub type IceConn = *mut c_void;
+pub type Bool = c_int;
+
+pub const SM_PROTO_MAJOR: c_int = 1;
+pub const SM_PROTO_MINOR: c_int = 0;
+
+pub const SMC_SAVE_YOURSELF_PROC_MASK: c_ulong = 1 << 0;
+pub const SMC_DIE_PROC_MASK: c_ulong = 1 << 1;
+pub const SMC_SAVE_COMPLETE_PROC_MASK: c_ulong = 1 << 2;
+pub const SMC_SHUTDOWN_CANCELLED_PROC_MASK: c_ulong = 1 << 3;
+
+pub const ICE_PROCESS_MESSAGES_IO_ERROR: c_int = 1;
+
+pub type SaveYourselfProc = extern "C" fn(SmcConn, SmPointer, c_int, Bool, c_int, Bool);
+pub type NotifyProc = extern "C" fn(SmcConn, SmPointer);
+
+#[repr(C)]
+pub struct SaveYourselfCallback {
+    pub callback: SaveYourselfProc,
+    pub client_data: SmPointer,
+}
+
+#[repr(C)]
+pub struct NotifyCallback {
+    pub callback: NotifyProc,
+    pub client_data: SmPointer,
+}
+
+#[repr(C)]
+pub struct SmcCallbacks {
+    pub save_yourself: SaveYourselfCallback,
+    pub die: NotifyCallback,
+    pub save_complete: NotifyCallback,
+    pub shutdown_cancelled: NotifyCallback,
+}
+
+#[repr(C)]
+pub struct SmPropValue {
+    pub length: c_int,
+    pub value: SmPointer,
+}
+
+#[repr(C)]
+pub struct SmProp {
+    pub name: *mut c_char,
+    pub type_: *mut c_char,
+    pub num_vals: c_int,
+    pub vals: *mut SmPropValue,
+}
+
+#[link(name = "SM")]
+extern "C" {
+    pub fn SmcOpenConnection(
+        network_ids_list: *mut c_char,
+        context: SmPointer,
+        xsmp_major_rev: c_int,
+        xsmp_minor_rev: c_int,
+        mask: c_ulong,
+        callbacks: *mut SmcCallbacks,
+        previous_id: *const c_char,
+        client_id_ret: *mut *mut c_char,
+        error_length: c_int,
+        error_string_ret: *mut c_char,
+    ) -> SmcConn;
+    pub fn SmcCloseConnection(conn: SmcConn, count: c_int, reason_msgs: *mut *mut c_char) -> c_int;
+    pub fn SmcSetProperties(conn: SmcConn, num_props: c_int, props: *mut *mut SmProp);
+    pub fn SmcSaveYourselfDone(conn: SmcConn, success: Bool);
+    pub fn SmcGetIceConnection(conn: SmcConn) -> IceConn;
+}
+
+pub type IceIOErrorHandler = extern "C" fn(IceConn);
+
+#[link(name = "ICE")]
+extern "C" {
+    pub fn IceSetIOErrorHandler(handler: Option<IceIOErrorHandler>) -> Option<IceIOErrorHandler>;
+    pub fn IceConnectionNumber(conn: IceConn) -> c_int;
+    pub fn IceProcessMessages(
+        conn: IceConn,
+        reply_wait: *mut c_void,
+        reply_ready_ret: *mut Bool,
+    ) -> c_int;
+}
@@ -0,0 +1,213 @@
+mod ffi;
+pub mod state;
+
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString},
+    os::unix::io::RawFd,
+    ptr,
+};
+
+use libc::{c_char, c_int, c_void};
+
+use self::ffi::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WSessionEvent {
+    // the session is being saved, which is answered once our state is written
+    Save,
+    // the session is ending
+    Die,
+}
+
+// a connection to the X session manager. it starts wwm again with the same client id
+// when the session is restored, which is what the saved state is looked up by.
+pub struct WSession {
+    conn: SmcConn,
+    id: String,
+    // filled by the callbacks while messages are processed
+    events: Box<RefCell<Vec<WSessionEvent>>>,
+}
+
+impl WSession {
+    // connects to the session manager in `SESSION_MANAGER`, if there is one.
+    // `previous_id` is the client id wwm was restarted with.
+    pub fn connect(previous_id: Option<&str>) -> Option<Self> {
+        std::env::var_os("SESSION_MANAGER")?;
+
+        // the default handler exits the process when the session manager goes away
+        unsafe { IceSetIOErrorHandler(Some(ignore_io_error)) };
+
+        let events = Box::new(RefCell::new(Vec::new()));
+        let data = &*events as *const RefCell<Vec<WSessionEvent>> as SmPointer;
+        let notify = |callback| NotifyCallback {
+            callback,
+            client_data: data,
+        };
+        // copied by libSM
+        let mut callbacks = SmcCallbacks {
+            save_yourself: SaveYourselfCallback {
+                callback: save_yourself,
+                client_data: data,
+            },
+            die: notify(die),
+            save_complete: notify(ignore),
+            shutdown_cancelled: notify(ignore),
+        };
+        let mask = SMC_SAVE_YOURSELF_PROC_MASK
+            | SMC_DIE_PROC_MASK
+            | SMC_SAVE_COMPLETE_PROC_MASK
+            | SMC_SHUTDOWN_CANCELLED_PROC_MASK;
+
+        let previous_id = previous_id.and_then(|id| CString::new(id).ok());
+        let mut id: *mut c_char = ptr::null_mut();
+        let mut error = [0 as c_char; 256];
+        let conn = unsafe {
+            SmcOpenConnection(
+                ptr::null_mut(),
+                ptr::null_mut(),
+                SM_PROTO_MAJOR,
+                SM_PROTO_MINOR,
+                mask,
+                &mut callbacks,
+                previous_id.as_ref().map_or(ptr::null(), |id| id.as_ptr()),
+                &mut id,
+                error.len() as c_int,
+                error.as_mut_ptr(),
+            )
+        };
+        if conn.is_null() {
+            let error = unsafe { CStr::from_ptr(error.as_ptr()) };
+            eprintln!(
+                "ERROR: Failed to connect to the session manager: {}",
+                error.to_string_lossy()
+            );
+            return None;
+        }
+
+        let id = unsafe {
+            let owned = CStr::from_ptr(id).to_string_lossy().into_owned();
+            libc::free(id as *mut c_void);
+            owned
+        };
+        let session = Self { conn, id, events };
+        session.set_properties();
+        Some(session)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    // negative once the session manager is gone, which poll ignores
+    pub fn fd(&self) -> RawFd {
+        if self.conn.is_null() {
+            return -1;
+        }
+        unsafe { IceConnectionNumber(SmcGetIceConnection(self.conn)) }
+    }
+
+    // handles what the session manager sent and returns what it asked of us
+    pub fn process(&mut self) -> Vec<WSessionEvent> {
+        if self.conn.is_null() {
+            return Vec::new();
+        }
+        let res = unsafe {
+            IceProcessMessages(
+                SmcGetIceConnection(self.conn),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if res == ICE_PROCESS_MESSAGES_IO_ERROR {
+            eprintln!("ERROR: Lost the connection to the session manager");
+            self.close();
+        }
+        std::mem::take(&mut *self.events.borrow_mut())
+    }
+
+    // answers the last `Save`
+    pub fn save_done(&self, success: bool) {
+        if !self.conn.is_null() {
+            unsafe { SmcSaveYourselfDone(self.conn, success as Bool) };
+        }
+    }
+
+    // tells the session manager how to start wwm again
+    fn set_properties(&self) {
+        let program = std::env::args().next().unwrap_or_else(|| "wwm".into());
+        let user = std::env::var("USER").unwrap_or_default();
+        let props: [(&CStr, &CStr, Vec<&str>); 4] = [
+            (c"Program", c"ARRAY8", vec![&program]),
+            (c"UserID", c"ARRAY8", vec![&user]),
+            (
+                c"RestartCommand",
+                c"LISTofARRAY8",
+                vec![&program, "--sm-client-id", &self.id],
+            ),
+            (c"CloneCommand", c"LISTofARRAY8", vec![&program]),
+        ];
+
+        // everything pointed to has to outlive the call
+        let mut values: Vec<Vec<SmPropValue>> = props
+            .iter()
+            .map(|(_, _, vals)| {
+                vals.iter()
+                    .map(|v| SmPropValue {
+                        length: v.len() as c_int,
+                        value: v.as_ptr() as SmPointer,
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut props: Vec<SmProp> = props
+            .iter()
+            .zip(values.iter_mut())
+            .map(|((name, type_, _), vals)| SmProp {
+                name: name.as_ptr() as *mut c_char,
+                type_: type_.as_ptr() as *mut c_char,
+                num_vals: vals.len() as c_int,
+                vals: vals.as_mut_ptr(),
+            })
+            .collect();
+        let mut props: Vec<*mut SmProp> = props.iter_mut().map(|p| p as *mut SmProp).collect();
+        unsafe { SmcSetProperties(self.conn, props.len() as c_int, props.as_mut_ptr()) };
+    }
+
+    fn close(&mut self) {
+        if !self.conn.is_null() {
+            unsafe { SmcCloseConnection(self.conn, 0, ptr::null_mut()) };
+            self.conn = ptr::null_mut();
+        }
+    }
+}
+
+impl Drop for WSession {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+fn push_event(data: SmPointer, event: WSessionEvent) {
+    let events = unsafe { &*(data as *const RefCell<Vec<WSessionEvent>>) };
+    events.borrow_mut().push(event);
+}
+
+extern "C" fn save_yourself(
+    _conn: SmcConn,
+    data: SmPointer,
+    _save_type: c_int,
+    _shutdown: Bool,
+    _interact_style: c_int,
+    _fast: Bool,
+) {
+    push_event(data, WSessionEvent::Save);
+}
+
+extern "C" fn die(_conn: SmcConn, data: SmPointer) {
+    push_event(data, WSessionEvent::Die);
+}
+
+extern "C" fn ignore(_conn: SmcConn, _data: SmPointer) {}
+
+extern "C" fn ignore_io_error(_conn: IceConn) {}
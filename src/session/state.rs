@@ -0,0 +1,183 @@
+use std::{fs, io, path::PathBuf};
+
+use wwm_core::util::WLayout;
+
+use crate::config::tags::WIDTH_FACTOR_RANGE;
+
+// what is written when the session is saved and restored once wwm is started again.
+// clients come back with new windows, so they are recognized by class and title.
+#[derive(Debug, Default, PartialEq)]
+pub struct WSavedState {
+    pub monitors: Vec<WSavedMonitor>,
+    pub clients: Vec<WSavedClient>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct WSavedMonitor {
+    pub tag: usize,
+    pub layout: WLayout,
+    pub width_factor: f32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct WSavedClient {
+    pub monitor: usize,
+    pub tag: usize,
+    pub is_floating: bool,
+    pub class: String,
+    pub title: String,
+}
+
+impl WSavedState {
+    // one tab separated line per monitor and client
+    pub fn serialize(&self) -> String {
+        let mut out = String::new();
+        for m in &self.monitors {
+            let layout = m.layout.name();
+            out += &format!("monitor\t{}\t{layout}\t{}\n", m.tag, m.width_factor);
+        }
+        for c in &self.clients {
+            let (class, title) = (clean(&c.class), clean(&c.title));
+            let floating = c.is_floating as u8;
+            out += &format!(
+                "client\t{}\t{}\t{floating}\t{class}\t{title}\n",
+                c.monitor, c.tag
+            );
+        }
+        out
+    }
+
+    // lines that can't be read are skipped
+    pub fn parse(text: &str) -> Self {
+        let mut state = Self::default();
+        for line in text.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields[..] {
+                ["monitor", tag, layout, width_factor] => {
+                    let (Ok(tag), Some(layout), Some(width_factor)) = (
+                        tag.parse(),
+                        WLayout::from_name(layout),
+                        width_factor.parse::<f32>().ok().filter(|f| !f.is_nan()),
+                    ) else {
+                        continue;
+                    };
+                    let (min, max) = WIDTH_FACTOR_RANGE;
+                    state.monitors.push(WSavedMonitor {
+                        tag,
+                        layout,
+                        width_factor: width_factor.clamp(min, max),
+                    });
+                }
+                ["client", monitor, tag, floating, class, title] => {
+                    let (Ok(monitor), Ok(tag)) = (monitor.parse(), tag.parse()) else {
+                        continue;
+                    };
+                    state.clients.push(WSavedClient {
+                        monitor,
+                        tag,
+                        is_floating: floating == "1",
+                        class: class.to_string(),
+                        title: title.to_string(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        state
+    }
+
+    pub fn load(id: &str) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path(id)?)?))
+    }
+
+    pub fn save(&self, id: &str) -> io::Result<()> {
+        let path = path(id)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.serialize())
+    }
+
+    // removes the saved client the window most likely belongs to. titles change while
+    // programs run, so a client of the same class with another title is taken otherwise.
+    pub fn take_client(&mut self, class: &str, title: &str) -> Option<WSavedClient> {
+        let (class, title) = (clean(class), clean(title));
+        let idx = self
+            .clients
+            .iter()
+            .position(|c| c.class == class && c.title == title)
+            .or_else(|| self.clients.iter().position(|c| c.class == class))?;
+        Some(self.clients.remove(idx))
+    }
+}
+
+// $XDG_DATA_HOME/wwm/session-<id>
+fn path(id: &str) -> io::Result<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+    Ok(data_home.join("wwm").join(format!("session-{id}")))
+}
+
+// the separators of the format can't be part of the text
+fn clean(text: &str) -> String {
+    text.replace(['\t', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(class: &str, title: &str, tag: usize) -> WSavedClient {
+        WSavedClient {
+            monitor: 0,
+            tag,
+            is_floating: false,
+            class: class.to_string(),
+            title: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_reads_what_serialize_writes() {
+        let state = WSavedState {
+            monitors: vec![WSavedMonitor {
+                tag: 2,
+                layout: WLayout::Column,
+                width_factor: 0.6,
+            }],
+            clients: vec![client("firefox", "a\ttab", 3)],
+        };
+        let parsed = WSavedState::parse(&state.serialize());
+        assert_eq!(parsed.monitors, state.monitors);
+        assert_eq!(parsed.clients, vec![client("firefox", "a tab", 3)]);
+    }
+
+    #[test]
+    fn width_factors_stay_in_range() {
+        let parsed = WSavedState::parse("monitor\t0\tcolumn\t1.5\nmonitor\t0\tcolumn\tNaN\n");
+        let factors: Vec<f32> = parsed.monitors.iter().map(|m| m.width_factor).collect();
+        assert_eq!(factors, [WIDTH_FACTOR_RANGE.1]);
+    }
+
+    #[test]
+    fn take_client_prefers_the_same_title() {
+        let mut state = WSavedState {
+            monitors: vec![],
+            clients: vec![
+                client("Alacritty", "vim", 1),
+                client("Alacritty", "htop", 2),
+            ],
+        };
+        assert_eq!(
+            state.take_client("Alacritty", "htop").map(|c| c.tag),
+            Some(2)
+        );
+        assert_eq!(
+            state.take_client("Alacritty", "zsh").map(|c| c.tag),
+            Some(1)
+        );
+        assert_eq!(state.take_client("Alacritty", "zsh"), None);
+    }
+}
@@ -0,0 +1,194 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{de, Deserialize, Deserializer};
+
+use crate::{client::WFocusPolicy, supervisor::WRestart};
+
+// the sections and keys of `wwm.toml`. all of them can be left out, which keeps the
+// setting as it was.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct WFile {
+    pub auto_start: Option<Vec<Vec<String>>>,
+    pub modkey: Option<String>,
+    pub theme: WThemeFile,
+    pub bar: WBarFile,
+    pub tags: WTagsFile,
+    pub gaps: WGapsFile,
+    pub focus: WFocusFile,
+    // keys like `"Mod+Shift+Return"` to commands like `"spawn alacritty"`
+    pub keybinds: BTreeMap<String, String>,
+    pub daemon: Vec<WDaemonFile>,
+    pub module: Vec<WScriptFile>,
+    pub idle: Vec<WIdleFile>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct WThemeFile {
+    pub border_focused: Option<WColor>,
+    pub border_unfocused: Option<WColor>,
+    pub fg: Option<WColor>,
+    pub bg: Option<WColor>,
+    pub fg_selected: Option<WColor>,
+    pub bg_selected: Option<WColor>,
+    pub bg_urgent: Option<WColor>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct WBarFile {
+    pub font: Option<String>,
+    pub font_size: Option<f32>,
+    pub padding: Option<u16>,
+    pub section_padding: Option<i16>,
+    pub tag_width: Option<u16>,
+    // the built-in status modules by name, like `["cpu", "time"]`
+    pub modules: Option<Vec<String>>,
+    // "modules" or "root", for the name of the root window
+    pub status: Option<String>,
+    pub temp_warning: Option<f32>,
+    // "mpris" or "mpd"
+    pub music: Option<String>,
+    pub mpd_address: Option<String>,
+    pub music_width: Option<usize>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct WTagsFile {
+    pub count: Option<usize>,
+    pub names: Option<Vec<String>>,
+    pub width_factor: Option<f32>,
+    pub width_step: Option<f32>,
+    pub main_count: Option<usize>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct WGapsFile {
+    pub inner: Option<u16>,
+    pub outer: Option<u16>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct WFocusFile {
+    pub policy: Option<WFocusPolicy>,
+    // classes like `steam` to the policy for their clients
+    pub rules: BTreeMap<String, WFocusPolicy>,
+}
+
+// a `[[daemon]]` with the `command` to start and when to `restart` it, which is after
+// it fails unless set otherwise
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WDaemonFile {
+    pub command: Vec<String>,
+    #[serde(default = "on_failure")]
+    pub restart: WRestart,
+}
+
+fn on_failure() -> WRestart {
+    WRestart::OnFailure
+}
+
+// a `[[module]]` with the `command` whose output is shown and the `interval` in
+// seconds it is run at
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WScriptFile {
+    pub command: Vec<String>,
+    pub interval: Option<f32>,
+}
+
+// an `[[idle]]` action taken `after` that many seconds without input, which runs the
+// `command` or turns the monitors off with `dpms_off = true`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WIdleFile {
+    pub after: u64,
+    pub command: Option<Vec<String>>,
+    #[serde(default)]
+    pub dpms_off: bool,
+}
+
+// either `0xrrggbb` or `"#rrggbb"`
+pub struct WColor(pub u32);
+
+impl<'de> Deserialize<'de> for WColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(WColorVisitor)
+    }
+}
+
+struct WColorVisitor;
+
+impl<'de> de::Visitor<'de> for WColorVisitor {
+    type Value = WColor;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a color like \"#rrggbb\"")
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<WColor, E> {
+        u32::try_from(v)
+            .ok()
+            .filter(|c| *c <= 0xffffff)
+            .map(WColor)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Signed(v), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<WColor, E> {
+        v.strip_prefix('#')
+            .filter(|hex| hex.len() == 6)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .map(WColor)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_all_of_toml() {
+        let src = r##"
+            # dotted keys, inline tables and multi-line strings
+            bar.font = """Iosevka \
+                Term"""
+            gaps = { inner = 4, outer = 8 }
+            focus.rules = { steam = "never" }
+
+            [theme]
+            bg = 0x23_26_34
+            fg = "#c6d0f5"
+
+            [[daemon]]
+            command = ["picom"]
+
+            [[daemon]]
+            command = ["dunst"]
+            restart = "always"
+        "##;
+        let file: WFile = toml::from_str(src).unwrap();
+        assert_eq!(file.bar.font.as_deref(), Some("Iosevka Term"));
+        assert_eq!((file.gaps.inner, file.gaps.outer), (Some(4), Some(8)));
+        assert_eq!(file.focus.rules.get("steam"), Some(&WFocusPolicy::Never));
+        assert_eq!(file.theme.bg.map(|c| c.0), Some(0x232634));
+        assert_eq!(file.theme.fg.map(|c| c.0), Some(0xc6d0f5));
+        let restarts: Vec<WRestart> = file.daemon.iter().map(|d| d.restart).collect();
+        assert_eq!(restarts, [WRestart::OnFailure, WRestart::Always]);
+    }
+
+    #[test]
+    fn mistakes_point_at_the_line() {
+        let err = toml::from_str::<WFile>("[theme]\nbg = \"#2326\"")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("line 2"), "{err}");
+        assert!(toml::from_str::<WFile>("[bar]\nfonts = \"x\"").is_err());
+        assert!(toml::from_str::<WFile>("[gaps]\ninner = -1").is_err());
+    }
+}
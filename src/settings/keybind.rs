@@ -0,0 +1,170 @@
+use wwm_core::util::{keysym, WLayout};
+use x11rb::protocol::xproto::ModMask;
+
+use super::WConfigError;
+use crate::{
+    command::{WAxis, WDirection, WKeyCommand, WMediaCommand},
+    keyboard::keybind::WKeybind,
+};
+
+// what a keybind in the config file does to the keybinds
+pub enum WBindChange {
+    Bind(WKeybind),
+    // the command "none" takes the default bind off of the keys
+    Unbind(ModMask, u32),
+}
+
+// a keybind written like `"Mod+Shift+Return" = "spawn alacritty"`, where `Mod` is
+// `modkey`, the modifier of the default keybinds
+pub fn parse(combo: &str, command: &str, modkey: ModMask) -> Result<WBindChange, WConfigError> {
+    let (mods, keysym) = keysym::parse_combo(combo, modkey)?;
+    if command.trim() == "none" {
+        return Ok(WBindChange::Unbind(mods, keysym));
+    }
+    let action = parse_command(command).ok_or_else(|| WConfigError::Command(command.into()))?;
+    Ok(WBindChange::Bind(WKeybind::new(mods, keysym, action)))
+}
+
+// a command and its arguments, like `layout column`. tags are counted from 1 like
+// they are labeled in the bar, and the arguments of `spawn` are split on whitespace.
+pub fn parse_command(s: &str) -> Option<WKeyCommand> {
+    let mut words = s.split_whitespace();
+    let name = words.next()?;
+    let args: Vec<&str> = words.collect();
+    command(name, &args)
+}
+
+// the names `command` takes, which `{"query": "version"}` lists
+pub const COMMANDS: &[&str] = &[
+    "spawn",
+    "destroy",
+    "exit",
+    "zoom",
+    "focus-urgent",
+    "unfloat",
+    "fullscreen",
+    "resize-mode",
+    "next-kb-layout",
+    "hint-mode",
+    "overview",
+    "toggle-title-bars",
+    "focus-client",
+    "move-client",
+    "focus-monitor",
+    "adjust-main-width",
+    "inc-nmaster",
+    "adjust-client-factor",
+    "adjust-gaps",
+    "move-client-to-monitor",
+    "cycle-tag",
+    "resize-pip",
+    "move-pip",
+    "select-tag",
+    "move-client-to-tag",
+    "tag-preview",
+    "move-float",
+    "resize-float",
+    "layout",
+    "media",
+];
+
+// a command by its name, like `layout`, and its arguments, like `["column"]`
+pub fn command(name: &str, args: &[&str]) -> Option<WKeyCommand> {
+    let dir = || match args[..] {
+        ["prev"] => Some(WDirection::Prev),
+        ["next"] => Some(WDirection::Next),
+        _ => None,
+    };
+    let tag = || match args[..] {
+        [n] => n.parse::<usize>().ok()?.checked_sub(1),
+        _ => None,
+    };
+    let axis_dir = || {
+        let axis = match args.first() {
+            Some(&"horizontal") => WAxis::Horizontal,
+            Some(&"vertical") => WAxis::Vertical,
+            _ => return None,
+        };
+        let dir = match args.get(1) {
+            Some(&"prev") => WDirection::Prev,
+            Some(&"next") => WDirection::Next,
+            _ => return None,
+        };
+        Some((axis, dir))
+    };
+
+    let command = match name {
+        "spawn" if !args.is_empty() => WKeyCommand::spawn(args),
+        "destroy" => WKeyCommand::Destroy,
+        "exit" => WKeyCommand::Exit,
+        "zoom" => WKeyCommand::Zoom,
+        "focus-urgent" => WKeyCommand::FocusUrgent,
+        "unfloat" => WKeyCommand::UnFloat,
+        "fullscreen" => WKeyCommand::Fullscreen,
+        "resize-mode" => WKeyCommand::ResizeMode,
+        "next-kb-layout" => WKeyCommand::NextKbLayout,
+        "hint-mode" => WKeyCommand::HintMode,
+        "overview" => WKeyCommand::Overview,
+        "toggle-title-bars" => WKeyCommand::ToggleTitleBars,
+        "focus-client" => WKeyCommand::FocusClient(dir()?),
+        "move-client" => WKeyCommand::MoveClient(dir()?),
+        "focus-monitor" => WKeyCommand::FocusMonitor(dir()?),
+        "adjust-main-width" => WKeyCommand::AdjustMainWidth(dir()?),
+        "inc-nmaster" => WKeyCommand::IncNMaster(dir()?),
+        "adjust-client-factor" => WKeyCommand::AdjustClientFactor(dir()?),
+        "adjust-gaps" => WKeyCommand::AdjustGaps(dir()?),
+        "move-client-to-monitor" => WKeyCommand::MoveClientToMonitor(dir()?),
+        "cycle-tag" => WKeyCommand::CycleTag(dir()?),
+        "resize-pip" => WKeyCommand::ResizePip(dir()?),
+        "move-pip" => WKeyCommand::MovePip(dir()?),
+        "select-tag" => WKeyCommand::SelectTag(tag()?),
+        "move-client-to-tag" => WKeyCommand::MoveClientToTag(tag()?),
+        "tag-preview" => WKeyCommand::TagPreview(tag()?),
+        "move-float" => {
+            let (axis, dir) = axis_dir()?;
+            WKeyCommand::MoveFloat(axis, dir)
+        }
+        "resize-float" => {
+            let (axis, dir) = axis_dir()?;
+            WKeyCommand::ResizeFloat(axis, dir)
+        }
+        "layout" => match args[..] {
+            [name] => WKeyCommand::Layout(WLayout::from_name(name)?),
+            _ => return None,
+        },
+        "media" => WKeyCommand::Media(match args[..] {
+            ["volume-up"] => WMediaCommand::VolumeUp,
+            ["volume-down"] => WMediaCommand::VolumeDown,
+            ["toggle-mute"] => WMediaCommand::ToggleMute,
+            ["brightness-up"] => WMediaCommand::BrightnessUp,
+            ["brightness-down"] => WMediaCommand::BrightnessDown,
+            ["play-pause"] => WMediaCommand::PlayPause,
+            ["next"] => WMediaCommand::Next,
+            ["prev"] => WMediaCommand::Prev,
+            _ => return None,
+        }),
+        _ => return None,
+    };
+    Some(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_listed_command_exists() {
+        let args: [&[&str]; 6] = [
+            &[],
+            &["next"],
+            &["3"],
+            &["column"],
+            &["horizontal", "next"],
+            &["volume-up"],
+        ];
+        for name in COMMANDS {
+            let exists = args.iter().any(|a| command(name, a).is_some());
+            assert!(exists, "`{name}` isn't a command");
+        }
+    }
+}
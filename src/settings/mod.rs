@@ -0,0 +1,462 @@
+pub mod file;
+pub mod keybind;
+pub mod resources;
+pub mod watch;
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+use wwm_bar::{
+    status_module::{WModuleKind, WModuleOptions, WModuleSpec, WMusicSource},
+    status_worker::WStatusSource,
+};
+use wwm_core::{
+    layout::WGaps,
+    text::RasterizedFont,
+    util::{
+        bar,
+        keysym::{self, ComboError},
+    },
+};
+use x11rb::{connection::Connection, protocol::xproto::ModMask};
+
+use crate::{
+    client::WFocusPolicy,
+    config::{
+        auto_start::AUTO_START_COMMANDS,
+        commands, focus, idle,
+        tags::{
+            MAIN_CLIENT_COUNT, MAIN_CLIENT_WIDTH_PERCENTAGE, TAG_CAP, TAG_NAMES,
+            WIDTH_ADJUSTMENT_FACTOR, WIDTH_FACTOR_RANGE, WIDTH_STEP_RANGE,
+        },
+        theme::{self, window::MAX_GAP},
+    },
+    idle::WIdleAction,
+    keyboard::keybind::WKeybind,
+    supervisor::{WAutoStart, WRestart},
+};
+
+use self::{file::WFile, keybind::WBindChange};
+
+#[derive(Error, Debug)]
+pub enum WConfigError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("`{0}` should be {1}")]
+    Type(String, &'static str),
+    #[error("{0}")]
+    Combo(#[from] ComboError),
+    #[error("unknown status module `{0}`")]
+    Module(String),
+    #[error("unknown command `{0}`")]
+    Command(String),
+}
+
+// the colors used across the bar, the overlays and the window borders
+#[derive(Debug, Clone, Copy)]
+pub struct WTheme {
+    pub border_focused: u32,
+    pub border_unfocused: u32,
+    pub fg: u32,
+    pub bg: u32,
+    pub fg_selected: u32,
+    pub bg_selected: u32,
+    pub bg_urgent: u32,
+    // padding around the text of the bar and the overlays, before scaling
+    pub padding: u16,
+}
+
+// the settings read from the `wwm.*` X resources and `~/.config/wwm/wwm.toml` at
+// startup. anything neither sets keeps its default from `config.rs`.
+pub struct WConfig {
+    // the file the settings were read from, if there is one to read them from
+    pub path: Option<PathBuf>,
+    pub theme: WTheme,
+    pub font: String,
+    pub font_size: f32,
+    pub section_padding: i16,
+    pub tag_width: u16,
+    pub modules: Vec<WModuleSpec>,
+    pub status_source: WStatusSource,
+    pub temp_warning: f32,
+    pub music: WMusicSource,
+    pub mpd_address: String,
+    pub music_width: usize,
+    // how many of the `TAG_CAP` tags are in use
+    pub tag_count: usize,
+    pub tag_names: Vec<String>,
+    pub width_factor: f32,
+    pub width_step: f32,
+    pub main_count: usize,
+    pub gaps: WGaps,
+    // whether new clients take the focus, unless a rule for their class says otherwise
+    pub focus: WFocusPolicy,
+    pub focus_rules: Vec<(String, WFocusPolicy)>,
+    // seconds without input after which each action is taken, in ascending order
+    pub idle_actions: Vec<(u64, WIdleAction)>,
+    pub auto_start: Vec<WAutoStart>,
+    // the modifier most keybinds and mousebinds share
+    pub modkey: ModMask,
+    pub keybinds: Vec<WKeybind>,
+}
+
+impl Default for WConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            theme: WTheme {
+                border_focused: theme::window::BORDER_FOCUSED,
+                border_unfocused: theme::window::BORDER_UNFOCUSED,
+                fg: theme::bar::FG,
+                bg: theme::bar::BG,
+                fg_selected: theme::bar::FG_SELECTED,
+                bg_selected: theme::bar::BG_SELECTED,
+                bg_urgent: theme::bar::BG_URGENT,
+                padding: theme::bar::PADDING,
+            },
+            font: theme::bar::FONT.to_string(),
+            font_size: theme::bar::FONT_SIZE,
+            section_padding: theme::bar::SECTION_PADDING,
+            tag_width: theme::bar::TAG_WIDTH,
+            modules: theme::bar::MODULES.to_vec(),
+            status_source: theme::bar::STATUS_SOURCE,
+            temp_warning: theme::bar::TEMP_WARNING,
+            music: theme::bar::MUSIC_SOURCE,
+            mpd_address: theme::bar::MPD_ADDRESS.to_string(),
+            music_width: theme::bar::MUSIC_WIDTH,
+            tag_count: TAG_CAP,
+            tag_names: owned(TAG_NAMES),
+            width_factor: MAIN_CLIENT_WIDTH_PERCENTAGE,
+            width_step: WIDTH_ADJUSTMENT_FACTOR,
+            main_count: MAIN_CLIENT_COUNT,
+            gaps: WGaps {
+                inner: theme::window::INNER_GAP,
+                outer: theme::window::OUTER_GAP,
+            },
+            focus: focus::POLICY,
+            focus_rules: focus::RULES
+                .iter()
+                .map(|(class, policy)| (class.to_string(), *policy))
+                .collect(),
+            idle_actions: idle::actions(),
+            auto_start: AUTO_START_COMMANDS
+                .iter()
+                .map(|(cmd, restart)| WAutoStart::new(owned(cmd), *restart))
+                .collect(),
+            modkey: commands::MOD,
+            keybinds: commands::setup_keybinds(),
+        }
+    }
+}
+
+impl WConfig {
+    // the defaults are used for whatever can't be read, after logging why
+    pub fn load<C: Connection>(conn: &C, path: Option<PathBuf>) -> Self {
+        let mut config = Self::default();
+        resources::apply(&mut config, conn);
+        let Some(path) = path else {
+            return config;
+        };
+        match config.apply_file(&path) {
+            Ok(errors) => {
+                for e in errors {
+                    eprintln!("ERROR: Invalid setting in {}: {e}", path.display());
+                }
+            }
+            Err(WConfigError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => eprintln!("ERROR: Failed to load {}: {e}", path.display()),
+        }
+        config.path = Some(path);
+        config
+    }
+
+    // applies the settings of the file at `path`, returning the ones that were wrong.
+    // the file as a whole fails when it can't be read or isn't laid out like `WFile`.
+    pub fn apply_file(&mut self, path: &Path) -> Result<Vec<WConfigError>, WConfigError> {
+        let file = toml::from_str(&fs::read_to_string(path)?)?;
+        Ok(self.apply(file))
+    }
+
+    fn apply(&mut self, file: WFile) -> Vec<WConfigError> {
+        let mut errors = Vec::new();
+        // the keybinds below are read with the new modifier already
+        if let Some(name) = file.modkey {
+            match keysym::parse_mod(&name, self.modkey) {
+                Some(modkey) => self.set_modkey(modkey),
+                None => errors.push(ComboError::Modifier(name).into()),
+            }
+        }
+        if let Some(cmds) = file.auto_start {
+            let cmds = cmds.into_iter().map(|cmd| command("auto_start", cmd));
+            match cmds.collect::<Result<Vec<_>, _>>() {
+                Ok(cmds) => {
+                    self.auto_start = cmds
+                        .into_iter()
+                        .map(|cmd| WAutoStart::new(cmd, WRestart::Never))
+                        .collect()
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        for d in file.daemon {
+            match command("daemon.command", d.command) {
+                Ok(cmd) => self.auto_start.push(WAutoStart::new(cmd, d.restart)),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        let (colors, t) = (&mut self.theme, file.theme);
+        for (field, color) in [
+            (&mut colors.border_focused, t.border_focused),
+            (&mut colors.border_unfocused, t.border_unfocused),
+            (&mut colors.fg, t.fg),
+            (&mut colors.bg, t.bg),
+            (&mut colors.fg_selected, t.fg_selected),
+            (&mut colors.bg_selected, t.bg_selected),
+            (&mut colors.bg_urgent, t.bg_urgent),
+        ] {
+            set(field, color.map(|c| c.0));
+        }
+
+        let bar = file.bar;
+        set(&mut self.font, bar.font);
+        set(&mut self.font_size, bar.font_size);
+        set(&mut self.theme.padding, bar.padding);
+        set(&mut self.section_padding, bar.section_padding);
+        set(&mut self.tag_width, bar.tag_width);
+        set(&mut self.temp_warning, bar.temp_warning);
+        set(&mut self.mpd_address, bar.mpd_address);
+        set(&mut self.music_width, bar.music_width);
+        if let Some(names) = bar.modules {
+            match modules(&names) {
+                Ok(modules) => {
+                    // script modules from `[[module]]` tables stay after the built-in ones
+                    self.modules
+                        .retain(|m| matches!(m.kind, WModuleKind::Script(_)));
+                    self.modules.splice(0..0, modules);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        for m in file.module {
+            let interval = m.interval.map_or(theme::bar::STATUS_INTERVAL, |secs| {
+                (secs.max(0.1) * 1000.0) as u64
+            });
+            match command("module.command", m.command) {
+                Ok(cmd) => self
+                    .modules
+                    .push(WModuleSpec::new(WModuleKind::Script(cmd), interval)),
+                Err(e) => errors.push(e),
+            }
+        }
+        match bar.status.as_deref().map(status_source).transpose() {
+            Ok(status) => set(&mut self.status_source, status),
+            Err(e) => errors.push(e),
+        }
+        match bar.music.as_deref().map(music_source).transpose() {
+            Ok(music) => set(&mut self.music, music),
+            Err(e) => errors.push(e),
+        }
+
+        let (min_step, max_step) = WIDTH_STEP_RANGE;
+        let (min_factor, max_factor) = WIDTH_FACTOR_RANGE;
+        let tags = file.tags;
+        set(&mut self.tag_count, tags.count.map(|c| c.clamp(1, TAG_CAP)));
+        set(&mut self.tag_names, tags.names);
+        set(
+            &mut self.width_factor,
+            tags.width_factor.map(|f| f.clamp(min_factor, max_factor)),
+        );
+        set(
+            &mut self.width_step,
+            tags.width_step.map(|s| s.clamp(min_step, max_step)),
+        );
+        set(&mut self.main_count, tags.main_count);
+        set(
+            &mut self.gaps.inner,
+            file.gaps.inner.map(|g| g.min(MAX_GAP)),
+        );
+        set(
+            &mut self.gaps.outer,
+            file.gaps.outer.map(|g| g.min(MAX_GAP)),
+        );
+
+        for i in file.idle {
+            let action = match (i.command, i.dpms_off) {
+                (None, true) => Ok(WIdleAction::DpmsOff),
+                (Some(cmd), false) => command("idle.command", cmd).map(WIdleAction::Run),
+                _ => Err(WConfigError::Type(
+                    "idle".into(),
+                    "a table with either a command or dpms_off = true",
+                )),
+            };
+            match action {
+                Ok(action) => self.idle_actions.push((i.after, action)),
+                Err(e) => errors.push(e),
+            }
+        }
+        self.idle_actions.sort_by_key(|(secs, _)| *secs);
+
+        set(&mut self.focus, file.focus.policy);
+        for (class, policy) in file.focus.rules {
+            self.focus_rules
+                .retain(|(c, _)| !c.eq_ignore_ascii_case(&class));
+            self.focus_rules.push((class, policy));
+        }
+
+        for (combo, cmd) in file.keybinds {
+            match keybind::parse(&combo, &cmd, self.modkey) {
+                Ok(change) => self.bind(change),
+                Err(e) => errors.push(e),
+            }
+        }
+        errors
+    }
+
+    pub fn module_options(&self) -> WModuleOptions {
+        WModuleOptions {
+            temp_warning: self.temp_warning,
+            warning_color: self.theme.bg_urgent,
+            music: self.music,
+            mpd_address: self.mpd_address.clone(),
+            music_width: self.music_width,
+        }
+    }
+
+    // the policy for a new client of `class`
+    pub fn focus_policy(&self, class: &str) -> WFocusPolicy {
+        self.focus_rules
+            .iter()
+            .find(|(c, _)| class.eq_ignore_ascii_case(c))
+            .map_or(self.focus, |(_, policy)| *policy)
+    }
+
+    pub fn tag_name(&self, tag: usize) -> String {
+        bar::tag_label(&self.tag_names, tag)
+    }
+
+    // moves the default keybinds over to `modkey`. the binds of the file are read with
+    // it already.
+    fn set_modkey(&mut self, modkey: ModMask) {
+        for kb in &mut self.keybinds {
+            kb.mods = keysym::replace_mod(kb.mods, self.modkey, modkey);
+        }
+        self.modkey = modkey;
+    }
+
+    // a bind from the file replaces the default ones on the same key
+    fn bind(&mut self, change: WBindChange) {
+        let (mods, keysym) = match &change {
+            WBindChange::Bind(kb) => (kb.mods, kb.keysym),
+            WBindChange::Unbind(mods, keysym) => (*mods, *keysym),
+        };
+        self.keybinds
+            .retain(|kb| kb.mods != mods || kb.keysym != keysym);
+        if let WBindChange::Bind(kb) = change {
+            self.keybinds.push(kb);
+        }
+    }
+}
+
+const FILE_NAME: &str = "wwm.toml";
+
+// reads the file at `path` the way it is read at startup, printing every mistake in
+// it rather than skipping over them. returns whether there were none.
+pub fn check(path: &Path) -> bool {
+    let mut config = WConfig::default();
+    let errors = match config.apply_file(path) {
+        Ok(errors) => errors,
+        Err(e) => {
+            eprintln!("{}: {e}", path.display());
+            return false;
+        }
+    };
+    for e in &errors {
+        eprintln!("{}: {e}", path.display());
+    }
+    let font = RasterizedFont::load(&config.font, config.font_size, 1.0);
+    if let Err(e) = &font {
+        eprintln!("{}: font `{}`: {e}", path.display(), config.font);
+    }
+    let ok = errors.is_empty() && font.is_ok();
+    if ok {
+        println!("{}: ok", path.display());
+    }
+    ok
+}
+
+// where the config file is read from when no other path is given
+pub fn path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("wwm").join(FILE_NAME))
+}
+
+fn set<T>(field: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        *field = value;
+    }
+}
+
+// the built-in status modules by name, like `["cpu", "time"]`
+fn modules(names: &[String]) -> Result<Vec<WModuleSpec>, WConfigError> {
+    names
+        .iter()
+        .map(|name| {
+            let kind = match name.as_str() {
+                "vol" => WModuleKind::Vol,
+                "ram" => WModuleKind::Ram,
+                "cpu" => WModuleKind::Cpu,
+                "date" => WModuleKind::Date,
+                "time" => WModuleKind::Time,
+                "temp" => WModuleKind::Temp,
+                "music" => WModuleKind::Music,
+                _ => return Err(WConfigError::Module(name.clone())),
+            };
+            let default = theme::bar::MODULES.iter().find(|m| m.kind == kind);
+            Ok(default
+                .cloned()
+                .unwrap_or(WModuleSpec::new(kind, theme::bar::STATUS_INTERVAL)))
+        })
+        .collect()
+}
+
+fn status_source(name: &str) -> Result<WStatusSource, WConfigError> {
+    match name {
+        "modules" => Ok(WStatusSource::Modules),
+        "root" => Ok(WStatusSource::RootName),
+        _ => Err(WConfigError::Type(
+            "bar.status".into(),
+            "either \"modules\" or \"root\"",
+        )),
+    }
+}
+
+fn music_source(name: &str) -> Result<WMusicSource, WConfigError> {
+    match name {
+        "mpris" => Ok(WMusicSource::Mpris),
+        "mpd" => Ok(WMusicSource::Mpd),
+        _ => Err(WConfigError::Type(
+            "bar.music".into(),
+            "either \"mpris\" or \"mpd\"",
+        )),
+    }
+}
+
+fn owned(strings: &[&str]) -> Vec<String> {
+    strings.iter().map(|s| s.to_string()).collect()
+}
+
+fn command(key: &str, args: Vec<String>) -> Result<Vec<String>, WConfigError> {
+    if args.is_empty() {
+        return Err(WConfigError::Type(key.to_string(), "a command"));
+    }
+    Ok(args)
+}
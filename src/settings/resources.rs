@@ -0,0 +1,65 @@
+use toml::{Table, Value};
+use x11rb::{connection::Connection, resource_manager};
+
+use super::{file::WFile, WConfig};
+
+// the X resources that stand in for a setting of the config file, so that the colors
+// can follow an `.Xresources` or pywal palette
+const RESOURCES: [(&str, &str, &str); 9] = [
+    ("wwm.border.focused", "theme", "border_focused"),
+    ("wwm.border.unfocused", "theme", "border_unfocused"),
+    ("wwm.bar.fg", "theme", "fg"),
+    ("wwm.bar.bg", "theme", "bg"),
+    ("wwm.bar.fgSelected", "theme", "fg_selected"),
+    ("wwm.bar.bgSelected", "theme", "bg_selected"),
+    ("wwm.bar.bgUrgent", "theme", "bg_urgent"),
+    ("wwm.bar.font", "bar", "font"),
+    ("wwm.bar.fontSize", "bar", "font_size"),
+];
+
+// applies the `wwm.*` resources of the resource database. the config file is read
+// after this, so the settings in it win.
+pub fn apply<C: Connection>(config: &mut WConfig, conn: &C) {
+    let db = match resource_manager::new_from_default(conn) {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("ERROR: Failed to read the X resources: {e}");
+            return;
+        }
+    };
+    for (name, table, key) in RESOURCES {
+        let Some(s) = db.get_string(name, &class(name)) else {
+            continue;
+        };
+        // resources are untyped, so anything that reads as a number is one
+        let s = s.trim();
+        let value = match s.parse() {
+            Ok(f) => Value::Float(f),
+            Err(_) => Value::String(s.to_string()),
+        };
+        // read as a file with just this one setting in it
+        let section = Table::from_iter([(key.to_string(), value)]);
+        let file = Value::Table(Table::from_iter([(table.to_string(), section.into())]));
+        let errors = match file.try_into::<WFile>() {
+            Ok(file) => config.apply(file),
+            Err(e) => vec![e.into()],
+        };
+        for e in errors {
+            eprintln!("ERROR: Invalid X resource {name}: {e}");
+        }
+    }
+}
+
+// `wwm.bar.fontSize` is of the class `Wwm.Bar.FontSize`
+fn class(name: &str) -> String {
+    name.split('.')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
@@ -0,0 +1,119 @@
+use std::{
+    ffi::CString,
+    io, mem,
+    os::unix::{ffi::OsStrExt, io::RawFd},
+    path::Path,
+    ptr,
+};
+
+use crate::signal;
+
+// tells when the config file should be read again, which is on a SIGHUP or once the
+// file has been saved. both fds become readable then.
+pub struct WConfigWatch {
+    signal: RawFd,
+    inotify: RawFd,
+    file_name: Vec<u8>,
+}
+
+impl WConfigWatch {
+    pub fn new(path: Option<&Path>) -> io::Result<Self> {
+        let signal = signal::pipe(libc::SIGHUP)?;
+        let inotify = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if inotify < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // the directory is watched since editors tend to replace the file instead of
+        // writing to it. without one there is only SIGHUP.
+        if let Some(dir) = path.and_then(|p| p.parent()) {
+            if let Ok(dir) = CString::new(dir.as_os_str().as_bytes()) {
+                let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO;
+                unsafe { libc::inotify_add_watch(inotify, dir.as_ptr(), mask) };
+            }
+        }
+
+        let file_name = path.and_then(|p| p.file_name()).unwrap_or_default();
+        Ok(Self {
+            signal,
+            inotify,
+            file_name: file_name.as_bytes().to_vec(),
+        })
+    }
+
+    pub fn fds(&self) -> [RawFd; 2] {
+        [self.signal, self.inotify]
+    }
+
+    // drains both fds and returns whether the config file should be read again
+    pub fn take_change(&self) -> bool {
+        let mut buf = [0u8; 4096];
+        let mut changed = signal::drain(self.signal);
+        loop {
+            let n = read(self.inotify, &mut buf);
+            if n <= 0 {
+                break;
+            }
+            changed |= names_file(&buf[..n as usize], &self.file_name);
+        }
+        changed
+    }
+}
+
+impl Drop for WConfigWatch {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.signal);
+            libc::close(self.inotify);
+        }
+    }
+}
+
+fn read(fd: RawFd, buf: &mut [u8]) -> isize {
+    unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) }
+}
+
+// whether any of the inotify events in `buf` is about the file named `file_name`
+fn names_file(buf: &[u8], file_name: &[u8]) -> bool {
+    let header = mem::size_of::<libc::inotify_event>();
+    let mut offset = 0;
+    while offset + header <= buf.len() {
+        let event: libc::inotify_event =
+            unsafe { ptr::read_unaligned(buf[offset..].as_ptr().cast()) };
+        let start = offset + header;
+        let end = (start + event.len as usize).min(buf.len());
+        // the name is padded with nul bytes
+        let name = buf[start..end].split(|b| *b == 0).next();
+        if name == Some(file_name) {
+            return true;
+        }
+        offset = end;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(name: &[u8], len: u32) -> Vec<u8> {
+        let event = libc::inotify_event {
+            wd: 1,
+            mask: libc::IN_CLOSE_WRITE,
+            cookie: 0,
+            len,
+        };
+        let header: [u8; mem::size_of::<libc::inotify_event>()] = unsafe { mem::transmute(event) };
+        let mut buf = header.to_vec();
+        buf.extend(name);
+        buf.resize(header.len() + len as usize, 0);
+        buf
+    }
+
+    #[test]
+    fn only_events_about_the_config_file_count() {
+        let mut buf = event(b"wwm.toml.swp", 16);
+        assert!(!names_file(&buf, b"wwm.toml"));
+        buf.extend(event(b"wwm.toml", 16));
+        assert!(names_file(&buf, b"wwm.toml"));
+    }
+}
@@ -0,0 +1,46 @@
+use std::{
+    io, mem,
+    os::unix::io::RawFd,
+    ptr,
+    sync::atomic::{AtomicI32, Ordering},
+};
+
+// the write ends of the pipes the signals wake the event loop through, by signal number
+static PIPES: [AtomicI32; 32] = [const { AtomicI32::new(-1) }; 32];
+
+extern "C" fn on_signal(signal: libc::c_int) {
+    let fd = PIPES[signal as usize].load(Ordering::Relaxed);
+    unsafe {
+        libc::write(fd, [0u8].as_ptr().cast(), 1);
+    }
+}
+
+// a pipe that becomes readable whenever `signal` arrives. a handler rather than a
+// signalfd, since a blocked signal would stay blocked in every program we spawn.
+pub fn pipe(signal: libc::c_int) -> io::Result<RawFd> {
+    let mut pipe = [-1; 2];
+    if unsafe { libc::pipe2(pipe.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    PIPES[signal as usize].store(pipe[1], Ordering::Relaxed);
+    unsafe {
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        if libc::sigaction(signal, &action, ptr::null_mut()) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(pipe[0])
+}
+
+// empties the pipe and returns whether the signal arrived since it was last drained
+pub fn drain(fd: RawFd) -> bool {
+    let mut buf = [0u8; 64];
+    let mut arrived = false;
+    while unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) } > 0 {
+        arrived = true;
+    }
+    arrived
+}
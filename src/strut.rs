@@ -0,0 +1,112 @@
+use wwm_core::util::primitives::WRect;
+
+// the space a window, like an external panel or dock, reserves along the edges of the
+// screen. see _NET_WM_STRUT_PARTIAL in the ewmh spec.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WStrut {
+    left: u32,
+    right: u32,
+    top: u32,
+    bottom: u32,
+    // the range along each edge that is reserved, which is all of it for _NET_WM_STRUT
+    left_start_y: u32,
+    left_end_y: u32,
+    right_start_y: u32,
+    right_end_y: u32,
+    top_start_x: u32,
+    top_end_x: u32,
+    bottom_start_x: u32,
+    bottom_end_x: u32,
+}
+
+impl WStrut {
+    // takes the twelve values of _NET_WM_STRUT_PARTIAL or the four of _NET_WM_STRUT
+    pub fn parse(values: &[u32]) -> Option<Self> {
+        match *values {
+            [left, right, top, bottom, left_start_y, left_end_y, right_start_y, right_end_y, top_start_x, top_end_x, bottom_start_x, bottom_end_x, ..] => {
+                Some(Self {
+                    left,
+                    right,
+                    top,
+                    bottom,
+                    left_start_y,
+                    left_end_y,
+                    right_start_y,
+                    right_end_y,
+                    top_start_x,
+                    top_end_x,
+                    bottom_start_x,
+                    bottom_end_x,
+                })
+            }
+            [left, right, top, bottom] => Some(Self {
+                left,
+                right,
+                top,
+                bottom,
+                left_end_y: u32::MAX,
+                right_end_y: u32::MAX,
+                top_end_x: u32::MAX,
+                bottom_end_x: u32::MAX,
+                ..Default::default()
+            }),
+            _ => None,
+        }
+    }
+
+    // what is left of `area` once the strut is reserved on a screen `w` by `h`
+    pub fn apply(&self, area: WRect, (w, h): (u16, u16)) -> WRect {
+        let (mut x0, mut y0) = (area.x as i64, area.y as i64);
+        let (mut x1, mut y1) = (x0 + area.w as i64, y0 + area.h as i64);
+        let overlaps =
+            |start: u32, end: u32, from: i64, to: i64| (start as i64) < to && (end as i64) >= from;
+
+        if self.left > 0 && overlaps(self.left_start_y, self.left_end_y, y0, y1) {
+            x0 = x0.max(self.left as i64);
+        }
+        if self.right > 0 && overlaps(self.right_start_y, self.right_end_y, y0, y1) {
+            x1 = x1.min(w as i64 - self.right as i64);
+        }
+        if self.top > 0 && overlaps(self.top_start_x, self.top_end_x, x0, x1) {
+            y0 = y0.max(self.top as i64);
+        }
+        if self.bottom > 0 && overlaps(self.bottom_start_x, self.bottom_end_x, x0, x1) {
+            y1 = y1.min(h as i64 - self.bottom as i64);
+        }
+
+        // a strut can't take up all of the area
+        if x1 <= x0 || y1 <= y0 {
+            return area;
+        }
+        WRect::new(x0 as i16, y0 as i16, (x1 - x0) as u16, (y1 - y0) as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: (u16, u16) = (3840, 1080);
+
+    fn dims(r: WRect) -> (i16, i16, u16, u16) {
+        (r.x, r.y, r.w, r.h)
+    }
+
+    #[test]
+    fn strut_only_affects_the_monitors_along_its_range() {
+        // a panel at the bottom of the right monitor
+        let strut = WStrut::parse(&[0, 0, 0, 40, 0, 0, 0, 0, 0, 0, 1920, 3839]).unwrap();
+        let left = WRect::new(0, 30, 1920, 1050);
+        let right = WRect::new(1920, 30, 1920, 1050);
+        assert_eq!(dims(strut.apply(left, SCREEN)), (0, 30, 1920, 1050));
+        assert_eq!(dims(strut.apply(right, SCREEN)), (1920, 30, 1920, 1010));
+    }
+
+    #[test]
+    fn plain_strut_reserves_the_whole_edge() {
+        let strut = WStrut::parse(&[0, 50, 0, 0]).unwrap();
+        let right = WRect::new(1920, 30, 1920, 1050);
+        assert_eq!(dims(strut.apply(right, SCREEN)), (1920, 30, 1870, 1050));
+        assert_eq!(WStrut::parse(&[0, 50]), None);
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    io,
+    os::unix::{io::RawFd, process::ExitStatusExt},
+    process::{Command, ExitStatus},
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::signal;
+
+// a program that stays up for this long counts as having started fine, which resets
+// the time it waits before being restarted
+const STABLE_AFTER: Duration = Duration::from_secs(30);
+// restarts wait 1, 2, 4, ... seconds after each other, up to this many doublings
+const MAX_BACKOFF_STEPS: u32 = 6;
+
+// when a program started along with the wm is started again after it exits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WRestart {
+    Never,
+    // only when it exits with an error or gets killed
+    OnFailure,
+    Always,
+}
+
+impl WRestart {
+    fn wants(self, status: ExitStatus) -> bool {
+        match self {
+            Self::Never => false,
+            Self::OnFailure => !status.success(),
+            Self::Always => true,
+        }
+    }
+}
+
+// a program started along with the wm, like a compositor or a notification daemon
+#[derive(Debug, Clone)]
+pub struct WAutoStart {
+    pub cmd: Vec<String>,
+    pub restart: WRestart,
+}
+
+impl WAutoStart {
+    pub fn new(cmd: Vec<String>, restart: WRestart) -> Self {
+        Self { cmd, restart }
+    }
+}
+
+struct WChild {
+    cmd: Vec<String>,
+    restart: WRestart,
+    // unset while waiting to be restarted
+    pid: Option<libc::pid_t>,
+    started: Instant,
+    // how many times in a row it exited before it was `STABLE_AFTER`
+    failures: u32,
+    restart_at: Option<Instant>,
+}
+
+// keeps track of the programs we spawn so that they are reaped once they exit, and
+// restarts the ones that should be
+pub struct WSupervisor {
+    signal: RawFd,
+    children: Vec<WChild>,
+}
+
+impl WSupervisor {
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            signal: signal::pipe(libc::SIGCHLD)?,
+            children: Vec::new(),
+        })
+    }
+
+    // readable once a child has exited
+    pub fn fd(&self) -> RawFd {
+        self.signal
+    }
+
+    // the pid of the started program, if the command isn't empty
+    pub fn spawn(
+        &mut self,
+        cmd: &[impl AsRef<str>],
+        restart: WRestart,
+    ) -> io::Result<Option<libc::pid_t>> {
+        let cmd: Vec<String> = cmd.iter().map(|a| a.as_ref().to_string()).collect();
+        let Some(pid) = start(&cmd)? else {
+            return Ok(None);
+        };
+        self.children.push(WChild {
+            cmd,
+            restart,
+            pid: Some(pid),
+            started: Instant::now(),
+            failures: 0,
+            restart_at: None,
+        });
+        Ok(Some(pid))
+    }
+
+    // whether the child hasn't been reaped yet
+    pub fn running(&self, pid: libc::pid_t) -> bool {
+        self.children.iter().any(|c| c.pid == Some(pid))
+    }
+
+    // reaps the children that exited and returns when the next one is due to be restarted.
+    // children are waited for by pid, leaving alone the ones spawned elsewhere to be
+    // waited for right away.
+    pub fn reap(&mut self) -> Option<Instant> {
+        signal::drain(self.signal);
+        let now = Instant::now();
+        self.children.retain_mut(|child| {
+            let Some(pid) = child.pid else {
+                return true;
+            };
+            let mut status = 0;
+            if unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) } <= 0 {
+                return true;
+            }
+            let status = ExitStatus::from_raw(status);
+            if !child.restart.wants(status) {
+                return false;
+            }
+            if now.duration_since(child.started) >= STABLE_AFTER {
+                child.failures = 0;
+            }
+            let delay = backoff(child.failures);
+            child.failures += 1;
+            eprintln!(
+                "ERROR: {} exited with {status}, restarting it in {}s",
+                child.cmd[0],
+                delay.as_secs()
+            );
+            child.pid = None;
+            child.restart_at = Some(now + delay);
+            true
+        });
+        self.next_restart()
+    }
+
+    // starts the children that are due again and returns when the next one is due
+    pub fn restart_due(&mut self) -> Option<Instant> {
+        let now = Instant::now();
+        for child in &mut self.children {
+            if child.restart_at.is_none_or(|at| at > now) {
+                continue;
+            }
+            child.restart_at = None;
+            child.started = now;
+            match start(&child.cmd) {
+                Ok(pid) => child.pid = pid,
+                Err(e) => {
+                    eprintln!("ERROR: Failed to restart {}: {e}", child.cmd[0]);
+                    child.restart_at = Some(now + backoff(child.failures));
+                    child.failures += 1;
+                }
+            }
+        }
+        self.next_restart()
+    }
+
+    fn next_restart(&self) -> Option<Instant> {
+        self.children.iter().filter_map(|c| c.restart_at).min()
+    }
+}
+
+impl Drop for WSupervisor {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.signal) };
+    }
+}
+
+// the pid of the started program, if the command isn't empty
+fn start(cmd: &[String]) -> io::Result<Option<libc::pid_t>> {
+    let Some((bin, args)) = wwm_core::util::cmd::format(cmd) else {
+        return Ok(None);
+    };
+    let child = Command::new(bin).args(args).spawn()?;
+    Ok(Some(child.id() as libc::pid_t))
+}
+
+fn backoff(failures: u32) -> Duration {
+    Duration::from_secs(1 << failures.min(MAX_BACKOFF_STEPS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restarts_back_off_up_to_a_minute() {
+        let delays: Vec<u64> = (0..9).map(|f| backoff(f).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 32, 64, 64, 64]);
+    }
+
+    #[test]
+    fn on_failure_skips_clean_exits() {
+        let ok = ExitStatus::from_raw(0);
+        let failed = ExitStatus::from_raw(1 << 8);
+        let killed = ExitStatus::from_raw(libc::SIGKILL);
+        assert!(!WRestart::OnFailure.wants(ok));
+        assert!(WRestart::OnFailure.wants(failed));
+        assert!(WRestart::OnFailure.wants(killed));
+        assert!(WRestart::Always.wants(ok));
+        assert!(!WRestart::Never.wants(failed));
+    }
+}
@@ -0,0 +1,103 @@
+use wwm_core::{
+    overlay::WOverlay,
+    text::{FontError, TextRenderer},
+    util::{color::hex_to_rgba, primitives::WRect},
+};
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{
+        ChangeWindowAttributesAux, ConfigureWindowAux, ConnectionExt, EventMask, StackMode,
+    },
+    rust_connection::ReplyOrIdError,
+};
+
+use crate::settings::WTheme;
+
+// a strip below the bar in the tabbed layout with a tab for each tiled client
+pub struct WTabStrip {
+    pub overlay: WOverlay,
+}
+
+impl WTabStrip {
+    pub fn new<C: Connection>(
+        conn: &C,
+        text_renderer: &TextRenderer<C>,
+        theme: &WTheme,
+        rect: WRect,
+    ) -> Result<Self, ReplyOrIdError> {
+        let overlay = WOverlay::new(conn, text_renderer, rect, theme.bg)?;
+        conn.change_window_attributes(
+            overlay.window,
+            &ChangeWindowAttributesAux::new()
+                .event_mask(EventMask::EXPOSURE | EventMask::BUTTON_PRESS),
+        )?;
+        // kept below everything else so that floating clients are drawn over it
+        conn.configure_window(
+            overlay.window,
+            &ConfigureWindowAux::new().stack_mode(StackMode::BELOW),
+        )?;
+        Ok(Self { overlay })
+    }
+
+    // the index of the tab at `x`, relative to the strip
+    pub fn tab_at(&self, x: i16, tabs: usize) -> Option<usize> {
+        tab_at(self.overlay.rect.w, x, tabs)
+    }
+
+    // `tabs` holds the title of each client and whether it is the focused one
+    pub fn draw<C: Connection>(
+        &self,
+        text_renderer: &TextRenderer<C>,
+        theme: &WTheme,
+        tabs: &[(&str, bool)],
+    ) -> Result<(), FontError> {
+        let WRect { w, h, .. } = self.overlay.rect;
+        for (i, (title, focused)) in tabs.iter().enumerate() {
+            let colors = if *focused {
+                (theme.fg_selected, theme.bg_selected)
+            } else {
+                (theme.fg, theme.bg)
+            };
+            let colors = (hex_to_rgba(colors.0), hex_to_rgba(colors.1));
+
+            let (x, tab_w) = tab_span(w, i, tabs.len());
+            let rect = WRect::new(x, 0, tab_w, h);
+            self.overlay
+                .draw_in(text_renderer, rect, title, theme.padding, colors, true)?;
+        }
+        Ok(())
+    }
+}
+
+// where the `i`th of `tabs` tabs starts in a strip `w` wide and how wide it is.
+// the last tab takes up whatever is left after rounding.
+fn tab_span(w: u16, i: usize, tabs: usize) -> (i16, u16) {
+    let tab_w = w / tabs as u16;
+    let x = i as u16 * tab_w;
+    if i == tabs - 1 {
+        (x as i16, w - x)
+    } else {
+        (x as i16, tab_w)
+    }
+}
+
+fn tab_at(w: u16, x: i16, tabs: usize) -> Option<usize> {
+    if tabs == 0 || x < 0 || x as u16 >= w {
+        return None;
+    }
+    Some((x as usize / (w as usize / tabs).max(1)).min(tabs - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tabs_split_the_strip_evenly() {
+        assert_eq!(tab_span(1000, 0, 3), (0, 333));
+        assert_eq!(tab_span(1000, 2, 3), (666, 334));
+        assert_eq!(tab_at(1000, 400, 3), Some(1));
+        assert_eq!(tab_at(1000, 999, 3), Some(2));
+        assert_eq!(tab_at(1000, 1000, 3), None);
+    }
+}
@@ -0,0 +1,79 @@
+use wwm_core::{
+    overlay::WOverlay,
+    text::{FontError, TextRenderer},
+    util::{color::hex_to_rgba, primitives::WRect},
+};
+use x11rb::connection::Connection;
+
+use crate::settings::WTheme;
+
+// a popup below a tag in the bar with a miniature of the clients on that tag
+pub struct WTagPreview {
+    pub overlay: WOverlay,
+    pub mon_idx: usize,
+    pub tag: usize,
+}
+
+impl WTagPreview {
+    pub fn draw<C: Connection>(
+        &self,
+        text_renderer: &TextRenderer<C>,
+        theme: &WTheme,
+        area: WRect,
+        clients: &[(WRect, &str, bool)],
+    ) -> Result<(), FontError> {
+        let WRect { w, h, .. } = self.overlay.rect;
+        let dst = WRect::new(0, 0, w, h);
+        draw_miniature(&self.overlay, text_renderer, theme, area, dst, clients)
+    }
+}
+
+// draws the clients scaled down from `area` into `dst`, a part of the overlay.
+// `clients` holds the rect, label and whether it would be focused of every client,
+// with rects being where they are placed within `area`.
+pub fn draw_miniature<C: Connection>(
+    overlay: &WOverlay,
+    text_renderer: &TextRenderer<C>,
+    theme: &WTheme,
+    area: WRect,
+    dst: WRect,
+    clients: &[(WRect, &str, bool)],
+) -> Result<(), FontError> {
+    for (rect, label, focused) in clients {
+        let colors = if *focused {
+            (theme.fg_selected, theme.bg_selected)
+        } else {
+            (theme.fg, theme.border_unfocused)
+        };
+        let colors = (hex_to_rgba(colors.0), hex_to_rgba(colors.1));
+        let rect = miniature(*rect, area, dst);
+        overlay.draw_in(text_renderer, rect, label, theme.padding, colors, true)?;
+    }
+    Ok(())
+}
+
+// scales `rect`, which is placed within `area`, down to the same place within `dst`.
+// a pixel is left around it to tell clients apart.
+pub fn miniature(rect: WRect, area: WRect, dst: WRect) -> WRect {
+    let sx = dst.w as f32 / area.w as f32;
+    let sy = dst.h as f32 / area.h as f32;
+    WRect::new(
+        dst.x + ((rect.x - area.x) as f32 * sx).round() as i16 + 1,
+        dst.y + ((rect.y - area.y) as f32 * sy).round() as i16 + 1,
+        ((rect.w as f32 * sx).round() as u16).saturating_sub(2),
+        ((rect.h as f32 * sy).round() as u16).saturating_sub(2),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn miniature_keeps_the_place_within_the_area() {
+        let area = WRect::new(1920, 30, 1920, 1050);
+        let rect = WRect::new(2880, 30, 960, 520);
+        let mini = miniature(rect, area, WRect::new(0, 0, 192, 105));
+        assert_eq!((mini.x, mini.y, mini.w, mini.h), (97, 1, 94, 50));
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    io,
+    os::unix::io::RawFd,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WTimerEvent {
+    // run the command of the held key again
+    KeyRepeat,
+    // the bare modifier was not tapped a second time in time
+    ModTapTimeout,
+    // the exit keybind was not pressed a second time in time
+    ExitTimeout,
+    // see if an idle action is due
+    IdleCheck,
+    // fade the osd out a step further
+    OsdFade,
+    // see if the last pinged client answered and ping the focused one
+    Ping,
+    // start the programs again that exited and were waiting to be restarted
+    RestartChildren,
+}
+
+// every pending timeout of the wm. the event loop blocks on a single timerfd
+// that is armed for whichever timeout is due first.
+pub struct WTimers {
+    fd: WTimerFd,
+    scheduled: Vec<(Instant, WTimerEvent)>,
+}
+
+impl WTimers {
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            fd: WTimerFd::new()?,
+            scheduled: Vec::new(),
+        })
+    }
+
+    pub fn fd(&self) -> RawFd {
+        self.fd.0
+    }
+
+    // schedules the event, replacing a pending one of the same kind
+    pub fn schedule(&mut self, event: WTimerEvent, after: Duration) {
+        self.cancel(event);
+        self.scheduled.push((Instant::now() + after, event));
+    }
+
+    pub fn cancel(&mut self, event: WTimerEvent) {
+        self.scheduled.retain(|(_, e)| *e != event);
+    }
+
+    pub fn is_scheduled(&self, event: WTimerEvent) -> bool {
+        self.scheduled.iter().any(|(_, e)| *e == event)
+    }
+
+    // arms the timerfd for the earliest deadline, or disarms it if nothing is pending
+    pub fn arm(&self) -> io::Result<()> {
+        let deadline = self.scheduled.iter().map(|(d, _)| *d).min();
+        self.fd.arm(deadline)
+    }
+
+    // removes and returns every event that is due, earliest first
+    pub fn take_due(&mut self) -> Vec<WTimerEvent> {
+        self.fd.clear();
+
+        let now = Instant::now();
+        let mut due: Vec<_> = self
+            .scheduled
+            .iter()
+            .filter(|(d, _)| *d <= now)
+            .copied()
+            .collect();
+        self.scheduled.retain(|(d, _)| *d > now);
+
+        due.sort_by_key(|(d, _)| *d);
+        due.into_iter().map(|(_, e)| e).collect()
+    }
+}
+
+struct WTimerFd(RawFd);
+
+impl WTimerFd {
+    fn new() -> io::Result<Self> {
+        let fd = unsafe {
+            libc::timerfd_create(
+                libc::CLOCK_MONOTONIC,
+                libc::TFD_NONBLOCK | libc::TFD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self(fd))
+    }
+
+    fn arm(&self, deadline: Option<Instant>) -> io::Result<()> {
+        // a zero value disarms the timer, so past deadlines expire right away instead
+        let after = match deadline {
+            Some(d) => d
+                .saturating_duration_since(Instant::now())
+                .max(Duration::from_nanos(1)),
+            None => Duration::ZERO,
+        };
+        let spec = libc::itimerspec {
+            it_interval: libc::timespec {
+                tv_sec: 0,
+                tv_nsec: 0,
+            },
+            it_value: libc::timespec {
+                tv_sec: after.as_secs() as libc::time_t,
+                tv_nsec: after.subsec_nanos() as libc::c_long,
+            },
+        };
+        let res = unsafe { libc::timerfd_settime(self.0, 0, &spec, std::ptr::null_mut()) };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // consumes pending expirations so the fd stops being readable
+    fn clear(&self) {
+        let mut expirations = 0u64;
+        unsafe {
+            libc::read(
+                self.0,
+                &mut expirations as *mut u64 as *mut libc::c_void,
+                std::mem::size_of::<u64>(),
+            );
+        }
+    }
+}
+
+impl Drop for WTimerFd {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use wwm_core::{
+    overlay::WOverlay,
+    text::{FontError, TextRenderer},
+    util::{color::hex_to_rgba, primitives::WRect},
+};
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{
+        ChangeWindowAttributesAux, ConfigureWindowAux, ConnectionExt, EventMask, StackMode, Window,
+    },
+    rust_connection::ReplyOrIdError,
+};
+
+use crate::settings::WTheme;
+
+// the label of the button that closes the client
+const CLOSE: &str = "×";
+
+// a strip above a tiled client showing its title and a close button
+pub struct WTitleBar {
+    pub overlay: WOverlay,
+    pub mon_idx: usize,
+    // the client the title bar belongs to
+    pub window: Window,
+}
+
+impl WTitleBar {
+    pub fn new<C: Connection>(
+        conn: &C,
+        text_renderer: &TextRenderer<C>,
+        theme: &WTheme,
+        rect: WRect,
+        mon_idx: usize,
+        window: Window,
+    ) -> Result<Self, ReplyOrIdError> {
+        let overlay = WOverlay::new(conn, text_renderer, rect, theme.bg)?;
+        conn.change_window_attributes(
+            overlay.window,
+            &ChangeWindowAttributesAux::new()
+                .event_mask(EventMask::EXPOSURE | EventMask::BUTTON_PRESS),
+        )?;
+        // kept below everything else so that floating clients are drawn over it
+        conn.configure_window(
+            overlay.window,
+            &ConfigureWindowAux::new().stack_mode(StackMode::BELOW),
+        )?;
+
+        Ok(Self {
+            overlay,
+            mon_idx,
+            window,
+        })
+    }
+
+    // the close button is a square at the right end
+    fn close_button_width(&self) -> u16 {
+        self.overlay.rect.h.min(self.overlay.rect.w)
+    }
+
+    // whether `x`, relative to the title bar, is on the close button
+    pub fn on_close_button(&self, x: i16) -> bool {
+        x >= (self.overlay.rect.w - self.close_button_width()) as i16
+    }
+
+    pub fn draw<C: Connection>(
+        &self,
+        text_renderer: &TextRenderer<C>,
+        theme: &WTheme,
+        title: &str,
+        focused: bool,
+    ) -> Result<(), FontError> {
+        let colors = if focused {
+            (theme.fg_selected, theme.bg_selected)
+        } else {
+            (theme.fg, theme.bg)
+        };
+        let colors = (hex_to_rgba(colors.0), hex_to_rgba(colors.1));
+
+        let WRect { w, h, .. } = self.overlay.rect;
+        let close_w = self.close_button_width();
+        let title_rect = WRect::new(0, 0, w - close_w, h);
+        let close_rect = WRect::new((w - close_w) as i16, 0, close_w, h);
+
+        let padding = theme.padding;
+        self.overlay
+            .draw_in(text_renderer, title_rect, title, padding, colors, false)?;
+        self.overlay
+            .draw_in(text_renderer, close_rect, CLOSE, padding, colors, true)
+    }
+}
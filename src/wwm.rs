@@ -1,51 +1,96 @@
 use crate::{
-    client::WClientState,
-    command::{WDirection, WKeyCommand, WMouseCommand},
+    batch::WConfigureBatch,
+    border,
+    client::{motif_no_decorations, WClientState, WFocusPolicy, WStackLayer},
+    command::{WAxis, WDirection, WKeyCommand, WMediaCommand, WMouseCommand},
     config::{
-        auto_start::AUTO_START_COMMANDS,
-        bar_height,
-        mouse::{DRAG_BUTTON, RESIZE_BUTTON},
-        tags::WIDTH_ADJUSTMENT_FACTOR,
-        theme::{self, window::BORDER_WIDTH},
+        double_tap, hints, idle, key_repeat, media,
+        mouse::{self, DRAG_BUTTON, GESTURE_THRESHOLD, MOTION_INTERVAL, RESIZE_BUTTON},
+        osd, ping, pip, resize_mode,
+        tags::{CLIENT_FACTOR_STEP, WIDTH_FACTOR_RANGE},
+        theme::{
+            self,
+            window::{BORDER_WIDTH, GAP_STEP},
+        },
+    },
+    error::WwmError,
+    hint::WHint,
+    idle::{WIdle, WIdleAction},
+    ipc::{
+        event::{self, WIpcEvent},
+        protocol::{self, WIpcRequest},
+        query::{self, WIpcQuery},
+        WIpcServer,
     },
-    keyboard::WKeyboard,
-    layouts::layout_clients,
+    keyboard::{keybind::WKeybindContext, WKeyboard},
+    layouts::{self, layout_clients},
     monitor::WMonitor,
-    mouse::WMouse,
+    mouse::{WFocusModel, WGesture, WMouse},
+    osd::{self as osd_overlay, WMediaChange, WOsd, WOsdQuery},
+    overview::WOverview,
+    pip::WCorner,
+    selection::WSelection,
+    session::{
+        state::{WSavedClient, WSavedMonitor, WSavedState},
+        WSession, WSessionEvent,
+    },
+    settings::{watch::WConfigWatch, WConfig},
+    strut::WStrut,
+    supervisor::{WRestart, WSupervisor},
+    tab_strip::WTabStrip,
+    tag_preview::{self, WTagPreview},
+    timer::{WTimerEvent, WTimers},
+    title_bar::WTitleBar,
     AtomCollection,
 };
-use wwm_core::util::{
-    primitives::{WPos, WRect},
-    WConfigWindow, WLayout,
+use wwm_bar::{
+    status_module::WModuleKind,
+    status_worker::{WStatusSource, WStatusWorker},
+};
+use wwm_core::{
+    icon::WIcon,
+    layout,
+    overlay::WOverlay,
+    util::{
+        color::hex_to_rgba,
+        keysym,
+        primitives::{WPos, WRect},
+        WConfigWindow, WLayout,
+    },
 };
 
 use std::{
     collections::HashSet,
-    process::{exit, Command},
+    os::unix::io::AsRawFd,
+    process::exit,
     rc::Rc,
-    sync::atomic::{AtomicBool, Ordering},
-    sync::Arc,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
-use wwm_core::text::TextRenderer;
+use wwm_core::text::{RasterizedFont, TextRenderer};
 use x11rb::{
     connection::Connection,
-    properties::WmSizeHints,
+    properties::{WmClass, WmHints, WmSizeHints},
     protocol::{
+        dpms,
         randr::ConnectionExt as _,
+        screensaver,
+        xinput::RawKeyPressEvent,
+        xkb::StateNotifyEvent,
         xproto::{
-            ButtonPressEvent, ButtonReleaseEvent, ChangeWindowAttributesAux, ClientMessageEvent,
-            CloseDown, ConfigureNotifyEvent, ConfigureRequestEvent, ConfigureWindowAux,
-            ConnectionExt, DestroyNotifyEvent, EnterNotifyEvent, EventMask, ExposeEvent,
-            GetGeometryReply, InputFocus, KeyPressEvent, MapRequestEvent, MapState,
-            MotionNotifyEvent, PropMode, PropertyNotifyEvent, Screen, StackMode, UnmapNotifyEvent,
-            Window,
+            Allow, Atom, AtomEnum, ButtonIndex, ButtonPressEvent, ButtonReleaseEvent,
+            ChangeWindowAttributesAux, ClientMessageEvent, CloseDown, ConfigureNotifyEvent,
+            ConfigureRequestEvent, ConfigureWindowAux, ConnectionExt, CreateWindowAux,
+            DestroyNotifyEvent, EnterNotifyEvent, EventMask, ExposeEvent, GetGeometryReply, Grab,
+            GrabMode, GrabStatus, InputFocus, KeyPressEvent, MapRequestEvent, MapState, ModMask,
+            MotionNotifyEvent, PropMode, PropertyNotifyEvent, Screen, ScreenSaver, StackMode,
+            UnmapNotifyEvent, Window, WindowClass,
         },
         ErrorKind, Event,
     },
     rust_connection::{ReplyError, ReplyOrIdError},
     wrapper::ConnectionExt as _,
+    x11_utils::X11Error,
     CURRENT_TIME, NONE,
 };
 
@@ -64,18 +109,55 @@ enum NotifyMode {
 pub struct WinMan<'a, C: Connection> {
     conn: &'a C,
     screen: &'a Screen,
-    #[allow(dead_code)]
-    text_renderer: Rc<TextRenderer<'a, C>>,
+    config: WConfig,
+    // reloads the config on a SIGHUP or once the file is saved
+    config_watch: Option<WConfigWatch>,
+    // one for every distinct monitor scale, only empty while starting up. see `load_fonts`
+    text_renderers: Vec<Rc<TextRenderer<'a, C>>>,
     monitors: Vec<WMonitor<'a, C>>,
     selmon: usize,
     pending_exposure: HashSet<Window>,
     drag_window: Option<(WPos, WPos, u32)>,
+    // the monitor and tag being dragged along the bar
+    drag_tag: Option<(usize, usize)>,
     resize_window: Option<u32>,
-    keyboard: WKeyboard,
+    gesture: Option<(WPos, WPos)>,
+    keyboard: WKeyboard<'a>,
     mouse: WMouse,
     atoms: AtomCollection,
     ignore_enter: bool,
-    should_exit: Arc<AtomicBool>,
+    resize_mode: bool,
+    hints: Vec<WHint>,
+    title_bars: Vec<WTitleBar>,
+    tag_preview: Option<WTagPreview>,
+    overview: Option<WOverview>,
+    osd: Option<WOsd>,
+    // the last volume or brightness command while it runs, and then the query for the
+    // value it left
+    media_change: Option<WMediaChange>,
+    osd_query: Option<WOsdQuery>,
+    timers: WTimers,
+    idle: WIdle,
+    // whether dpms was enabled before the first `DpmsOff` enabled it, which is
+    // restored on exit
+    dpms_was_enabled: Option<bool>,
+    status: WStatusWorker,
+    // the programs we started, which are reaped and maybe restarted once they exit
+    children: WSupervisor,
+    batch: Option<WConfigureBatch>,
+    session: Option<WSession>,
+    // takes commands from scripts and `wwm-msg`
+    ipc: Option<WIpcServer>,
+    // what is left to restore of the session wwm was restarted into
+    saved_state: Option<WSavedState>,
+    // the space reserved by docks, panels and clients along the edges of the screen
+    struts: Vec<(Window, WStrut)>,
+    // the client pinged last, until it answers
+    pending_ping: Option<Window>,
+    selection: WSelection,
+    // the `_NET_SUPPORTING_WM_CHECK` window, which tells ewmh clients that we are running
+    wm_check: Window,
+    exiting: bool,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
@@ -84,70 +166,651 @@ enum ShouldExit {
     No,
 }
 
-impl<'a, C: Connection> WinMan<'a, C> {
+impl<'a, C: Connection + AsRawFd> WinMan<'a, C> {
     pub fn init(
         conn: &'a C,
         screen_num: usize,
-        keyboard: WKeyboard,
-        mouse: WMouse,
+        config: WConfig,
+        keyboard: WKeyboard<'a>,
         atoms: AtomCollection,
-    ) -> Result<Self, ReplyOrIdError> {
-        // TODO: error handling
+        session: Option<WSession>,
+        replace: bool,
+    ) -> Result<Self, WwmError> {
         let screen = &conn.setup().roots[screen_num];
+        let mouse = WMouse::new(conn, screen_num, config.modkey)?;
 
-        Self::become_wm(conn, screen, mouse.cursors.normal)?;
-        Self::run_auto_start_commands().unwrap();
+        let Some(selection) =
+            WSelection::acquire(conn, screen, screen_num, atoms.MANAGER, replace)?
+        else {
+            eprintln!("ERROR: Another WM is already running, start with --replace to replace it.");
+            exit(1);
+        };
+        let ipc = match WIpcServer::bind() {
+            Ok(ipc) => Some(ipc),
+            Err(e) => {
+                eprintln!("ERROR: Failed to create the ipc socket: {e}");
+                None
+            }
+        };
 
-        let text_renderer =
-            TextRenderer::new(conn, screen, theme::bar::FONT, theme::bar::FONT_SIZE).unwrap();
-        let text_renderer = Rc::new(text_renderer);
+        let mut monitors: Vec<WMonitor<'a, C>> = Self::get_monitors(conn, screen, &config)?;
+
+        // rasterizing the font is by far the slowest part of starting up, so it
+        // happens while existing windows are taken over and the bars show up after
+        let mut scales: Vec<f32> = monitors.iter().map(|m| m.scale).collect();
+        scales.sort_by(f32::total_cmp);
+        scales.dedup();
+        let (font, font_size) = (config.font.clone(), config.font_size);
+        let fonts = thread::spawn(move || {
+            scales
+                .into_iter()
+                .map(|s| RasterizedFont::load(&font, font_size, s))
+                .collect::<Result<Vec<_>, _>>()
+        });
 
-        let mut monitors: Vec<WMonitor<'a, C>> =
-            Self::get_monitors(conn, screen, &text_renderer)?.into();
+        let wm_check = Self::become_wm(conn, screen, &atoms, mouse.cursors.normal)?;
+        let mut children = WSupervisor::new()?;
+        for auto_start in &config.auto_start {
+            if let Err(e) = children.spawn(&auto_start.cmd, auto_start.restart) {
+                eprintln!("ERROR: Failed to run {}: {e}", auto_start.cmd[0]);
+            }
+        }
+
+        let saved_state = session
+            .as_ref()
+            .and_then(|s| WSavedState::load(s.id()).ok());
+        if let Some(saved) = &saved_state {
+            for (m, s) in monitors.iter_mut().zip(&saved.monitors) {
+                if m.set_tag(s.tag).is_ok() {
+                    m.bar.update_tags(s.tag);
+                }
+                if m.set_layout(s.layout) {
+                    m.bar.update_layout_symbol(m.layout);
+                }
+                m.width_factor = s.width_factor;
+            }
+        }
 
         let selmon = monitors.iter().position(|m| m.primary).unwrap_or(0);
         monitors[selmon].bar.set_is_focused(true);
 
+        let status = WStatusWorker::new(&config.modules, config.module_options())?;
+        let config_watch = match WConfigWatch::new(config.path.as_deref()) {
+            Ok(watch) => Some(watch),
+            Err(e) => {
+                eprintln!("ERROR: Failed to watch the config file: {e}");
+                None
+            }
+        };
+
         let mut wwm = Self {
             conn,
             screen,
-            text_renderer,
+            config,
+            config_watch,
+            text_renderers: Vec::new(),
             monitors,
             selmon,
             pending_exposure: Default::default(),
             drag_window: None,
+            drag_tag: None,
             resize_window: None,
+            gesture: None,
             keyboard,
             mouse,
             atoms,
             ignore_enter: false,
-            should_exit: Arc::new(AtomicBool::new(false)),
+            resize_mode: false,
+            hints: Vec::new(),
+            title_bars: Vec::new(),
+            tag_preview: None,
+            overview: None,
+            osd: None,
+            media_change: None,
+            osd_query: None,
+            timers: WTimers::new()?,
+            idle: WIdle::default(),
+            dpms_was_enabled: None,
+            status,
+            children,
+            batch: None,
+            session,
+            ipc,
+            saved_state,
+            struts: Vec::new(),
+            pending_ping: None,
+            selection,
+            wm_check,
+            exiting: false,
         };
+        wwm.refresh_status()?;
+        if conn
+            .extension_information(screensaver::X11_EXTENSION_NAME)?
+            .is_some()
+        {
+            wwm.timers.schedule(WTimerEvent::IdleCheck, Duration::ZERO);
+        } else {
+            eprintln!(
+                "ERROR: The X server has no screensaver extension, idle actions are disabled"
+            );
+        }
+        wwm.timers
+            .schedule(WTimerEvent::Ping, Duration::from_millis(ping::INTERVAL));
+        wwm.update_kb_layout_indicator();
+        wwm.publish_desktops()?;
         wwm.warp_pointer_to_focused_monitor()?;
 
         // take care of potentially unmanaged windows
         wwm.scan_windows()?;
+        wwm.conn.flush()?;
+
+        let fonts = fonts
+            .join()
+            .unwrap_or_else(|e| std::panic::resume_unwind(e))?;
+        wwm.load_fonts(fonts)?;
         Ok(wwm)
     }
 
-    pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        'eventloop: loop {
-            loop {
-                self.conn.flush()?;
-                if let Ok(Some(event)) = self.conn.poll_for_event() {
-                    if self.handle_event(event)? == ShouldExit::Yes {
-                        break 'eventloop;
+    fn load_fonts(&mut self, fonts: Vec<RasterizedFont>) -> Result<(), WwmError> {
+        for font in fonts {
+            self.text_renderers.push(Rc::new(TextRenderer::from_font(
+                self.conn,
+                self.screen,
+                font,
+                theme::bar::FALLBACK_FONTS,
+                theme::bar::TEXT_RENDERING,
+            )?));
+        }
+        for i in 0..self.monitors.len() {
+            if let Some(text_renderer) = self.text_renderer(i).cloned() {
+                self.monitors[i].bar.attach(self.conn, text_renderer)?;
+            }
+            // title bars and tabs can only be drawn now
+            let m = &self.monitors[i];
+            if m.has_title_bars() || m.layout == WLayout::Tabbed {
+                self.recompute_layout(i)?;
+            }
+        }
+        Ok(())
+    }
+
+    // the text renderer rasterized at the scale of the monitor
+    fn text_renderer(&self, mon_idx: usize) -> Option<&Rc<TextRenderer<'a, C>>> {
+        let scale = self.monitors[mon_idx].scale;
+        self.text_renderers.iter().find(|r| r.scale() == scale)
+    }
+
+    pub fn run(&mut self) -> Result<(), WwmError> {
+        loop {
+            if self.exiting {
+                return self.shutdown();
+            }
+
+            for event in self.timers.take_due() {
+                let res = self.handle_timer(event);
+                self.tolerate_stale_window(res)?;
+            }
+            for m in self.monitors.iter_mut() {
+                if let Err(e) = m.bar.draw(self.conn) {
+                    eprintln!("ERROR: Failed to draw bar: {e}");
+                }
+            }
+            self.conn.flush()?;
+
+            // events read while waiting for a reply are queued without the fd
+            // becoming readable, so only block once the queue has been drained
+            let mut handled = false;
+            // of consecutive motion events only the latest one is handled
+            let mut motion = None;
+            while let Some(event) = self.conn.poll_for_event()? {
+                handled = true;
+                if let Event::MotionNotify(_) = event {
+                    motion = Some(event);
+                    continue;
+                }
+                for event in motion.take().into_iter().chain([event]) {
+                    if self.dispatch(event)? == ShouldExit::Yes {
+                        return self.shutdown();
                     }
                 }
+            }
+            if let Some(event) = motion {
+                if self.dispatch(event)? == ShouldExit::Yes {
+                    return self.shutdown();
+                }
+            }
+
+            if !handled {
+                self.wait_for_work()?;
+            }
+        }
+    }
+
+    fn dispatch(&mut self, event: Event) -> Result<ShouldExit, ReplyOrIdError> {
+        let res = self.handle_event(event);
+        Ok(self.tolerate_stale_window(res)?.unwrap_or(ShouldExit::No))
+    }
+
+    fn handle_timer(&mut self, event: WTimerEvent) -> Result<(), ReplyOrIdError> {
+        match event {
+            WTimerEvent::KeyRepeat => {
+                if let Some(action) = self.keyboard.repeat_action() {
+                    self.timers.schedule(
+                        WTimerEvent::KeyRepeat,
+                        Duration::from_millis(key_repeat::INTERVAL),
+                    );
+                    self.handle_key_command(action)?;
+                }
+            }
+            WTimerEvent::IdleCheck => self.check_idle()?,
+            WTimerEvent::OsdFade => self.fade_osd()?,
+            WTimerEvent::Ping => self.ping()?,
+            WTimerEvent::RestartChildren => {
+                let next = self.children.restart_due();
+                self.schedule_restart(next);
+            }
+            // these only matter while they are pending
+            WTimerEvent::ModTapTimeout | WTimerEvent::ExitTimeout => {}
+        }
+        Ok(())
+    }
+
+    // hands every client back in a usable state so that they survive us exiting.
+    // the connection itself is closed once the wm is dropped.
+    fn shutdown(&mut self) -> Result<(), WwmError> {
+        for m in self.monitors.iter() {
+            for c in m.clients.iter() {
+                // clients on hidden tags are moved off screen
+                self.conn.configure_window(
+                    c.window,
+                    &ConfigureWindowAux::new()
+                        .x(c.rect.x as i32)
+                        .y(c.rect.y as i32),
+                )?;
+                self.conn.map_window(c.window)?;
+                self.conn
+                    .ungrab_button(ButtonIndex::ANY, c.window, ModMask::ANY)?;
+            }
+        }
+
+        for hint in self.hints.drain(..) {
+            hint.overlay.destroy(self.conn)?;
+        }
+        for t in self.title_bars.drain(..) {
+            t.overlay.destroy(self.conn)?;
+        }
+        for m in self.monitors.iter_mut() {
+            if let Some(t) = m.tab_strip.take() {
+                t.overlay.destroy(self.conn)?;
+            }
+        }
+        self.hide_tag_preview()?;
+        if let Some(overview) = self.overview.take() {
+            overview.overlay.destroy(self.conn)?;
+        }
+        self.hide_osd()?;
+
+        let root = self.screen.root;
+        self.conn.ungrab_key(Grab::ANY, root, ModMask::ANY)?;
+        self.conn
+            .ungrab_button(ButtonIndex::ANY, root, ModMask::ANY)?;
+        self.conn
+            .delete_property(root, self.atoms._NET_CLIENT_LIST)?;
+        self.conn
+            .delete_property(root, self.atoms._NET_ACTIVE_WINDOW)?;
+        self.conn.delete_property(root, self.atoms._NET_SUPPORTED)?;
+        self.conn
+            .delete_property(root, self.atoms._NET_SUPPORTING_WM_CHECK)?;
+        self.conn.destroy_window(self.wm_check)?;
+        self.conn.set_input_focus(
+            InputFocus::POINTER_ROOT,
+            InputFocus::POINTER_ROOT,
+            CURRENT_TIME,
+        )?;
+        if self.dpms_was_enabled == Some(false) {
+            dpms::disable(self.conn)?;
+        }
+        // which lets a wm replacing us know that we are done
+        self.conn.destroy_window(self.selection.window)?;
+
+        self.conn.sync()?;
+        Ok(())
+    }
+
+    // blocks until the x connection has something for us, the next timer is due,
+    // a new status text is ready, the session manager or an ipc client sent something or
+    // a child exited, whichever comes first
+    fn wait_for_work(&mut self) -> Result<(), WwmError> {
+        self.timers.arm()?;
+
+        let watch_fds = self.config_watch.as_ref().map_or([-1; 2], |w| w.fds());
+        let mut fds = vec![
+            libc::pollfd {
+                fd: self.conn.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: self.timers.fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: self.status.fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: self.session.as_ref().map_or(-1, |s| s.fd()),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: watch_fds[0],
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: watch_fds[1],
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: self.children.fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        let ipc_fds = self.ipc.as_ref().map(|ipc| ipc.fds()).unwrap_or_default();
+        fds.extend(ipc_fds.into_iter().map(|fd| libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        }));
+
+        let res = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) };
+        if res < 0 {
+            let err = std::io::Error::last_os_error();
+            // being interrupted by a signal just means we go around the loop once more
+            if err.kind() != std::io::ErrorKind::Interrupted {
+                return Err(err.into());
+            }
+        }
+
+        if fds[2].revents & libc::POLLIN != 0 {
+            let status = self.status.take_result();
+            // one the modules were still working on when the root name took over is late
+            if let Some(changes) =
+                status.filter(|_| self.config.status_source == WStatusSource::Modules)
+            {
                 for m in self.monitors.iter_mut() {
-                    m.bar.draw(self.conn);
+                    for (i, text) in &changes {
+                        m.bar.set_status_section(*i, text.clone());
+                    }
+                }
+            }
+        }
+        if fds[3].revents & libc::POLLIN != 0 {
+            self.handle_session_events();
+        }
+        if fds[6].revents & libc::POLLIN != 0 {
+            let next = self.children.reap();
+            self.schedule_restart(next);
+            self.handle_media_exits()?;
+        }
+        // hang ups count too, since the client is dropped then
+        if fds[7..].iter().any(|fd| fd.revents != 0) {
+            self.handle_ipc()?;
+        }
+        if fds[4..6].iter().any(|fd| fd.revents & libc::POLLIN != 0)
+            && self.config_watch.as_ref().is_some_and(|w| w.take_change())
+        {
+            self.reload_config();
+        }
+        Ok(())
+    }
+
+    fn handle_ipc(&mut self) -> Result<(), ReplyOrIdError> {
+        let Some(ipc) = &mut self.ipc else {
+            return Ok(());
+        };
+        for (client, request) in ipc.take_requests() {
+            let response = match request {
+                // asked for on purpose, unlike a keybind that might be pressed by accident
+                Ok(WIpcRequest::Command(WKeyCommand::Exit)) => {
+                    self.exiting = true;
+                    protocol::success()
+                }
+                Ok(WIpcRequest::Command(cmd)) => {
+                    let res = self.handle_key_command(cmd);
+                    self.tolerate_stale_window(res)?;
+                    protocol::success()
+                }
+                Ok(WIpcRequest::Query(q)) => protocol::data(self.query(q)),
+                Ok(WIpcRequest::Subscribe(events)) => {
+                    if let Some(ipc) = &mut self.ipc {
+                        ipc.subscribe(client, events);
+                    }
+                    protocol::success()
+                }
+                Err(e) => protocol::failure(&e),
+            };
+            if let Some(ipc) = &mut self.ipc {
+                ipc.reply(client, &response);
+            }
+        }
+        Ok(())
+    }
+
+    // pushes the event to the ipc clients subscribed to it
+    fn emit(&mut self, (kind, event): (WIpcEvent, serde_json::Value)) {
+        if let Some(ipc) = &mut self.ipc {
+            ipc.broadcast(kind, event);
+        }
+    }
+
+    fn query(&self, q: WIpcQuery) -> serde_json::Value {
+        let monitors = self.monitors.iter().enumerate();
+        match q {
+            WIpcQuery::Monitors => monitors
+                .map(|(i, m)| query::monitor(m, i, i == self.selmon))
+                .collect(),
+            WIpcQuery::Tags => monitors
+                .flat_map(|(i, m)| query::tags(m, i, &self.config))
+                .collect(),
+            WIpcQuery::Clients => monitors
+                .flat_map(|(i, m)| {
+                    let focused = (i == self.selmon).then_some(m.client).flatten();
+                    m.clients
+                        .iter()
+                        .enumerate()
+                        .map(move |(ci, c)| query::client(c, i, focused == Some(ci)))
+                })
+                .collect(),
+            WIpcQuery::Version => query::version(),
+        }
+    }
+
+    // reads the config file again and applies everything that can change while running.
+    // the fonts and status modules are loaded first, and the old config is kept when
+    // they can't be, so that a typo in the file doesn't take the session down.
+    fn reload_config(&mut self) {
+        let mut config = WConfig::load(self.conn, self.config.path.clone());
+        // tags can't come and go with clients on them, so their count needs a restart
+        config.tag_count = self.config.tag_count;
+
+        let mut fonts = Vec::new();
+        if (&config.font, config.font_size) != (&self.config.font, self.config.font_size) {
+            let loaded = self
+                .text_renderers
+                .iter()
+                .map(|r| RasterizedFont::load(&config.font, config.font_size, r.scale()))
+                .collect();
+            fonts = match loaded {
+                Ok(fonts) => fonts,
+                Err(e) => {
+                    eprintln!(
+                        "ERROR: Failed to load font `{}`, keeping the old config: {e}",
+                        config.font
+                    );
+                    return;
+                }
+            };
+        }
+        let mut status = None;
+        if (&config.modules, config.module_options())
+            != (&self.config.modules, self.config.module_options())
+        {
+            status = match WStatusWorker::new(&config.modules, config.module_options()) {
+                Ok(status) => Some(status),
+                Err(e) => {
+                    eprintln!(
+                        "ERROR: Failed to start the status modules, keeping the old config: {e}"
+                    );
+                    return;
                 }
+            };
+        }
+        if let Err(e) = self.apply_config(config, fonts, status) {
+            eprintln!("ERROR: Failed to apply the config: {e}");
+        }
+    }
+
+    fn apply_config(
+        &mut self,
+        config: WConfig,
+        fonts: Vec<RasterizedFont>,
+        status: Option<WStatusWorker>,
+    ) -> Result<(), WwmError> {
+        let keybinds = config.keybinds.clone();
+        self.keyboard
+            .set_keybinds(self.conn, self.screen.root, keybinds)?;
+        if config.modkey != self.config.modkey {
+            self.mouse
+                .set_modkey(self.conn, self.screen.root, config.modkey)?;
+        }
+        let status_changed = status.is_some() || config.status_source != self.config.status_source;
+        // the check stops once there are no actions to take
+        if config.idle_actions != self.config.idle_actions
+            && self
+                .conn
+                .extension_information(screensaver::X11_EXTENSION_NAME)?
+                .is_some()
+        {
+            self.idle = WIdle::default();
+            self.timers.schedule(WTimerEvent::IdleCheck, Duration::ZERO);
+        }
+        self.config = config;
+        if let Some(status) = status {
+            self.status = status;
+            // the new modules send their texts from scratch, which leaves nothing of the
+            // old ones behind when there are fewer of them
+            for m in self.monitors.iter_mut() {
+                m.bar.set_status("");
+            }
+            self.pause_unseen_status();
+        }
+
+        for (r, font) in self.text_renderers.iter().zip(fonts) {
+            r.set_font(font)?;
+        }
+        for m in self.monitors.iter_mut() {
+            m.apply_config(&self.config)?;
+        }
+        // the bars might have changed height
+        self.apply_struts()?;
+        // and the tags their names
+        self.publish_desktops()?;
+        if status_changed {
+            self.refresh_status()?;
+        }
+        for i in 0..self.monitors.len() {
+            self.recompute_layout(i)?;
+            let m = &self.monitors[i];
+            for (ci, c) in m.clients.iter().enumerate() {
+                let focused = i == self.selmon && m.client == Some(ci);
+                border::paint(self.conn, &self.config.theme, c, focused)?;
             }
         }
         Ok(())
     }
 
-    fn become_wm(conn: &'a C, screen: &Screen, cursor: u32) -> Result<(), ReplyError> {
+    fn handle_session_events(&mut self) {
+        let events = self.session.as_mut().map(|s| s.process());
+        for event in events.unwrap_or_default() {
+            match event {
+                WSessionEvent::Save => {
+                    let Some(session) = &self.session else { return };
+                    let res = self.saved_session().save(session.id());
+                    if let Err(e) = &res {
+                        eprintln!("ERROR: Failed to save the session: {e}");
+                    }
+                    session.save_done(res.is_ok());
+                }
+                WSessionEvent::Die => self.exiting = true,
+            }
+        }
+    }
+
+    fn saved_session(&self) -> WSavedState {
+        let monitors = self
+            .monitors
+            .iter()
+            .map(|m| WSavedMonitor {
+                tag: m.tag,
+                layout: m.layout,
+                width_factor: m.width_factor,
+            })
+            .collect();
+        let clients = self
+            .monitors
+            .iter()
+            .enumerate()
+            .flat_map(|(mi, m)| {
+                m.clients.iter().map(move |c| WSavedClient {
+                    monitor: mi,
+                    tag: c.tag,
+                    is_floating: c.is_floating,
+                    class: c.class.clone(),
+                    title: c.title.clone(),
+                })
+            })
+            .collect();
+        WSavedState { monitors, clients }
+    }
+
+    // clients can destroy their windows between an event and our follow-up requests.
+    // errors caused by that are logged and ignored instead of taking down the wm.
+    fn tolerate_stale_window<T>(
+        &self,
+        res: Result<T, ReplyOrIdError>,
+    ) -> Result<Option<T>, ReplyOrIdError> {
+        let err = match res {
+            Err(ReplyOrIdError::X11Error(ref e)) => e,
+            Err(e) => return Err(e),
+            Ok(v) => return Ok(Some(v)),
+        };
+        if self.is_stale_window_error(err) {
+            eprintln!(
+                "ERROR: ignoring {:?} error for window {}",
+                err.error_kind, err.bad_value
+            );
+            return Ok(None);
+        }
+        res.map(Some)
+    }
+
+    fn is_stale_window_error(&self, err: &X11Error) -> bool {
+        matches!(err.error_kind, ErrorKind::Window | ErrorKind::Drawable)
+            && err.bad_value != self.screen.root
+    }
+
+    fn become_wm(
+        conn: &'a C,
+        screen: &Screen,
+        atoms: &AtomCollection,
+        cursor: u32,
+    ) -> Result<Window, ReplyOrIdError> {
         let change = ChangeWindowAttributesAux::default()
             .event_mask(
                 EventMask::SUBSTRUCTURE_REDIRECT
@@ -160,31 +823,97 @@ impl<'a, C: Connection> WinMan<'a, C> {
             )
             .cursor(cursor);
 
-        let res = conn
-            .change_window_attributes(screen.root, &change)
-            .unwrap()
-            .check();
+        conn.change_window_attributes(screen.root, &change)?
+            .check()?;
 
-        if let Err(ReplyError::X11Error(ref error)) = res {
-            if error.error_kind == ErrorKind::Access {
-                eprintln!("ERROR: Another WM is already running.");
-                exit(1);
-            }
+        // ewmh clients look for a child of the root naming the wm before they trust
+        // anything in `_NET_SUPPORTED`
+        let wm_check = conn.generate_id()?;
+        conn.create_window(
+            0,
+            wm_check,
+            screen.root,
+            -1,
+            -1,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            0,
+            &CreateWindowAux::new(),
+        )?;
+        for win in [screen.root, wm_check] {
+            conn.change_property32(
+                PropMode::REPLACE,
+                win,
+                atoms._NET_SUPPORTING_WM_CHECK,
+                AtomEnum::WINDOW,
+                &[wm_check],
+            )?;
         }
-
+        conn.change_property8(
+            PropMode::REPLACE,
+            wm_check,
+            atoms._NET_WM_NAME,
+            atoms.UTF8_STRING,
+            b"wwm",
+        )?;
+        conn.change_property32(
+            PropMode::REPLACE,
+            screen.root,
+            atoms._NET_SUPPORTED,
+            AtomEnum::ATOM,
+            &Self::supported_atoms(atoms),
+        )?;
         conn.sync()?;
+        Ok(wm_check)
+    }
 
-        res
+    // the ewmh hints and properties we handle
+    fn supported_atoms(atoms: &AtomCollection) -> Vec<Atom> {
+        vec![
+            atoms._NET_SUPPORTED,
+            atoms._NET_SUPPORTING_WM_CHECK,
+            atoms._NET_CLIENT_LIST,
+            atoms._NET_ACTIVE_WINDOW,
+            atoms._NET_CURRENT_DESKTOP,
+            atoms._NET_NUMBER_OF_DESKTOPS,
+            atoms._NET_DESKTOP_NAMES,
+            atoms._NET_DESKTOP_VIEWPORT,
+            atoms._NET_WORKAREA,
+            atoms._NET_FRAME_EXTENTS,
+            atoms._NET_REQUEST_FRAME_EXTENTS,
+            atoms._NET_WM_NAME,
+            atoms._NET_WM_DESKTOP,
+            atoms._NET_WM_ICON,
+            atoms._NET_WM_PING,
+            atoms._NET_WM_STRUT,
+            atoms._NET_WM_STRUT_PARTIAL,
+            atoms._NET_WM_FULLSCREEN_MONITORS,
+            atoms._NET_WM_STATE,
+            atoms._NET_WM_STATE_FULLSCREEN,
+            atoms._NET_WM_STATE_DEMANDS_ATTENTION,
+            atoms._NET_WM_STATE_ABOVE,
+            atoms._NET_WM_STATE_BELOW,
+            atoms._NET_WM_WINDOW_TYPE,
+            atoms._NET_WM_WINDOW_TYPE_DIALOG,
+            atoms._NET_WM_WINDOW_TYPE_DOCK,
+            atoms._NET_WM_WINDOW_TYPE_NOTIFICATION,
+            atoms._NET_WM_WINDOW_TYPE_TOOLTIP,
+            atoms._NET_WM_WINDOW_TYPE_SPLASH,
+            atoms._NET_WM_WINDOW_TYPE_UTILITY,
+        ]
     }
 
+    // the client stays managed until it is gone, which it might not be if it asks to save
+    // something first or has hung
     fn destroy_window(&mut self) -> Result<(), ReplyOrIdError> {
-        let win = if let Some(cidx) = self.monitors[self.selmon].client {
-            self.monitors[self.selmon].clients[cidx].window
-        } else {
+        let m = &self.monitors[self.selmon];
+        let Some(ci) = m.client else {
             return Ok(());
         };
-
-        self.detach(win, self.selmon);
+        let c = &m.clients[ci];
+        let (win, supports_ping, is_unresponsive) = (c.window, c.supports_ping, c.is_unresponsive);
 
         let delete_exists = self.window_property_exists(
             win,
@@ -193,8 +922,14 @@ impl<'a, C: Connection> WinMan<'a, C> {
             self.atoms.ATOM,
         )?;
 
-        if delete_exists {
+        // a client that stopped responding can't close itself so it is killed instead
+        if delete_exists && !is_unresponsive {
             self.send_event(win, self.atoms.WM_DELETE_WINDOW)?;
+            if supports_ping {
+                self.send_ping(win)?;
+                self.timers
+                    .schedule(WTimerEvent::Ping, Duration::from_millis(ping::TIMEOUT));
+            }
         } else {
             self.conn.grab_server()?;
             self.conn.set_close_down_mode(CloseDown::DESTROY_ALL)?;
@@ -202,23 +937,160 @@ impl<'a, C: Connection> WinMan<'a, C> {
             self.conn.sync()?;
             self.conn.ungrab_server()?;
         }
-        self.recompute_layout(self.selmon)?;
-        self.focus()?;
-        self.warp_pointer_to_focused_client()?;
 
         Ok(())
     }
 
+    // marks the client pinged last as unresponsive if it didn't answer, then pings the
+    // focused client
+    fn ping(&mut self) -> Result<(), ReplyOrIdError> {
+        if let Some((mon_idx, ci)) = self.pending_ping.and_then(|w| self.win_to_client(w)) {
+            self.set_unresponsive(mon_idx, ci, true);
+        }
+        self.pending_ping = None;
+
+        let m = &self.monitors[self.selmon];
+        if let Some(c) = m.client.map(|ci| &m.clients[ci]) {
+            if c.supports_ping {
+                self.send_ping(c.window)?;
+            }
+        }
+        self.timers
+            .schedule(WTimerEvent::Ping, Duration::from_millis(ping::INTERVAL));
+        Ok(())
+    }
+
+    fn send_ping(&mut self, win: Window) -> Result<(), ReplyError> {
+        let event = ClientMessageEvent::new(
+            32,
+            win,
+            self.atoms.WM_PROTOCOLS,
+            [self.atoms._NET_WM_PING, CURRENT_TIME, win, 0, 0],
+        );
+        self.conn
+            .send_event(false, win, EventMask::NO_EVENT, event)?;
+        self.pending_ping = Some(win);
+        Ok(())
+    }
+
+    fn set_unresponsive(&mut self, mon_idx: usize, ci: usize, is_unresponsive: bool) {
+        let m = &mut self.monitors[mon_idx];
+        let c = &mut m.clients[ci];
+        if c.is_unresponsive == is_unresponsive {
+            return;
+        }
+        c.is_unresponsive = is_unresponsive;
+        if m.client == Some(ci) {
+            m.bar.update_title(m.clients[ci].bar_text());
+        }
+    }
+
     fn detach(&mut self, win: Window, monitor: usize) {
         let m = &mut self.monitors[monitor];
 
-        let remove_idx = m.clients.iter().position(|c| c.window == win);
-        if let Some(i) = remove_idx {
+        if let Some(i) = m.client_idx(win) {
             m.remove_client(i);
         }
     }
 
-    fn get_window_title(&mut self, window: Window) -> Result<String, ReplyOrIdError> {
+    // pagers see the tags of the focused monitor as the desktops, which all share
+    // the one viewport there is
+    fn publish_desktops(&self) -> Result<(), ReplyOrIdError> {
+        let root = self.screen.root;
+        self.conn.change_property32(
+            PropMode::REPLACE,
+            root,
+            self.atoms._NET_NUMBER_OF_DESKTOPS,
+            AtomEnum::CARDINAL,
+            &[self.config.tag_count as u32],
+        )?;
+        let names: Vec<u8> = (0..self.config.tag_count)
+            .flat_map(|i| format!("{}\0", self.config.tag_name(i)).into_bytes())
+            .collect();
+        self.conn.change_property8(
+            PropMode::REPLACE,
+            root,
+            self.atoms._NET_DESKTOP_NAMES,
+            self.atoms.UTF8_STRING,
+            &names,
+        )?;
+        self.conn.change_property32(
+            PropMode::REPLACE,
+            root,
+            self.atoms._NET_DESKTOP_VIEWPORT,
+            AtomEnum::CARDINAL,
+            &vec![0; self.config.tag_count * 2],
+        )?;
+        self.update_workarea()?;
+        self.update_current_desktop()
+    }
+
+    // the area of the focused monitor that is left for clients by the bar and any docks.
+    // every desktop shares it since they are the tags of whichever monitor is focused.
+    fn update_workarea(&self) -> Result<(), ReplyOrIdError> {
+        let r = self.monitors[self.selmon].rect;
+        let area = [r.x as u32, r.y as u32, r.w as u32, r.h as u32];
+        self.conn.change_property32(
+            PropMode::REPLACE,
+            self.screen.root,
+            self.atoms._NET_WORKAREA,
+            AtomEnum::CARDINAL,
+            &area.repeat(self.config.tag_count),
+        )?;
+        Ok(())
+    }
+
+    // the border is the only frame a client gets
+    fn set_frame_extents(&self, win: Window, bw: u16) -> Result<(), ReplyOrIdError> {
+        self.conn.change_property32(
+            PropMode::REPLACE,
+            win,
+            self.atoms._NET_FRAME_EXTENTS,
+            AtomEnum::CARDINAL,
+            &[bw as u32; 4],
+        )?;
+        Ok(())
+    }
+
+    fn update_current_desktop(&self) -> Result<(), ReplyOrIdError> {
+        self.conn.change_property32(
+            PropMode::REPLACE,
+            self.screen.root,
+            self.atoms._NET_CURRENT_DESKTOP,
+            AtomEnum::CARDINAL,
+            &[self.monitors[self.selmon].tag as u32],
+        )?;
+        Ok(())
+    }
+
+    // asks the status modules for a new status, or shows the name of the root window
+    // right away when that is the status
+    fn refresh_status(&mut self) -> Result<(), ReplyOrIdError> {
+        self.pause_unseen_status();
+        if self.config.status_source == WStatusSource::Modules {
+            self.status.request();
+            return Ok(());
+        }
+        let reply = self
+            .conn
+            .get_property(
+                false,
+                self.screen.root,
+                AtomEnum::WM_NAME,
+                AtomEnum::ANY,
+                0,
+                u32::MAX,
+            )?
+            .reply()?;
+        let name: Vec<u8> = reply.value8().into_iter().flatten().collect();
+        let status = String::from_utf8_lossy(&name);
+        for m in self.monitors.iter_mut() {
+            m.bar.set_status(status.trim());
+        }
+        Ok(())
+    }
+
+    fn get_window_title(&self, window: Window) -> Result<String, ReplyOrIdError> {
         if let Ok(reply) = self.conn.get_property(
             false,
             window,
@@ -229,40 +1101,109 @@ impl<'a, C: Connection> WinMan<'a, C> {
         ) {
             if let Ok(reply) = reply.reply() {
                 if let Some(text) = reply.value8() {
-                    let text = String::from_utf8(text.collect()).unwrap();
-                    return Ok(text);
+                    let text: Vec<u8> = text.collect();
+                    return Ok(String::from_utf8_lossy(&text).into_owned());
                 }
             }
         }
         Ok(String::new())
     }
 
+    fn get_window_icon(&self, window: Window, size: u16) -> Result<Option<WIcon>, ReplyOrIdError> {
+        let reply = self
+            .conn
+            .get_property(
+                false,
+                window,
+                self.atoms._NET_WM_ICON,
+                AtomEnum::CARDINAL,
+                0,
+                u32::MAX,
+            )?
+            .reply();
+        let icon = reply.ok().and_then(|r| {
+            let data: Vec<u32> = r.value32()?.collect();
+            WIcon::from_net_wm_icon(&data, size)
+        });
+        Ok(icon)
+    }
+
+    fn has_no_decorations(&self, window: Window) -> Result<bool, ReplyOrIdError> {
+        let reply = self
+            .conn
+            .get_property(
+                false,
+                window,
+                self.atoms._MOTIF_WM_HINTS,
+                self.atoms._MOTIF_WM_HINTS,
+                0,
+                5,
+            )?
+            .reply();
+        let no_decorations = reply.ok().is_some_and(|r| {
+            r.value32()
+                .is_some_and(|v| motif_no_decorations(&v.collect::<Vec<_>>()))
+        });
+        Ok(no_decorations)
+    }
+
+    fn get_window_class(&self, window: Window) -> Result<String, ReplyOrIdError> {
+        let class = WmClass::get(self.conn, window)?
+            .reply()
+            .map(|c| String::from_utf8_lossy(c.class()).into_owned())
+            .unwrap_or_default();
+        Ok(class)
+    }
+
     fn focus(&mut self) -> Result<(), ReplyOrIdError> {
-        let (win, mon) = {
+        let (ci, win, mon, title, accepts_input, takes_focus) = {
             let m = &mut self.monitors[self.selmon];
             if let Some(ci) = m.client {
-                let c = m.clients[ci];
-                (c.window, c.monitor)
+                m.clear_urgency(ci);
+                let c = &m.clients[ci];
+                let title = c.bar_text();
+                (
+                    ci,
+                    c.window,
+                    c.monitor,
+                    title,
+                    c.accepts_input,
+                    c.takes_focus,
+                )
             } else {
                 m.bar.update_title("");
+                m.bar.set_icon(self.conn, None)?;
                 self.conn.set_input_focus(
                     InputFocus::POINTER_ROOT,
                     self.screen.root,
                     CURRENT_TIME,
                 )?;
+                self.update_current_desktop()?;
+                self.draw_title_bars();
+                self.draw_tab_strips();
+                self.emit(event::focus(None, ""));
                 return Ok(());
             }
         };
 
-        self.conn.sync()?;
-
-        let name = self.get_window_title(win)?;
-        self.monitors[mon].bar.update_title(name);
+        let m = &mut self.monitors[mon];
+        m.bar.update_title(title);
+        let icon = m.clients[ci].icon.clone();
+        m.bar.set_icon(self.conn, icon.as_ref())?;
 
-        self.conn
-            .set_input_focus(InputFocus::POINTER_ROOT, win, CURRENT_TIME)?;
-
-        self.send_event(win, self.atoms.WM_TAKE_FOCUS)?;
+        // of the focus models of the icccm, passive and locally active clients get the
+        // focus set on them while locally and globally active ones are asked to take it.
+        // no input clients never get it.
+        if accepts_input {
+            self.conn
+                .set_input_focus(InputFocus::POINTER_ROOT, win, CURRENT_TIME)?;
+        } else if !takes_focus {
+            self.conn
+                .set_input_focus(InputFocus::POINTER_ROOT, self.screen.root, CURRENT_TIME)?;
+        }
+        if takes_focus {
+            self.send_event(win, self.atoms.WM_TAKE_FOCUS)?;
+        }
         self.conn.change_property(
             PropMode::REPLACE,
             self.screen.root,
@@ -272,10 +1213,21 @@ impl<'a, C: Connection> WinMan<'a, C> {
             1,
             &win.to_ne_bytes(),
         )?;
+        self.update_current_desktop()?;
 
-        let focus_aux =
-            ChangeWindowAttributesAux::new().border_pixel(theme::window::BORDER_FOCUSED);
-        self.conn.change_window_attributes(win, &focus_aux)?;
+        border::paint(
+            self.conn,
+            &self.config.theme,
+            &self.monitors[self.selmon].clients[ci],
+            true,
+        )?;
+        self.mouse.update_client_grab(self.conn, win, true)?;
+        self.raise_tab(self.selmon, ci)?;
+        self.restack(self.selmon)?;
+        self.draw_title_bars();
+        self.draw_tab_strips();
+        let title = self.monitors[self.selmon].clients[ci].title.clone();
+        self.emit(event::focus(Some(win), &title));
 
         Ok(())
     }
@@ -338,37 +1290,179 @@ impl<'a, C: Connection> WinMan<'a, C> {
     fn get_monitors(
         conn: &'a C,
         screen: &Screen,
-        text_renderer: &Rc<TextRenderer<'a, C>>,
-    ) -> Result<Vec<WMonitor<'a, C>>, ReplyError> {
+        config: &WConfig,
+    ) -> Result<Vec<WMonitor<'a, C>>, ReplyOrIdError> {
         let monitors = conn.randr_get_monitors(screen.root, true)?.reply()?;
-        let monitors: Vec<WMonitor<C>> = monitors
+        Ok(monitors
             .monitors
             .iter()
-            .map(|m| WMonitor::new(m, conn, Rc::clone(text_renderer)))
-            .collect();
-        Ok(monitors)
+            .map(|m| WMonitor::new(m, conn, config))
+            .collect())
+    }
+
+    fn handle_button_press(&mut self, evt: ButtonPressEvent) -> Result<(), ReplyOrIdError> {
+        self.interrupt_mod_tap();
+        let m = &mut self.monitors[self.selmon];
+        if m.bar.has_pointer(evt.root_x, evt.root_y) {
+            if let Some(idx) = m.bar.select_tag_at_pos(evt.event_x, evt.event_y) {
+                if evt.detail == u8::from(DRAG_BUTTON) {
+                    self.drag_tag = Some((self.selmon, idx));
+                }
+                self.select_tag(idx, false)?;
+            } else if let Some(idx) = m.bar.status_section_at(evt.event_x) {
+                self.status.click(idx);
+            }
+            return Ok(());
+        }
+
+        if self.overview.as_ref().map(|o| o.overlay.window) == Some(evt.event) {
+            return self.click_overview(evt.event_x, evt.event_y);
+        }
+
+        if let Some(t) = self
+            .title_bars
+            .iter()
+            .find(|t| t.overlay.window == evt.event)
+        {
+            let (window, close) = (t.window, t.on_close_button(evt.event_x));
+            return self.click_title_bar(window, close);
+        }
+
+        let tab_strip = self.monitors.iter().position(|m| {
+            m.tab_strip
+                .as_ref()
+                .is_some_and(|t| t.overlay.window == evt.event)
+        });
+        if let Some(mon_idx) = tab_strip {
+            return self.click_tab(mon_idx, evt.event_x);
+        }
+
+        // only unfocused clients have their buttons grabbed with click-to-focus
+        if let Some((mon_idx, client_idx)) = self.win_to_client(evt.event) {
+            return self.click_to_focus(mon_idx, client_idx, evt.time);
+        }
+
+        let mut action = WMouseCommand::Idle;
+        for bind in &self.mouse.binds {
+            if u8::from(bind.button) != evt.detail || bind.mods_as_key_but_mask() != evt.state {
+                continue;
+            }
+
+            // the child is unset when the pointer is on the root window itself.
+            // root window binds take precedence over other binds on the same button.
+            if !bind.root_only {
+                action = bind.action;
+            } else if evt.child == NONE {
+                action = bind.action;
+                break;
+            }
+        }
+
+        match action {
+            WMouseCommand::CycleTag(dir) => self.cycle_tag_at_pointer(evt, dir)?,
+            WMouseCommand::AdjustMainWidth(dir) => self.adjust_main_width_at_pointer(evt, dir)?,
+            WMouseCommand::Gesture => {
+                let pos = WPos::new(evt.root_x, evt.root_y);
+                self.gesture = Some((pos, pos));
+            }
+            WMouseCommand::Run(cmd) => {
+                // spawned programs should end up on the monitor that was clicked
+                self.select_monitor_at(WPos::new(evt.root_x, evt.root_y))?;
+                self.spawn_program(cmd);
+            }
+            _ => self.manipulate_client_dims(evt, action)?,
+        }
+
+        Ok(())
+    }
+
+    fn click_to_focus(
+        &mut self,
+        mon_idx: usize,
+        client_idx: usize,
+        time: u32,
+    ) -> Result<(), ReplyOrIdError> {
+        self.unfocus(self.selmon)?;
+        self.set_selmon(mon_idx)?;
+        self.monitors[mon_idx].client = Some(client_idx);
+
+        let win = self.monitors[mon_idx].clients[client_idx].window;
+        self.raise(mon_idx, win)?;
+        self.focus()?;
+
+        // let the client receive the click as well
+        self.conn.allow_events(Allow::REPLAY_POINTER, time)?;
+        Ok(())
+    }
+
+    fn click_title_bar(&mut self, window: Window, close: bool) -> Result<(), ReplyOrIdError> {
+        let Some((mon_idx, client_idx)) = self.win_to_client(window) else {
+            return Ok(());
+        };
+        self.unfocus(self.selmon)?;
+        self.set_selmon(mon_idx)?;
+        self.monitors[mon_idx].client = Some(client_idx);
+        if close {
+            self.destroy_window()
+        } else {
+            self.focus()
+        }
+    }
+
+    fn cycle_tag_at_pointer(
+        &mut self,
+        evt: ButtonPressEvent,
+        dir: WDirection,
+    ) -> Result<(), ReplyOrIdError> {
+        self.select_monitor_at(WPos::new(evt.root_x, evt.root_y))?;
+        self.cycle_tag(dir)
+    }
+
+    fn cycle_tag(&mut self, dir: WDirection) -> Result<(), ReplyOrIdError> {
+        let tag = self.monitors[self.selmon].tag;
+        let new_tag = match dir {
+            WDirection::Prev if tag == 0 => self.config.tag_count - 1,
+            WDirection::Prev => tag - 1,
+            WDirection::Next if tag == self.config.tag_count - 1 => 0,
+            WDirection::Next => tag + 1,
+        };
+
+        self.select_tag(new_tag, false)
     }
 
-    fn handle_button_press(&mut self, evt: ButtonPressEvent) -> Result<(), ReplyOrIdError> {
-        let m = &mut self.monitors[self.selmon];
-        if m.bar.has_pointer(evt.root_x, evt.root_y) {
-            if let Some(idx) = m.bar.select_tag_at_pos(evt.event_x, evt.event_y) {
-                self.select_tag(idx, false)?;
+    fn adjust_main_width_at_pointer(
+        &mut self,
+        evt: ButtonPressEvent,
+        dir: WDirection,
+    ) -> Result<(), ReplyOrIdError> {
+        // floating clients are not part of the layout
+        if let Some((mon_idx, ci)) = self.win_to_client(evt.child) {
+            if self.monitors[mon_idx].clients[ci].is_floating {
+                return Ok(());
             }
-            return Ok(());
         }
 
-        let mut action = WMouseCommand::Idle;
-        for bind in &self.mouse.binds {
-            if u8::from(bind.button) == evt.detail && bind.mods_as_key_but_mask() == evt.state {
-                action = bind.action;
-                break;
-            }
+        self.select_monitor_at(WPos::new(evt.root_x, evt.root_y))?;
+        self.adjust_main_width(dir)
+    }
+
+    fn select_monitor_at(&mut self, pos: WPos) -> Result<(), ReplyOrIdError> {
+        if let Some(mon_idx) = self.monitors.iter().position(|m| m.has_pos(pos)) {
+            self.set_selmon(mon_idx)?;
         }
+        Ok(())
+    }
 
-        self.manipulate_client_dims(evt, action)?;
+    fn set_selmon(&mut self, mon_idx: usize) -> Result<(), ReplyOrIdError> {
+        if mon_idx == self.selmon {
+            return Ok(());
+        }
 
-        Ok(())
+        self.unfocus(self.selmon)?;
+        self.monitors[self.selmon].bar.set_is_focused(false);
+        self.monitors[mon_idx].bar.set_is_focused(true);
+        self.selmon = mon_idx;
+        self.update_workarea()
     }
 
     fn manipulate_client_dims(
@@ -421,8 +1515,14 @@ impl<'a, C: Connection> WinMan<'a, C> {
                 &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
             )?;
 
+            // there is nothing to take the client out of in the floating layout
+            if m.layout == WLayout::Floating {
+                return self.restack(self.selmon);
+            }
+            // a client tiled alone may have had its border taken away
+            c.bw = c.decorated_bw(BORDER_WIDTH);
             c.is_floating = true;
-            self.recompute_layout(self.selmon).unwrap();
+            self.recompute_layout(self.selmon)?;
         }
         Ok(())
     }
@@ -439,6 +1539,7 @@ impl<'a, C: Connection> WinMan<'a, C> {
     ) -> Result<bool, ReplyOrIdError> {
         let m = &self.monitors[mon_idx];
         let mon_rect = m.rect;
+        let bh = m.bar_height;
         let c = &mut self.monitors[mon_idx].clients[c_idx];
 
         w = w.min(1);
@@ -476,7 +1577,6 @@ impl<'a, C: Connection> WinMan<'a, C> {
                 y = mon_rect.y;
             }
         }
-        let bh = bar_height();
         if h < bh {
             h = bh;
         }
@@ -506,15 +1606,72 @@ impl<'a, C: Connection> WinMan<'a, C> {
         }
     }
 
-    fn handle_button_release(&mut self, evt: ButtonReleaseEvent) -> Result<(), ReplyError> {
+    fn handle_button_release(&mut self, evt: ButtonReleaseEvent) -> Result<(), ReplyOrIdError> {
+        let pos = WPos::new(evt.root_x, evt.root_y);
         if evt.detail == u8::from(DRAG_BUTTON) {
-            self.drag_window = None;
-        } else if evt.detail == u8::from(RESIZE_BUTTON) {
-            self.resize_window = None;
+            if let Some((mon_idx, from)) = self.drag_tag.take() {
+                // the bar gets the release since it got the press, so the position is
+                // relative to it
+                let to = self.monitors[mon_idx]
+                    .bar
+                    .tag_at_pos(evt.event_x, evt.event_y);
+                if let Some(to) = to.filter(|to| *to != from) {
+                    self.move_tag(mon_idx, from, to)?;
+                }
+            } else if let Some((oc_pos, op_pos, _)) = self.drag_window.take() {
+                // motion might have been skipped right before the release
+                self.mouse_move(oc_pos, op_pos, pos)?;
+                self.drop_client_at(pos)?;
+            } else if let Some((start, end)) = self.gesture.take() {
+                self.finish_gesture(start, end)?;
+            }
+        } else if evt.detail == u8::from(RESIZE_BUTTON) && self.resize_window.take().is_some() {
+            self.mouse_resize(pos)?;
+        }
+        Ok(())
+    }
+
+    fn finish_gesture(&mut self, start: WPos, end: WPos) -> Result<(), ReplyOrIdError> {
+        let gesture = WGesture::from_delta(end.x - start.x, end.y - start.y, GESTURE_THRESHOLD);
+        let action = self
+            .mouse
+            .gestures
+            .iter()
+            .find(|(g, _)| Some(*g) == gesture)
+            .map(|(_, action)| action.clone());
+
+        if let Some(action) = action {
+            self.handle_key_command(action)?;
         }
         Ok(())
     }
 
+    // hands a dragged client over to the monitor it was dropped on
+    fn drop_client_at(&mut self, pos: WPos) -> Result<(), ReplyOrIdError> {
+        let dest = match self.monitors.iter().position(|m| m.has_pos(pos)) {
+            Some(idx) if idx != self.selmon => idx,
+            _ => return Ok(()),
+        };
+
+        let ci = match self.monitors[self.selmon].client {
+            Some(ci) => ci,
+            None => return Ok(()),
+        };
+
+        self.unfocus(self.selmon)?;
+
+        let mut c = self.monitors[self.selmon].remove_client(ci);
+        c.monitor = dest;
+        c.tag = self.monitors[dest].tag;
+        self.monitors[dest].push_client(c, true);
+
+        self.recompute_layout(self.selmon)?;
+        self.set_selmon(dest)?;
+        self.recompute_layout(dest)?;
+        self.focus()?;
+        Ok(())
+    }
+
     fn handle_configure_request(
         &mut self,
         evt: ConfigureRequestEvent,
@@ -532,7 +1689,7 @@ impl<'a, C: Connection> WinMan<'a, C> {
             let value_mask = WConfigWindow::from(evt.value_mask);
             if value_mask & WConfigWindow::BORDER_WIDTH {
                 self.monitors[mon_idx].clients[client_idx].bw = evt.border_width;
-            } else if is_floating {
+            } else if is_floating || self.monitors[mon_idx].layout == WLayout::Floating {
                 if value_mask & WConfigWindow::X {
                     old_rect.x = rect.x;
                     rect.x = mr.x + evt.x;
@@ -619,6 +1776,7 @@ impl<'a, C: Connection> WinMan<'a, C> {
     }
 
     fn handle_destroy(&mut self, evt: DestroyNotifyEvent) -> Result<(), ReplyOrIdError> {
+        self.remove_strut(evt.window)?;
         if self.win_to_client(evt.window).is_some() {
             self.unmanage(evt.window, true)?;
         }
@@ -631,7 +1789,10 @@ impl<'a, C: Connection> WinMan<'a, C> {
             return Ok(());
         }
 
-        if self.resize_window.is_some() || self.drag_window.is_some() {
+        if self.resize_window.is_some()
+            || self.drag_window.is_some()
+            || mouse::FOCUS_MODEL == WFocusModel::ClickToFocus
+        {
             return Ok(());
         }
 
@@ -662,13 +1823,30 @@ impl<'a, C: Connection> WinMan<'a, C> {
             Event::MapRequest(e) => self.handle_map_request(e)?,
             Event::Expose(e) => self.handle_expose(e),
             Event::EnterNotify(e) => self.handle_enter(e)?,
+            // only bars listen for the pointer leaving them
+            Event::LeaveNotify(_) => self.hide_tag_preview()?,
             Event::DestroyNotify(e) => self.handle_destroy(e)?,
             Event::ButtonPress(e) => self.handle_button_press(e)?,
             Event::ButtonRelease(e) => self.handle_button_release(e)?,
             Event::MotionNotify(e) => self.handle_motion_notify(e)?,
             Event::KeyPress(e) => self.handle_key_press(e)?,
+            Event::KeyRelease(e) if self.keyboard.stop_repeat(e.detail) => {
+                self.timers.cancel(WTimerEvent::KeyRepeat)
+            }
             Event::PropertyNotify(e) => self.handle_property_notify(e)?,
             Event::ClientMessage(e) => self.handle_client_message(e)?,
+            Event::XkbNewKeyboardNotify(_) | Event::XkbMapNotify(_) => {
+                self.keyboard.update_keymap(self.conn, self.screen.root)?;
+                self.update_kb_layout_indicator();
+            }
+            Event::XkbStateNotify(e) => self.handle_xkb_state_notify(e)?,
+            Event::XinputRawKeyPress(e) => self.handle_raw_key_press(e),
+            // another wm is replacing us
+            Event::SelectionClear(e) if e.selection == self.selection.atom => {
+                return Ok(ShouldExit::Yes)
+            }
+            // errors of unchecked requests for windows that are already gone are expected
+            Event::Error(e) if self.is_stale_window_error(&e) => {}
             Event::Error(e) => eprintln!("ERROR: {e:#?}"),
             _ => {}
         }
@@ -679,107 +1857,645 @@ impl<'a, C: Connection> WinMan<'a, C> {
     fn handle_client_message(&mut self, evt: ClientMessageEvent) -> Result<(), ReplyOrIdError> {
         if evt.type_ == self.atoms._NET_WM_STATE {
             let data = evt.data.as_data32();
-            if data[1] == self.atoms._NET_WM_STATE_FULLSCREEN
-                || data[2] == self.atoms._NET_WM_STATE_FULLSCREEN
-            {
-                if let Some((mon_idx, client_idx)) = self.win_to_client(evt.window) {
-                    let c = &self.monitors[mon_idx].clients[client_idx];
-                    let fullscreen = data[0] == self.atoms._NET_WM_STATE_ADD
-                        || (data[0] == self.atoms._NET_WM_STATE_TOGGLE && !c.is_fullscreen);
-                    self.fullscreen(mon_idx, fullscreen)?;
+            let Some((mon_idx, client_idx)) = self.win_to_client(evt.window) else {
+                return Ok(());
+            };
+            let states = [data[1], data[2]];
+            if states.contains(&self.atoms._NET_WM_STATE_FULLSCREEN) {
+                let c = &self.monitors[mon_idx].clients[client_idx];
+                let fullscreen = wm_state_requested(data[0], c.is_fullscreen);
+                self.fullscreen(mon_idx, client_idx, fullscreen)?;
+            }
+            let layers = [
+                (self.atoms._NET_WM_STATE_ABOVE, WStackLayer::Above),
+                (self.atoms._NET_WM_STATE_BELOW, WStackLayer::Below),
+            ];
+            for (atom, layer) in layers {
+                if !states.contains(&atom) {
+                    continue;
+                }
+                let c = &mut self.monitors[mon_idx].clients[client_idx];
+                let is_set = c.stack_layer == layer;
+                if wm_state_requested(data[0], is_set) {
+                    c.stack_layer = layer;
+                } else if is_set {
+                    c.stack_layer = WStackLayer::Normal;
+                }
+                self.restack(mon_idx)?;
+            }
+            if states.contains(&self.atoms._NET_WM_STATE_DEMANDS_ATTENTION) {
+                let c = &self.monitors[mon_idx].clients[client_idx];
+                let urgent = wm_state_requested(data[0], c.is_urgent);
+                self.set_urgent(mon_idx, client_idx, urgent);
+            }
+        } else if evt.type_ == self.atoms._NET_CURRENT_DESKTOP {
+            // pagers (and `wmctrl -s`) address desktops by index,
+            // which we map onto the tags of the focused monitor
+            let tag = evt.data.as_data32()[0] as usize;
+            if tag < self.config.tag_count {
+                self.select_tag(tag, true)?;
+            }
+        } else if evt.type_ == self.atoms._NET_WM_DESKTOP {
+            // moving a client onto all desktops (0xFFFFFFFF) isn't supported
+            let tag = evt.data.as_data32()[0] as usize;
+            if let Some((mon_idx, client_idx)) = self.win_to_client(evt.window) {
+                if tag < self.config.tag_count {
+                    self.client_to_tag(mon_idx, client_idx, tag)?;
+                }
+            }
+        } else if evt.type_ == self.atoms.WM_PROTOCOLS {
+            // clients answer pings by sending them back to the root window
+            let data = evt.data.as_data32();
+            if data[0] == self.atoms._NET_WM_PING {
+                let win = data[2];
+                if self.pending_ping == Some(win) {
+                    self.pending_ping = None;
+                }
+                if let Some((mon_idx, client_idx)) = self.win_to_client(win) {
+                    self.set_unresponsive(mon_idx, client_idx, false);
+                }
+            }
+        } else if evt.type_ == self.atoms._NET_WM_FULLSCREEN_MONITORS {
+            // the monitors are given by their index for the top, bottom, left and right edge
+            let data = evt.data.as_data32();
+            let monitors = [data[0], data[1], data[2], data[3]].map(|i| i as usize);
+            if let Some((mon_idx, client_idx)) = self.win_to_client(evt.window) {
+                if monitors.iter().all(|i| *i < self.monitors.len()) {
+                    self.set_fullscreen_monitors(mon_idx, client_idx, monitors)?;
                 }
             }
+        } else if evt.type_ == self.atoms._NET_REQUEST_FRAME_EXTENTS {
+            // asked before the window is mapped, so it is usually not managed yet
+            let bw = match self.win_to_client(evt.window) {
+                Some((mon_idx, client_idx)) => self.monitors[mon_idx].clients[client_idx].bw,
+                None => BORDER_WIDTH,
+            };
+            self.set_frame_extents(evt.window, bw)?;
+        } else if evt.type_ == self.atoms._NET_ACTIVE_WINDOW {
+            if let Some((mon_idx, client_idx)) = self.win_to_client(evt.window) {
+                self.activate_client(mon_idx, client_idx)?;
+            }
+        }
+        Ok(())
+    }
+
+    // the focused client has the user's attention already
+    fn set_urgent(&mut self, mon_idx: usize, client_idx: usize, urgent: bool) {
+        let m = &mut self.monitors[mon_idx];
+        let focused = mon_idx == self.selmon && m.client == Some(client_idx);
+        m.set_urgent(client_idx, urgent && !focused);
+    }
+
+    fn get_wm_hints(&self, window: Window) -> Result<Option<WmHints>, ReplyOrIdError> {
+        Ok(WmHints::get(self.conn, window)?.reply().ok())
+    }
+
+    fn focus_urgent(&mut self) -> Result<(), ReplyOrIdError> {
+        let urgent = self.monitors.iter().enumerate().find_map(|(mon_idx, m)| {
+            m.clients
+                .iter()
+                .position(|c| c.is_urgent)
+                .map(|ci| (mon_idx, ci))
+        });
+        if let Some((mon_idx, client_idx)) = urgent {
+            self.activate_client(mon_idx, client_idx)?;
         }
         Ok(())
     }
 
+    fn activate_client(&mut self, mon_idx: usize, client_idx: usize) -> Result<(), ReplyOrIdError> {
+        self.unfocus(self.selmon)?;
+        self.set_selmon(mon_idx)?;
+
+        // bring the client's tag into view before focusing it
+        let tag = self.monitors[mon_idx].clients[client_idx].tag;
+        self.select_tag(tag, false)?;
+        self.unfocus(mon_idx)?;
+
+        self.monitors[mon_idx].client = Some(client_idx);
+        self.focus()?;
+        self.warp_pointer_to_focused_client()?;
+        Ok(())
+    }
+
     fn handle_expose(&mut self, evt: ExposeEvent) {
+        if let Some(hint) = self.hints.iter().find(|h| h.overlay.window == evt.window) {
+            self.draw_hint(hint);
+            return;
+        }
+        if let Some(t) = self
+            .title_bars
+            .iter()
+            .find(|t| t.overlay.window == evt.window)
+        {
+            self.draw_title_bar(t);
+            return;
+        }
+        let tab_strip = self.monitors.iter().position(|m| {
+            m.tab_strip
+                .as_ref()
+                .is_some_and(|t| t.overlay.window == evt.window)
+        });
+        if let Some(mon_idx) = tab_strip {
+            self.draw_tab_strip(mon_idx);
+            return;
+        }
+        if let Some(p) = self
+            .tag_preview
+            .as_ref()
+            .filter(|p| p.overlay.window == evt.window)
+        {
+            self.draw_tag_preview(p);
+            return;
+        }
+        if let Some(o) = self
+            .overview
+            .as_ref()
+            .filter(|o| o.overlay.window == evt.window)
+        {
+            self.draw_overview(o);
+            return;
+        }
+        if let Some(o) = self.osd.as_ref().filter(|o| o.overlay.window == evt.window) {
+            self.draw_osd(o);
+            return;
+        }
+        if self.monitors.iter_mut().any(|m| m.bar.handle_expose(&evt)) {
+            return;
+        }
         self.pending_exposure.insert(evt.window);
     }
 
+    fn handle_xkb_state_notify(&mut self, evt: StateNotifyEvent) -> Result<(), ReplyOrIdError> {
+        if self.keyboard.update_state(&evt) {
+            self.update_kb_layout_indicator();
+        }
+
+        if !self.keyboard.track_mod_tap(&evt) {
+            return Ok(());
+        }
+
+        if self.timers.is_scheduled(WTimerEvent::ModTapTimeout) {
+            self.timers.cancel(WTimerEvent::ModTapTimeout);
+            if let Some(action) = double_tap::ACTION {
+                self.handle_key_command(action)?;
+            }
+        } else {
+            self.timers.schedule(
+                WTimerEvent::ModTapTimeout,
+                Duration::from_millis(double_tap::TIMEOUT),
+            );
+        }
+        Ok(())
+    }
+
+    // any key but a modifier, pressed in whichever window has the focus
+    fn handle_raw_key_press(&mut self, evt: RawKeyPressEvent) {
+        if !keysym::is_modifier(self.keyboard.key_sym(evt.detail)) {
+            self.interrupt_mod_tap();
+        }
+    }
+
+    fn interrupt_mod_tap(&mut self) {
+        self.keyboard.interrupt_mod_tap();
+        self.timers.cancel(WTimerEvent::ModTapTimeout);
+    }
+
     fn handle_key_press(&mut self, evt: KeyPressEvent) -> Result<(), ReplyOrIdError> {
+        self.interrupt_mod_tap();
         let sym = self.keyboard.key_sym(evt.detail.into());
 
+        if !self.hints.is_empty() {
+            return self.select_hint(sym);
+        }
+        if self.overview.is_some() {
+            return self.select_overview_tag(sym);
+        }
+
+        let keybinds = if self.resize_mode {
+            &self.keyboard.resize_mode_keybinds
+        } else {
+            &self.keyboard.keybinds
+        };
+
         let mut action = WKeyCommand::Idle;
-        for bind in &self.keyboard.keybinds {
-            if bind.keysym == sym && evt.state == bind.mods_as_key_but_mask() {
-                action = bind.action;
-                break;
+        for bind in keybinds {
+            if bind.keysym != sym || evt.state != bind.mods_as_key_but_mask() {
+                continue;
+            }
+
+            match bind.context {
+                // keep looking in case there is a bind for the current context
+                WKeybindContext::Always => action = bind.action.clone(),
+                context if self.in_keybind_context(context) => {
+                    action = bind.action.clone();
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        if action.repeats() {
+            // the server keeps sending presses while the key is held, but
+            // repeating is driven by us from the event loop instead
+            if self.keyboard.is_repeating(evt.detail) {
+                return Ok(());
             }
+            self.keyboard.start_repeat(evt.detail, action.clone());
+            self.timers.schedule(
+                WTimerEvent::KeyRepeat,
+                Duration::from_millis(key_repeat::DELAY),
+            );
+        }
+
+        self.handle_key_command(action)
+    }
+
+    fn in_keybind_context(&self, context: WKeybindContext) -> bool {
+        let m = &self.monitors[self.selmon];
+        let focused = m.client.map(|ci| &m.clients[ci]);
+        match context {
+            WKeybindContext::Always => true,
+            WKeybindContext::Layout(layout) => m.layout == layout,
+            WKeybindContext::Floating => focused.is_some_and(|c| c.is_floating),
         }
+    }
 
+    fn handle_key_command(&mut self, action: WKeyCommand) -> Result<(), ReplyOrIdError> {
+        if !matches!(action, WKeyCommand::TagPreview(_)) {
+            self.hide_tag_preview()?;
+        }
+        // the default keybinds cover every tag there is room for
+        if let WKeyCommand::SelectTag(tag)
+        | WKeyCommand::MoveClientToTag(tag)
+        | WKeyCommand::TagPreview(tag) = action
+        {
+            if tag >= self.config.tag_count {
+                return Ok(());
+            }
+        }
         match action {
             WKeyCommand::FocusClient(dir) => self.focus_adjacent(dir)?,
             WKeyCommand::MoveClient(dir) => self.move_adjacent(dir)?,
+            WKeyCommand::Zoom => self.zoom()?,
+            WKeyCommand::FocusUrgent => self.focus_urgent()?,
             WKeyCommand::FocusMonitor(dir) => self.focus_adjacent_monitor(dir)?,
-            WKeyCommand::Spawn(cmd) => self.spawn_program(cmd),
+            WKeyCommand::Spawn(cmd) => self.spawn_program(&cmd),
             WKeyCommand::Destroy => self.destroy_window()?,
             WKeyCommand::AdjustMainWidth(dir) => self.adjust_main_width(dir)?,
-            WKeyCommand::Layout(layout) => self.update_layout(layout),
+            WKeyCommand::IncNMaster(dir) => self.adjust_main_count(dir)?,
+            WKeyCommand::AdjustClientFactor(dir) => self.adjust_client_factor(dir)?,
+            WKeyCommand::AdjustGaps(dir) => self.adjust_gaps(dir)?,
+            WKeyCommand::Layout(layout) => self.update_layout(layout)?,
             WKeyCommand::SelectTag(idx) => self.select_tag(idx, true)?,
             WKeyCommand::MoveClientToTag(ws_idx) => self.move_client_to_tag(ws_idx)?,
             WKeyCommand::MoveClientToMonitor(dir) => self.move_client_to_monitor(dir)?,
             WKeyCommand::UnFloat => self.unfloat_focused_client()?,
             WKeyCommand::Fullscreen => self.fullscreen_focused_client()?,
             WKeyCommand::Exit => self.try_exit(),
+            WKeyCommand::NextKbLayout => self.keyboard.next_layout(self.conn)?,
+            WKeyCommand::Media(cmd) => self.run_media_command(cmd)?,
+            WKeyCommand::CycleTag(dir) => self.cycle_tag(dir)?,
+            WKeyCommand::HintMode => self.enter_hint_mode()?,
+            WKeyCommand::Overview => self.enter_overview()?,
+            WKeyCommand::ToggleTitleBars => self.toggle_title_bars()?,
+            WKeyCommand::ResizePip(dir) => self.resize_pip(dir)?,
+            WKeyCommand::MovePip(dir) => self.move_pip(dir)?,
+            WKeyCommand::TagPreview(tag) => self.toggle_tag_preview(tag)?,
+            WKeyCommand::ResizeMode => self.enter_resize_mode()?,
+            WKeyCommand::ExitMode => self.exit_resize_mode()?,
+            WKeyCommand::MoveFloat(axis, dir) => self.move_float(axis, dir)?,
+            WKeyCommand::ResizeFloat(axis, dir) => self.resize_float(axis, dir)?,
             _ => {}
         }
         Ok(())
     }
 
+    fn update_kb_layout_indicator(&mut self) {
+        let name = self.keyboard.layout_name();
+        for m in self.monitors.iter_mut() {
+            m.bar.set_kb_layout(name.clone());
+        }
+    }
+
+    fn grab_keyboard(&self) -> Result<bool, ReplyOrIdError> {
+        let grab = self
+            .conn
+            .grab_keyboard(
+                true,
+                self.screen.root,
+                CURRENT_TIME,
+                GrabMode::ASYNC,
+                GrabMode::ASYNC,
+            )?
+            .reply()?;
+        Ok(grab.status == GrabStatus::SUCCESS)
+    }
+
+    fn enter_hint_mode(&mut self) -> Result<(), ReplyOrIdError> {
+        if !self.hints.is_empty() || self.resize_mode {
+            return Ok(());
+        }
+        let mut keys = hints::KEYS.chars();
+
+        for (mon_idx, m) in self.monitors.iter().enumerate() {
+            let Some(text_renderer) = self.text_renderer(mon_idx).cloned() else {
+                return Ok(());
+            };
+            let size = m.bar_height;
+
+            for client_idx in m.clients_in_tag(m.tag) {
+                let key = match keys.next() {
+                    Some(key) => key,
+                    None => break,
+                };
+
+                // place the hint in the middle of the client
+                let c = &m.clients[client_idx];
+                let rect = WRect::new(
+                    c.rect.x + (c.rect.w as i16 / 2) - (size as i16 / 2),
+                    c.rect.y + (c.rect.h as i16 / 2) - (size as i16 / 2),
+                    size,
+                    size,
+                );
+
+                let overlay = WOverlay::new(
+                    self.conn,
+                    &text_renderer,
+                    rect,
+                    self.config.theme.bg_selected,
+                )?;
+
+                self.hints.push(WHint {
+                    key,
+                    overlay,
+                    mon_idx,
+                    client_idx,
+                });
+            }
+        }
+
+        if self.hints.is_empty() {
+            return Ok(());
+        }
+
+        // grab the entire keyboard so that hints can be selected without a modifier
+        if !self.grab_keyboard()? {
+            return self.exit_hint_mode();
+        }
+
+        for hint in &self.hints {
+            self.draw_hint(hint);
+        }
+        Ok(())
+    }
+
+    fn draw_hint(&self, hint: &WHint) {
+        let Some(text_renderer) = self.text_renderer(hint.mon_idx) else {
+            return;
+        };
+        let res = hint.overlay.draw(
+            text_renderer,
+            &hint.key.to_string(),
+            self.config.theme.padding,
+            hex_to_rgba(self.config.theme.fg_selected),
+            hex_to_rgba(self.config.theme.bg_selected),
+        );
+        if let Err(e) = res {
+            eprintln!("ERROR: failed to draw hint {}: {e}", hint.key);
+        }
+    }
+
+    fn select_hint(&mut self, sym: u32) -> Result<(), ReplyOrIdError> {
+        // hint keys are ascii letters whose keysyms match their character codes
+        let selected = self
+            .hints
+            .iter()
+            .find(|h| h.key as u32 == sym)
+            .map(|h| (h.mon_idx, h.client_idx));
+
+        // any key press leaves hint mode, whether it selected a client or not
+        self.exit_hint_mode()?;
+
+        if let Some((mon_idx, client_idx)) = selected {
+            self.activate_client(mon_idx, client_idx)?;
+        }
+        Ok(())
+    }
+
+    fn exit_hint_mode(&mut self) -> Result<(), ReplyOrIdError> {
+        for hint in self.hints.drain(..) {
+            hint.overlay.destroy(self.conn)?;
+        }
+        self.conn.ungrab_keyboard(CURRENT_TIME)?;
+        Ok(())
+    }
+
+    fn enter_resize_mode(&mut self) -> Result<(), ReplyOrIdError> {
+        if self.resize_mode || !self.hints.is_empty() {
+            return Ok(());
+        }
+
+        // grab the entire keyboard so that the mode keybinds work without a modifier
+        if !self.grab_keyboard()? {
+            return Ok(());
+        }
+
+        self.resize_mode = true;
+        self.monitors[self.selmon].bar.set_mode(Some("resize"));
+        Ok(())
+    }
+
+    fn exit_resize_mode(&mut self) -> Result<(), ReplyOrIdError> {
+        if !self.resize_mode {
+            return Ok(());
+        }
+
+        self.conn.ungrab_keyboard(CURRENT_TIME)?;
+        self.resize_mode = false;
+
+        // the pointer might have moved focus to another monitor while in the mode
+        for m in self.monitors.iter_mut() {
+            m.bar.set_mode(None);
+        }
+        Ok(())
+    }
+
+    fn float_focused_client(&mut self) -> Result<Option<usize>, ReplyOrIdError> {
+        let m = &mut self.monitors[self.selmon];
+        if let Some(ci) = m.client {
+            let c = &mut m.clients[ci];
+            if c.is_fullscreen {
+                return Ok(None);
+            }
+
+            if !c.is_floating && m.layout != WLayout::Floating {
+                self.conn.configure_window(
+                    c.window,
+                    &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+                )?;
+                c.bw = c.decorated_bw(BORDER_WIDTH);
+                c.is_floating = true;
+                self.recompute_layout(self.selmon)?;
+            }
+            return Ok(Some(ci));
+        }
+        Ok(None)
+    }
+
+    fn move_float(&mut self, axis: WAxis, dir: WDirection) -> Result<(), ReplyOrIdError> {
+        if let Some(ci) = self.float_focused_client()? {
+            let WRect { mut x, mut y, w, h } = self.monitors[self.selmon].clients[ci].rect;
+            let step = match dir {
+                WDirection::Prev => -(resize_mode::STEP as i16),
+                WDirection::Next => resize_mode::STEP as i16,
+            };
+
+            match axis {
+                WAxis::Horizontal => x += step,
+                WAxis::Vertical => y += step,
+            }
+
+            self.resize_client(ci, self.selmon, x, y, w, h)?;
+            self.warp_pointer_to_focused_client()?;
+        }
+        Ok(())
+    }
+
+    fn resize_float(&mut self, axis: WAxis, dir: WDirection) -> Result<(), ReplyOrIdError> {
+        if let Some(ci) = self.float_focused_client()? {
+            let m = &self.monitors[self.selmon];
+            let WRect { x, y, mut w, mut h } = m.clients[ci].rect;
+            let min = m.bar_height;
+            let resize = |size: u16| match dir {
+                WDirection::Prev => size.saturating_sub(resize_mode::STEP).max(min),
+                WDirection::Next => size.saturating_add(resize_mode::STEP),
+            };
+
+            match axis {
+                WAxis::Horizontal => w = resize(w),
+                WAxis::Vertical => h = resize(h),
+            }
+
+            self.resize(ci, self.selmon, x, y, w, h, true)?;
+            self.warp_pointer_to_focused_client()?;
+        }
+        Ok(())
+    }
+
     fn fullscreen_focused_client(&mut self) -> Result<(), ReplyOrIdError> {
         let m = &self.monitors[self.selmon];
         if let Some(ci) = m.client {
             let fullscreen = !m.clients[ci].is_fullscreen;
-            self.fullscreen(self.selmon, fullscreen)?;
+            self.fullscreen(self.selmon, ci, fullscreen)?;
+        }
+        Ok(())
+    }
+
+    fn fullscreen(
+        &mut self,
+        mon_idx: usize,
+        ci: usize,
+        fullscreen: bool,
+    ) -> Result<(), ReplyOrIdError> {
+        let rect = self.fullscreen_area(mon_idx, ci);
+        let c = &mut self.monitors[mon_idx].clients[ci];
+        if fullscreen && !c.is_fullscreen {
+            self.conn.change_property32(
+                PropMode::REPLACE,
+                c.window,
+                self.atoms._NET_WM_STATE,
+                self.atoms.ATOM,
+                &[self.atoms._NET_WM_STATE_FULLSCREEN],
+            )?;
+            c.is_fullscreen = true;
+            c.old_state = c.is_floating;
+            c.old_bw = c.bw;
+            c.bw = 0;
+            c.is_floating = true;
+            self.resize_client(ci, mon_idx, rect.x, rect.y, rect.w, rect.h)?;
+            self.update_bar_visibility(mon_idx)?;
+        } else if !fullscreen && c.is_fullscreen {
+            self.conn.change_property32(
+                PropMode::REPLACE,
+                c.window,
+                self.atoms._NET_WM_STATE,
+                self.atoms.ATOM,
+                &[0],
+            )?;
+            c.is_fullscreen = false;
+            c.is_floating = c.old_state;
+            c.bw = c.old_bw;
+            let r = c.old_rect;
+            self.resize_client(ci, mon_idx, r.x, r.y, r.w, r.h)?;
+            self.recompute_layout(mon_idx)?;
+        }
+        Ok(())
+    }
+
+    // the area a fullscreen client covers, which is all of its monitor including the bar
+    // unless it asked to span several monitors
+    fn fullscreen_area(&self, mon_idx: usize, ci: usize) -> WRect {
+        let area = |i: usize| {
+            let m = &self.monitors[i];
+            let r = m.full_rect;
+            WRect::new(r.x, r.y - m.bar_height as i16, r.w, r.h + m.bar_height)
+        };
+        let Some([top, bottom, left, right]) = self.monitors[mon_idx].clients[ci]
+            .fullscreen_monitors
+            .filter(|ms| ms.iter().all(|i| *i < self.monitors.len()))
+        else {
+            return area(mon_idx);
+        };
+        let (top, bottom, left, right) = (area(top), area(bottom), area(left), area(right));
+        let w = right.x + right.w as i16 - left.x;
+        let h = bottom.y + bottom.h as i16 - top.y;
+        WRect::new(left.x, top.y, w.max(1) as u16, h.max(1) as u16)
+    }
+
+    // clients like video players drop their decorations while they are in use
+    fn update_decorations(&mut self, mon_idx: usize, ci: usize) -> Result<(), ReplyOrIdError> {
+        let no_border = self.has_no_decorations(self.monitors[mon_idx].clients[ci].window)?;
+        let c = &mut self.monitors[mon_idx].clients[ci];
+        if c.no_border == no_border {
+            return Ok(());
+        }
+        c.no_border = no_border;
+        if c.is_floating && !c.is_fullscreen && c.pip.is_none() {
+            c.bw = c.decorated_bw(BORDER_WIDTH);
+            let r = c.rect;
+            self.resize_client(ci, mon_idx, r.x, r.y, r.w, r.h)?;
+            let focused = self.monitors[mon_idx].client == Some(ci);
+            border::paint(
+                self.conn,
+                &self.config.theme,
+                &self.monitors[mon_idx].clients[ci],
+                focused,
+            )?;
         }
-        Ok(())
+        self.recompute_layout(mon_idx)
     }
 
-    fn fullscreen(&mut self, mon_idx: usize, fullscreen: bool) -> Result<(), ReplyOrIdError> {
-        let rect = self.monitors[mon_idx].rect;
-        let idx = self.monitors[mon_idx].client.unwrap();
-        if let Some(ci) = self.monitors[mon_idx].client {
-            let c = &mut self.monitors[mon_idx].clients[ci];
-            if fullscreen && !c.is_fullscreen {
-                self.conn.change_property32(
-                    PropMode::REPLACE,
-                    c.window,
-                    self.atoms._NET_WM_STATE,
-                    self.atoms.ATOM,
-                    &[self.atoms._NET_WM_STATE_FULLSCREEN],
-                )?;
-                c.is_fullscreen = true;
-                c.old_state = c.is_floating;
-                c.old_bw = c.bw;
-                c.bw = 0;
-                c.is_floating = true;
-                let bh = bar_height();
-                self.resize_client(
-                    idx,
-                    mon_idx,
-                    rect.x,
-                    rect.y - bh as i16,
-                    rect.w,
-                    rect.h + bh,
-                )?;
-            } else if !fullscreen && c.is_fullscreen {
-                self.conn.change_property32(
-                    PropMode::REPLACE,
-                    c.window,
-                    self.atoms._NET_WM_STATE,
-                    self.atoms.ATOM,
-                    &[0],
-                )?;
-                c.is_fullscreen = false;
-                c.is_floating = c.old_state;
-                c.bw = c.old_bw;
-                let r = c.old_rect;
-                self.resize_client(idx, mon_idx, r.x, r.y, r.w, r.h)?;
-                self.recompute_layout(self.selmon)?;
-            }
+    fn set_fullscreen_monitors(
+        &mut self,
+        mon_idx: usize,
+        ci: usize,
+        monitors: [usize; 4],
+    ) -> Result<(), ReplyOrIdError> {
+        let c = &mut self.monitors[mon_idx].clients[ci];
+        c.fullscreen_monitors = Some(monitors);
+        let (win, is_fullscreen) = (c.window, c.is_fullscreen);
+        self.conn.change_property32(
+            PropMode::REPLACE,
+            win,
+            self.atoms._NET_WM_FULLSCREEN_MONITORS,
+            AtomEnum::CARDINAL,
+            &monitors.map(|i| i as u32),
+        )?;
+        if is_fullscreen {
+            let rect = self.fullscreen_area(mon_idx, ci);
+            self.resize_client(ci, mon_idx, rect.x, rect.y, rect.w, rect.h)?;
         }
-
         Ok(())
     }
 
@@ -795,7 +2511,7 @@ impl<'a, C: Connection> WinMan<'a, C> {
             let pos = WPos::new(c.rect.x + (c.rect.w as i16 / 2), c.rect.y);
 
             if let Some(dir) = m.find_adjacent_monitor(pos) {
-                self.move_client_to_monitor(dir).unwrap();
+                self.move_client_to_monitor(dir)?;
             }
             self.recompute_layout(self.selmon)?;
             self.warp_pointer_to_focused_client()?;
@@ -805,19 +2521,70 @@ impl<'a, C: Connection> WinMan<'a, C> {
 
     fn adjust_main_width(&mut self, dir: WDirection) -> Result<(), ReplyOrIdError> {
         let m = &mut self.monitors[self.selmon];
-        match dir {
-            WDirection::Prev if m.width_factor - WIDTH_ADJUSTMENT_FACTOR >= 0.05 => {
-                m.width_factor -= WIDTH_ADJUSTMENT_FACTOR;
-            }
-            WDirection::Next if m.width_factor <= 0.95 => {
-                m.width_factor += WIDTH_ADJUSTMENT_FACTOR;
-            }
-            _ => {}
+        // there is no main client in the column layout, so the focused column is resized
+        if m.layout == WLayout::Column {
+            m.adjust_column_width(dir);
+            return self.recompute_layout(self.selmon);
         }
+        let (min, max) = WIDTH_FACTOR_RANGE;
+        let width_factor = match dir {
+            WDirection::Prev => m.width_factor - m.width_step,
+            WDirection::Next => m.width_factor + m.width_step,
+        };
+        m.width_factor = width_factor.clamp(min, max);
         self.recompute_layout(self.selmon)?;
         Ok(())
     }
 
+    fn adjust_client_factor(&mut self, dir: WDirection) -> Result<(), ReplyOrIdError> {
+        let m = &mut self.monitors[self.selmon];
+        let Some(ci) = m.client.filter(|i| !m.clients[*i].is_floating) else {
+            return Ok(());
+        };
+        let c = &mut m.clients[ci];
+        let size_factor = match dir {
+            WDirection::Prev => c.size_factor - CLIENT_FACTOR_STEP,
+            WDirection::Next => c.size_factor + CLIENT_FACTOR_STEP,
+        };
+        // the same bounds as dwm's cfacts
+        c.size_factor = size_factor.clamp(0.25, 4.0);
+        self.recompute_layout(self.selmon)
+    }
+
+    fn adjust_main_count(&mut self, dir: WDirection) -> Result<(), ReplyOrIdError> {
+        let m = &mut self.monitors[self.selmon];
+        // growing past the clients there are would have no visible effect
+        let tiled = m
+            .clients_in_tag(m.tag)
+            .into_iter()
+            .filter(|i| !m.clients[*i].is_floating)
+            .count();
+        let main_count = match dir {
+            WDirection::Prev => m.main_count.saturating_sub(1),
+            WDirection::Next => (m.main_count + 1).min(tiled.max(m.main_count).max(1)),
+        };
+        if main_count != m.main_count {
+            m.main_count = main_count;
+            self.recompute_layout(self.selmon)?;
+        }
+        Ok(())
+    }
+
+    fn adjust_gaps(&mut self, dir: WDirection) -> Result<(), ReplyOrIdError> {
+        let m = &mut self.monitors[self.selmon];
+        let mut gaps = m.gaps;
+        for gap in [&mut gaps.inner, &mut gaps.outer] {
+            *gap = match dir {
+                WDirection::Prev => gap.saturating_sub(GAP_STEP),
+                WDirection::Next => gap.saturating_add(GAP_STEP),
+            };
+        }
+        // they stop growing once the clients on the tag would have no room left
+        let clients = m.tiled_clients().len();
+        m.gaps = layout::fit_gaps(gaps, m.rect, clients, layouts::border_width(clients));
+        self.recompute_layout(self.selmon)
+    }
+
     fn move_client_to_monitor(&mut self, dir: WDirection) -> Result<(), ReplyOrIdError> {
         if self.monitors[self.selmon].client.is_none() {
             return Ok(());
@@ -843,7 +2610,9 @@ impl<'a, C: Connection> WinMan<'a, C> {
         c.monitor = idx;
         c.tag = dest_mon.tag;
 
-        dest_mon.push_client(c);
+        let (window, tag) = (c.window, c.tag);
+        dest_mon.push_client(c, true);
+        self.set_client_desktop(window, tag)?;
 
         self.recompute_layout(idx)?;
         self.recompute_layout(self.selmon)?;
@@ -854,15 +2623,52 @@ impl<'a, C: Connection> WinMan<'a, C> {
     }
 
     fn move_client_to_tag(&mut self, new_tag: usize) -> Result<(), ReplyOrIdError> {
-        if self.monitors[self.selmon].client.is_none() || self.monitors[self.selmon].tag == new_tag
-        {
+        match self.monitors[self.selmon].client {
+            Some(ci) => self.client_to_tag(self.selmon, ci, new_tag),
+            None => Ok(()),
+        }
+    }
+
+    fn client_to_tag(
+        &mut self,
+        mon_idx: usize,
+        ci: usize,
+        tag: usize,
+    ) -> Result<(), ReplyOrIdError> {
+        let m = &self.monitors[mon_idx];
+        let WClientState {
+            window,
+            rect,
+            is_floating,
+            ..
+        } = m.clients[ci];
+        if m.clients[ci].tag == tag {
             return Ok(());
         }
+        let shown = tag == m.tag;
 
         self.unfocus(self.selmon)?;
-        self.monitors[self.selmon].client_to_tag(&self.conn, new_tag)?;
-        self.focus()?;
-        self.recompute_layout(self.selmon)?;
+        self.batched(|wwm| {
+            let m = &mut wwm.monitors[mon_idx];
+            m.client_to_tag(wwm.batch.get_or_insert_with(Default::default), ci, tag);
+            wwm.focus()?;
+            wwm.recompute_layout(mon_idx)
+        })?;
+        // floating clients aren't part of the layout, so they are brought back by hand
+        if shown && is_floating {
+            self.resize_client(ci, mon_idx, rect.x, rect.y, rect.w, rect.h)?;
+        }
+        self.set_client_desktop(window, tag)
+    }
+
+    fn set_client_desktop(&self, window: Window, tag: usize) -> Result<(), ReplyOrIdError> {
+        self.conn.change_property32(
+            PropMode::REPLACE,
+            window,
+            self.atoms._NET_WM_DESKTOP,
+            AtomEnum::CARDINAL,
+            &[tag as u32],
+        )?;
         Ok(())
     }
 
@@ -900,6 +2706,20 @@ impl<'a, C: Connection> WinMan<'a, C> {
     }
 
     fn handle_motion_notify(&mut self, evt: MotionNotifyEvent) -> Result<(), ReplyOrIdError> {
+        let bar = self
+            .monitors
+            .iter()
+            .position(|m| m.bar.window() == Some(evt.event));
+        if let Some(mon_idx) = bar {
+            match self.monitors[mon_idx]
+                .bar
+                .tag_at_pos(evt.event_x, evt.event_y)
+            {
+                Some(tag) => self.show_tag_preview(mon_idx, tag)?,
+                None => self.hide_tag_preview()?,
+            }
+        }
+
         let m = &self.monitors[self.selmon];
         if m.bar.has_pointer(evt.root_x, evt.root_y) {
             return Ok(());
@@ -907,37 +2727,46 @@ impl<'a, C: Connection> WinMan<'a, C> {
 
         let mon_has_pointer = m.has_pos(WPos::from(&evt));
 
+        if let Some((_, end)) = self.gesture.as_mut() {
+            *end = WPos::new(evt.root_x, evt.root_y);
+            return Ok(());
+        }
+
         // skip monitor focus change if a window is being manipulated
         if !mon_has_pointer && self.drag_window.is_none() && self.resize_window.is_none() {
             self.focus_at_pointer(&evt)?;
         }
 
-        if let Some(drag_info) = self.drag_window {
-            self.mouse_move(drag_info, evt)?;
+        let pos = WPos::new(evt.root_x, evt.root_y);
+
+        if let Some((oc_pos, op_pos, last_move)) = self.drag_window {
+            if evt.time.wrapping_sub(last_move) > MOTION_INTERVAL {
+                self.mouse_move(oc_pos, op_pos, pos)?;
+                self.drag_window = Some((oc_pos, op_pos, evt.time));
+            }
         }
 
-        if let Some(last_time) = self.resize_window {
-            self.mouse_resize(last_time, evt)?;
+        if let Some(last_resize) = self.resize_window {
+            if evt.time.wrapping_sub(last_resize) > MOTION_INTERVAL {
+                self.mouse_resize(pos)?;
+                self.resize_window = Some(evt.time);
+            }
         }
 
         Ok(())
     }
 
-    fn mouse_resize(
-        &mut self,
-        last_resize: u32,
-        ev: MotionNotifyEvent,
-    ) -> Result<(), ReplyOrIdError> {
+    fn mouse_resize(&mut self, pos: WPos) -> Result<(), ReplyOrIdError> {
         let m = &self.monitors[self.selmon];
         if let Some(idx) = m.client {
-            let c = m.clients[idx];
-            if c.is_fullscreen || ev.time - last_resize <= (1000 / 60) {
+            let c = &m.clients[idx];
+            if c.is_fullscreen {
                 return Ok(());
             }
 
             if c.is_floating {
-                let nw = 1.max(ev.root_x - c.rect.x - (2 * BORDER_WIDTH as i16) + 1) as u16;
-                let nh = 1.max(ev.root_y - c.rect.y - (2 * BORDER_WIDTH as i16) + 1) as u16;
+                let nw = 1.max(pos.x - c.rect.x - (2 * BORDER_WIDTH as i16) + 1) as u16;
+                let nh = 1.max(pos.y - c.rect.y - (2 * BORDER_WIDTH as i16) + 1) as u16;
 
                 // copy before move
                 let x = c.rect.x;
@@ -982,15 +2811,24 @@ impl<'a, C: Connection> WinMan<'a, C> {
             let c = &mut m.clients[c_idx];
             c.rect = WRect::new(x, y, w, h);
 
-            self.conn.configure_window(
-                c.window,
-                &ConfigureWindowAux::new()
-                    .x(x as i32)
-                    .y(y as i32)
-                    .width(w as u32)
-                    .height(h as u32)
-                    .border_width(c.bw as u32),
-            )?;
+            let aux = ConfigureWindowAux::new()
+                .x(x as i32)
+                .y(y as i32)
+                .width(w as u32)
+                .height(h as u32)
+                .border_width(c.bw as u32);
+            match self.batch.as_mut() {
+                Some(batch) => batch.configure(c.window, aux),
+                None => {
+                    self.conn.configure_window(c.window, &aux)?;
+                }
+            }
+
+            // the border pixmap has to match the new size
+            if theme::window::BORDER_ACCENT.is_some() {
+                let focused = mon_idx == self.selmon && m.client == Some(c_idx);
+                border::paint(self.conn, &self.config.theme, &m.clients[c_idx], focused)?;
+            }
         }
 
         let WClientState {
@@ -1000,19 +2838,16 @@ impl<'a, C: Connection> WinMan<'a, C> {
         Ok(())
     }
 
-    fn mouse_move(
-        &mut self,
-        (oc_pos, op_pos, last_move): (WPos, WPos, u32),
-        ev: MotionNotifyEvent,
-    ) -> Result<(), ReplyOrIdError> {
+    // moves the client by how far the pointer moved from where the drag started
+    fn mouse_move(&mut self, oc_pos: WPos, op_pos: WPos, pos: WPos) -> Result<(), ReplyOrIdError> {
         if let Some(ci) = self.monitors[self.selmon].client {
             let c = &mut self.monitors[self.selmon].clients[ci];
-            if c.is_fullscreen || ev.time - last_move <= (1000 / 60) {
+            if c.is_fullscreen {
                 return Ok(());
             }
 
-            let pdx = ev.root_x - op_pos.x;
-            let pdy = ev.root_y - op_pos.y;
+            let pdx = pos.x - op_pos.x;
+            let pdy = pos.y - op_pos.y;
             let nx = oc_pos.x + pdx;
             let ny = oc_pos.y + pdy;
 
@@ -1028,14 +2863,57 @@ impl<'a, C: Connection> WinMan<'a, C> {
     }
 
     fn handle_property_notify(&mut self, evt: PropertyNotifyEvent) -> Result<(), ReplyOrIdError> {
+        if evt.window == self.screen.root && evt.atom == u32::from(AtomEnum::WM_NAME) {
+            if self.config.status_source == WStatusSource::RootName {
+                self.refresh_status()?;
+            }
+            return Ok(());
+        }
+        if evt.atom == self.atoms._NET_WM_STRUT || evt.atom == self.atoms._NET_WM_STRUT_PARTIAL {
+            return self.update_strut(evt.window);
+        }
+        let Some((mon_idx, ci)) = self.win_to_client(evt.window) else {
+            return Ok(());
+        };
+
+        if evt.atom == self.atoms._MOTIF_WM_HINTS {
+            return self.update_decorations(mon_idx, ci);
+        }
+        if evt.atom == self.atoms.WM_HINTS {
+            let hints = self.get_wm_hints(evt.window)?;
+            self.monitors[mon_idx].clients[ci].accepts_input = accepts_input(hints.as_ref());
+            self.set_urgent(mon_idx, ci, hints.is_some_and(|h| h.urgent));
+            return Ok(());
+        }
         if evt.atom == self.atoms._NET_WM_NAME {
             let title = self.get_window_title(evt.window)?;
-            self.monitors[self.selmon].bar.update_title(title);
+            self.emit(event::title(evt.window, &title));
+            self.monitors[mon_idx].clients[ci].title = title;
+        } else if evt.atom == self.atoms.WM_CLASS {
+            self.monitors[mon_idx].clients[ci].class = self.get_window_class(evt.window)?;
+        } else if evt.atom == self.atoms._NET_WM_ICON {
+            let size = self.monitors[mon_idx].bar.icon_size();
+            self.monitors[mon_idx].clients[ci].icon = self.get_window_icon(evt.window, size)?;
+        } else {
+            return Ok(());
         }
+
+        // the title of a window that isn't focused can change at any time
+        let m = &mut self.monitors[mon_idx];
+        if m.client == Some(ci) {
+            m.bar.update_title(m.clients[ci].bar_text());
+            let icon = m.clients[ci].icon.clone();
+            m.bar.set_icon(self.conn, icon.as_ref())?;
+        }
+        if let Some(t) = self.title_bars.iter().find(|t| t.window == evt.window) {
+            self.draw_title_bar(t);
+        }
+        self.draw_tab_strip(mon_idx);
         Ok(())
     }
 
     fn handle_unmap_notify(&mut self, evt: UnmapNotifyEvent) -> Result<(), ReplyOrIdError> {
+        self.remove_strut(evt.window)?;
         if self.win_to_client(evt.window).is_some() {
             self.unmanage(evt.window, false)?;
         }
@@ -1047,12 +2925,21 @@ impl<'a, C: Connection> WinMan<'a, C> {
         win: Window,
         geom: &GetGeometryReply,
     ) -> Result<(), ReplyOrIdError> {
-        let is_floating = self.window_property_exists(
-            win,
-            self.atoms._NET_WM_WINDOW_TYPE_DIALOG,
-            self.atoms._NET_WM_WINDOW_TYPE,
-            self.atoms.ATOM,
-        )?;
+        let types = self.window_types(win)?;
+        if types.contains(&self.atoms._NET_WM_WINDOW_TYPE_DOCK) {
+            return self.manage_dock(win);
+        }
+        // popups that come and go on their own are shown but never managed or focused
+        if types.contains(&self.atoms._NET_WM_WINDOW_TYPE_NOTIFICATION)
+            || types.contains(&self.atoms._NET_WM_WINDOW_TYPE_TOOLTIP)
+        {
+            self.conn.map_window(win)?;
+            return Ok(());
+        }
+
+        let is_centered = types.contains(&self.atoms._NET_WM_WINDOW_TYPE_SPLASH)
+            || types.contains(&self.atoms._NET_WM_WINDOW_TYPE_UTILITY);
+        let is_floating = is_centered || types.contains(&self.atoms._NET_WM_WINDOW_TYPE_DIALOG);
 
         let is_fullscreen = self.window_property_exists(
             win,
@@ -1097,6 +2984,10 @@ impl<'a, C: Connection> WinMan<'a, C> {
 
             rect.x = rect.x.max(mx);
             rect.y = rect.y.max(my);
+            if is_centered {
+                rect.x = mx + (mw.saturating_sub(rect.w) / 2) as i16;
+                rect.y = my + (mh.saturating_sub(rect.h) / 2) as i16;
+            }
             rect
         };
 
@@ -1109,116 +3000,652 @@ impl<'a, C: Connection> WinMan<'a, C> {
             mtag,
             self.selmon,
         );
+        c.title = self.get_window_title(win)?;
+        c.class = self.get_window_class(win)?;
+        c.no_border = self.has_no_decorations(win)?;
+        c.bw = c.decorated_bw(c.bw);
+        let icon_size = self.monitors[c.monitor].bar.icon_size();
+        c.icon = self.get_window_icon(win, icon_size)?;
+        c.depth = geom.depth;
+        let hints = self.get_wm_hints(win)?;
+        c.accepts_input = accepts_input(hints.as_ref());
+        c.takes_focus = self.window_property_exists(
+            win,
+            self.atoms.WM_TAKE_FOCUS,
+            self.atoms.WM_PROTOCOLS,
+            self.atoms.ATOM,
+        )?;
+        c.supports_ping = self.window_property_exists(
+            win,
+            self.atoms._NET_WM_PING,
+            self.atoms.WM_PROTOCOLS,
+            self.atoms.ATOM,
+        )?;
+        for (atom, layer) in [
+            (self.atoms._NET_WM_STATE_ABOVE, WStackLayer::Above),
+            (self.atoms._NET_WM_STATE_BELOW, WStackLayer::Below),
+        ] {
+            if self.window_property_exists(win, atom, self.atoms._NET_WM_STATE, self.atoms.ATOM)? {
+                c.stack_layer = layer;
+            }
+        }
+
+        if let Ok(hints) = Self::get_normal_hints(&self.conn, win) {
+            c.apply_size_hints(hints);
+        }
+
+        if !is_floating {
+            c.old_state = trans.is_some() || c.is_fixed;
+            c.is_floating = c.old_state;
+        }
+
+        if let Some(t) = trans {
+            if let Some((mon_idx, _)) = self.win_to_client(t) {
+                c.monitor = mon_idx;
+            }
+        }
+
+        // clients of a restored session go back to where they were
+        let saved = self
+            .saved_state
+            .as_mut()
+            .and_then(|s| s.take_client(&c.class, &c.title));
+        if let Some(saved) = saved {
+            if saved.monitor < self.monitors.len() && saved.tag < self.config.tag_count {
+                c.monitor = saved.monitor;
+                c.tag = saved.tag;
+            }
+            c.is_floating = saved.is_floating;
+        }
+        let hidden = c.tag != self.monitors[c.monitor].tag;
+
+        if crate::pip::matches(pip::RULES, &c.class, &c.title) {
+            let area = self.monitors[c.monitor].rect;
+            let width = (area.w as f32 * pip::WIDTH) as u16;
+            let size = crate::pip::scaled((c.rect.w, c.rect.h), width);
+            c.rect = crate::pip::place(area, pip::CORNER, size, pip::MARGIN);
+            c.pip = Some(pip::CORNER);
+            c.stack_layer = WStackLayer::Above;
+            c.is_floating = true;
+            c.bw = 0;
+        }
+        let is_pip = c.pip.is_some();
+        let rect = if is_pip { c.rect } else { rect };
+
+        let policy = self.config.focus_policy(&c.class);
+        let takes_focus = !hidden
+            && match policy {
+                WFocusPolicy::Always => true,
+                WFocusPolicy::VisibleTag => c.monitor == self.selmon,
+                WFocusPolicy::Never => false,
+            };
+        c.is_urgent = !takes_focus && (!hidden || hints.as_ref().is_some_and(|h| h.urgent));
+
+        if c.is_floating {
+            self.raise(c.monitor, win)?;
+        }
+
+        border::paint(self.conn, &self.config.theme, &c, false)?;
+        self.set_frame_extents(win, c.bw)?;
+        let change_aux = ChangeWindowAttributesAux::new().event_mask(
+            EventMask::ENTER_WINDOW
+                | EventMask::FOCUS_CHANGE
+                | EventMask::PROPERTY_CHANGE
+                | EventMask::SUBSTRUCTURE_REDIRECT
+                | EventMask::STRUCTURE_NOTIFY,
+        );
+
+        self.configure_client(win, rect, c.bw)?;
+        self.conn.change_window_attributes(win, &change_aux)?;
+        self.mouse.update_client_grab(self.conn, win, false)?;
+
+        if c.monitor == self.selmon && takes_focus {
+            self.unfocus(self.selmon)?;
+        }
+
+        let mon_idx = c.monitor;
+        self.set_client_desktop(win, c.tag)?;
+        self.monitors[mon_idx].push_client(c, takes_focus);
+        self.update_client_list()?;
+        // some clients reserve space like docks do, without being one
+        self.update_strut(win)?;
+
+        self.set_client_state(win, WindowState::Normal)?;
+
+        self.recompute_layout(mon_idx)?;
+        // whether or not it got the focus
+        if is_fullscreen {
+            if let Some(ci) = self.monitors[mon_idx].client_idx(win) {
+                self.fullscreen(mon_idx, ci, true)?;
+            }
+        }
+        if hidden {
+            self.conn
+                .configure_window(win, &ConfigureWindowAux::new().x(rect.w as i32 * -2))?;
+        }
+        self.conn.map_window(win)?;
+
+        if mon_idx == self.selmon && takes_focus && !is_pip {
+            self.conn
+                .warp_pointer(NONE, win, 0, 0, 0, 0, rect.w as i16 / 2, rect.h as i16 / 2)?;
+        }
+        self.emit(event::client("mapped", win));
+        self.focus()?;
+
+        self.conn.flush()?;
+        self.conn.sync()?;
+
+        Ok(())
+    }
+
+    fn win_to_client(&self, win: Window) -> Option<(usize, usize)> {
+        self.monitors
+            .iter()
+            .enumerate()
+            .find_map(|(mi, m)| Some((mi, m.client_idx(win)?)))
+    }
+
+    fn move_adjacent(&mut self, dir: WDirection) -> Result<(), ReplyOrIdError> {
+        let m = &mut self.monitors[self.selmon];
+        m.swap_clients(dir);
+        self.ignore_enter = true;
+        self.recompute_layout(self.selmon)?;
+        self.warp_pointer_to_focused_client()?;
+        Ok(())
+    }
+
+    fn zoom(&mut self) -> Result<(), ReplyOrIdError> {
+        if !self.monitors[self.selmon].zoom() {
+            return Ok(());
+        }
+        self.ignore_enter = true;
+        self.recompute_layout(self.selmon)?;
+        self.warp_pointer_to_focused_client()?;
+        Ok(())
+    }
+
+    fn recompute_layout(&mut self, mon_idx: usize) -> Result<(), ReplyOrIdError> {
+        let mon = &mut self.monitors[mon_idx];
+        // nothing is tiled in the floating layout
+        let client_indices = match mon.layout {
+            WLayout::Floating => vec![],
+            _ => mon.tiled_clients(),
+        };
+
+        let rects =
+            layout_clients(&mon.layout, mon.width_factor, mon, &client_indices).unwrap_or_default();
+        let bw = layouts::border_width(client_indices.len());
+        let mut changed_bw = vec![];
+        for i in &client_indices {
+            let c = &mut mon.clients[*i];
+            let client_bw = c.decorated_bw(bw);
+            if c.bw != client_bw {
+                c.bw = client_bw;
+                changed_bw.push((c.window, client_bw));
+            }
+        }
+
+        // title bars take the top of each client's space
+        let title_height = if mon.has_title_bars() {
+            mon.bar_height
+        } else {
+            0
+        };
+        // and the tab strip takes the top of the monitor in the tabbed layout
+        let tab_strip = (mon.layout == WLayout::Tabbed && !client_indices.is_empty())
+            .then(|| WRect::new(mon.rect.x, mon.rect.y, mon.rect.w, mon.bar_height));
+        let tab_height = tab_strip.map_or(0, |r| r.h);
+
+        let mut titles = vec![];
+        let rects: Vec<WRect> = rects
+            .into_iter()
+            .zip(&client_indices)
+            .map(|(mut rect, i)| {
+                rect.y += tab_height as i16;
+                rect.h = rect.h.saturating_sub(tab_height);
+                if title_height > 0 {
+                    let w = rect.w + bw * 2;
+                    let title = WRect::new(rect.x, rect.y, w, title_height);
+                    titles.push((mon.clients[*i].window, title));
+                    rect.y += title_height as i16;
+                    rect.h = rect.h.saturating_sub(title_height);
+                }
+                let c = &mon.clients[*i];
+                let mut rect = layouts::fit_size_hints(c, rect);
+                // the client takes up the space of the border it doesn't have
+                if c.no_border {
+                    rect.w += bw * 2;
+                    rect.h += bw * 2;
+                }
+                rect
+            })
+            .collect();
+        self.update_title_bars(mon_idx, titles)?;
+        self.update_tab_strip(mon_idx, tab_strip)?;
+        for (win, bw) in changed_bw {
+            self.set_frame_extents(win, bw)?;
+        }
+
+        self.batched(|wwm| {
+            for (i, rect) in client_indices.iter().zip(rects) {
+                wwm.resize(*i, mon_idx, rect.x, rect.y, rect.w, rect.h, false)?;
+            }
+            Ok(())
+        })?;
+        self.restack(mon_idx)?;
+        self.update_bar_visibility(mon_idx)
+    }
+
+    // the bar is unmapped while a fullscreen client on the shown tag covers it,
+    // instead of being drawn underneath
+    fn update_bar_visibility(&mut self, mon_idx: usize) -> Result<(), ReplyOrIdError> {
+        let m = &mut self.monitors[mon_idx];
+        let covered = m
+            .clients_in_tag(m.tag)
+            .into_iter()
+            .any(|i| m.clients[i].is_fullscreen);
+        m.bar.set_hidden(self.conn, covered)?;
+        self.pause_unseen_status();
+        Ok(())
+    }
+
+    // the modules aren't updated while the root name is the status or nobody would see
+    // them, with every bar covered
+    fn pause_unseen_status(&mut self) {
+        let unseen = self.config.status_source != WStatusSource::Modules
+            || self.monitors.iter().all(|m| m.bar.is_hidden());
+        self.status.set_paused(unseen);
+    }
+
+    // creates, moves and removes the title bars of the monitor to match `titles`,
+    // which holds the client window and title bar rect of every tiled client
+    fn update_title_bars(
+        &mut self,
+        mon_idx: usize,
+        titles: Vec<(Window, WRect)>,
+    ) -> Result<(), ReplyOrIdError> {
+        let Some(text_renderer) = self.text_renderer(mon_idx).cloned() else {
+            return Ok(());
+        };
+
+        let (keep, gone): (Vec<_>, Vec<_>) = std::mem::take(&mut self.title_bars)
+            .into_iter()
+            .partition(|t| t.mon_idx != mon_idx || titles.iter().any(|(w, _)| *w == t.window));
+        self.title_bars = keep;
+        for t in gone {
+            t.overlay.destroy(self.conn)?;
+        }
+
+        for (window, rect) in titles {
+            let existing = self
+                .title_bars
+                .iter_mut()
+                .find(|t| t.mon_idx == mon_idx && t.window == window);
+            match existing {
+                Some(t) => t.overlay.configure(self.conn, rect)?,
+                None => {
+                    let t = WTitleBar::new(
+                        self.conn,
+                        &text_renderer,
+                        &self.config.theme,
+                        rect,
+                        mon_idx,
+                        window,
+                    )?;
+                    self.title_bars.push(t);
+                }
+            }
+        }
+        self.draw_title_bars();
+        Ok(())
+    }
+
+    fn update_tab_strip(
+        &mut self,
+        mon_idx: usize,
+        rect: Option<WRect>,
+    ) -> Result<(), ReplyOrIdError> {
+        let Some(rect) = rect else {
+            if let Some(t) = self.monitors[mon_idx].tab_strip.take() {
+                t.overlay.destroy(self.conn)?;
+            }
+            return Ok(());
+        };
+
+        if let Some(t) = self.monitors[mon_idx].tab_strip.as_mut() {
+            t.overlay.configure(self.conn, rect)?;
+        } else if let Some(text_renderer) = self.text_renderer(mon_idx).cloned() {
+            let t = WTabStrip::new(self.conn, &text_renderer, &self.config.theme, rect)?;
+            self.monitors[mon_idx].tab_strip = Some(t);
+        }
+        self.draw_tab_strip(mon_idx);
+        Ok(())
+    }
+
+    fn draw_tab_strips(&self) {
+        for i in 0..self.monitors.len() {
+            self.draw_tab_strip(i);
+        }
+    }
+
+    fn draw_tab_strip(&self, mon_idx: usize) {
+        let m = &self.monitors[mon_idx];
+        let (Some(t), Some(text_renderer)) = (&m.tab_strip, self.text_renderer(mon_idx)) else {
+            return;
+        };
+        let tabs: Vec<(&str, bool)> = m
+            .tiled_clients()
+            .into_iter()
+            .map(|i| (m.clients[i].bar_title(), m.client == Some(i)))
+            .collect();
+        if let Err(e) = t.draw(text_renderer, &self.config.theme, &tabs) {
+            eprintln!("ERROR: failed to draw tab strip of monitor {mon_idx}: {e}");
+        }
+    }
 
-        if let Ok(hints) = Self::get_normal_hints(&self.conn, win) {
-            c.apply_size_hints(hints);
+    fn click_tab(&mut self, mon_idx: usize, x: i16) -> Result<(), ReplyOrIdError> {
+        let m = &self.monitors[mon_idx];
+        let tiled = m.tiled_clients();
+        let tab = m.tab_strip.as_ref().and_then(|t| t.tab_at(x, tiled.len()));
+        match tab {
+            Some(tab) => self.click_title_bar(m.clients[tiled[tab]].window, false),
+            None => Ok(()),
         }
+    }
 
-        if !is_floating {
-            c.old_state = trans.is_some() || c.is_fixed;
-            c.is_floating = c.old_state;
+    // in the tabbed layout only the focused client can be seen, below floating clients
+    fn raise_tab(&self, mon_idx: usize, ci: usize) -> Result<(), ReplyOrIdError> {
+        let m = &self.monitors[mon_idx];
+        if m.layout != WLayout::Tabbed || m.clients[ci].is_floating {
+            return Ok(());
+        }
+        let floating = m
+            .clients_in_tag(m.tag)
+            .into_iter()
+            .filter(|i| m.clients[*i].is_floating);
+        for i in std::iter::once(ci).chain(floating) {
+            self.conn.configure_window(
+                m.clients[i].window,
+                &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+            )?;
         }
+        self.restack(mon_idx)
+    }
 
-        if let Some(t) = trans {
-            if let Some((mon_idx, _)) = self.win_to_client(t) {
-                c.monitor = mon_idx;
-            }
+    fn draw_title_bars(&self) {
+        for t in &self.title_bars {
+            self.draw_title_bar(t);
         }
+    }
 
-        if c.is_floating {
-            self.conn
-                .configure_window(win, &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE))?;
+    fn draw_title_bar(&self, t: &WTitleBar) {
+        let (Some(text_renderer), Some((mon_idx, ci))) =
+            (self.text_renderer(t.mon_idx), self.win_to_client(t.window))
+        else {
+            return;
+        };
+        let m = &self.monitors[mon_idx];
+        let focused = mon_idx == self.selmon && m.client == Some(ci);
+        if let Err(e) = t.draw(
+            text_renderer,
+            &self.config.theme,
+            m.clients[ci].bar_title(),
+            focused,
+        ) {
+            eprintln!("ERROR: failed to draw title bar of {}: {e}", t.window);
         }
+    }
 
-        let change_aux = ChangeWindowAttributesAux::new()
-            .border_pixel(theme::window::BORDER_UNFOCUSED)
-            .event_mask(
-                EventMask::ENTER_WINDOW
-                    | EventMask::FOCUS_CHANGE
-                    | EventMask::PROPERTY_CHANGE
-                    | EventMask::SUBSTRUCTURE_REDIRECT
-                    | EventMask::STRUCTURE_NOTIFY,
-            );
+    fn show_tag_preview(&mut self, mon_idx: usize, tag: usize) -> Result<(), ReplyOrIdError> {
+        if self.tag_preview.as_ref().map(|p| (p.mon_idx, p.tag)) == Some((mon_idx, tag)) {
+            return Ok(());
+        }
+        self.hide_tag_preview()?;
 
-        self.configure_client(win, rect, c.bw)?;
-        self.conn.change_window_attributes(win, &change_aux)?;
+        // there is nothing to preview on a tag that is in view or empty
+        let m = &self.monitors[mon_idx];
+        if tag == m.tag || m.clients_in_tag(tag).is_empty() {
+            return Ok(());
+        }
+        let Some(text_renderer) = self.text_renderer(mon_idx).cloned() else {
+            return Ok(());
+        };
 
-        if c.monitor == self.selmon {
-            self.unfocus(self.selmon)?;
+        let scale = theme::bar::TAG_PREVIEW_SCALE;
+        let w = (m.rect.w as f32 * scale) as u16;
+        let h = (m.rect.h as f32 * scale) as u16;
+        // right below the tag, without going past the edge of the monitor
+        let bar = m.bar.rect();
+        let x = bar.x + m.bar.tag_rect(tag).x;
+        let x = x.min(m.rect.x + m.rect.w as i16 - w as i16);
+        let rect = WRect::new(x, bar.y + bar.h as i16, w, h);
+
+        let overlay = WOverlay::new(self.conn, &text_renderer, rect, self.config.theme.bg)?;
+        let preview = WTagPreview {
+            overlay,
+            mon_idx,
+            tag,
+        };
+        self.draw_tag_preview(&preview);
+        self.tag_preview = Some(preview);
+        Ok(())
+    }
+
+    fn hide_tag_preview(&mut self) -> Result<(), ReplyOrIdError> {
+        if let Some(preview) = self.tag_preview.take() {
+            preview.overlay.destroy(self.conn)?;
         }
+        Ok(())
+    }
 
-        self.monitors[c.monitor].push_client(c);
-        self.update_client_list()?;
+    fn toggle_tag_preview(&mut self, tag: usize) -> Result<(), ReplyOrIdError> {
+        let shown = self.tag_preview.as_ref().map(|p| (p.mon_idx, p.tag));
+        if shown == Some((self.selmon, tag)) {
+            self.hide_tag_preview()
+        } else {
+            self.show_tag_preview(self.selmon, tag)
+        }
+    }
 
-        self.set_client_state(win, WindowState::Normal)?;
+    // where the clients of the tag would be if it was selected, with floating clients
+    // last so that they end up on top
+    fn tag_layout(&self, mon_idx: usize, tag: usize) -> Vec<(usize, WRect)> {
+        let m = &self.monitors[mon_idx];
+        let (floating, tiled): (Vec<usize>, Vec<usize>) = m
+            .clients_in_tag(tag)
+            .into_iter()
+            .partition(|i| m.clients[*i].is_floating || m.layout == WLayout::Floating);
+        let tiled_rects = layout_clients(&m.layout, m.width_factor, m, &tiled);
+        let floating_rects = floating.iter().map(|i| m.clients[*i].rect);
 
-        self.recompute_layout(c.monitor)?;
-        self.conn.map_window(win)?;
+        tiled
+            .into_iter()
+            .chain(floating.iter().copied())
+            .zip(
+                tiled_rects
+                    .unwrap_or_default()
+                    .into_iter()
+                    .chain(floating_rects),
+            )
+            .collect()
+    }
 
-        if is_fullscreen {
-            self.fullscreen(c.monitor, true)?;
+    // the clients of the tag as they are drawn in a miniature of it
+    fn miniature_clients(&self, mon_idx: usize, tag: usize) -> Vec<(WRect, &str, bool)> {
+        let m = &self.monitors[mon_idx];
+        let selected = m.clients.selection_in_tag(None, tag);
+        self.tag_layout(mon_idx, tag)
+            .into_iter()
+            .map(|(i, rect)| {
+                let c = &m.clients[i];
+                let label = if c.class.is_empty() {
+                    &c.title
+                } else {
+                    &c.class
+                };
+                (rect, label.as_str(), selected == Some(i))
+            })
+            .collect()
+    }
+
+    fn draw_tag_preview(&self, preview: &WTagPreview) {
+        let Some(text_renderer) = self.text_renderer(preview.mon_idx) else {
+            return;
+        };
+        let area = self.monitors[preview.mon_idx].rect;
+        let clients = self.miniature_clients(preview.mon_idx, preview.tag);
+        if let Err(e) = preview.draw(text_renderer, &self.config.theme, area, &clients) {
+            eprintln!(
+                "ERROR: failed to draw the preview of tag {}: {e}",
+                preview.tag
+            );
         }
+    }
 
-        if c.monitor == self.selmon {
-            self.conn
-                .warp_pointer(NONE, win, 0, 0, 0, 0, rect.w as i16 / 2, rect.h as i16 / 2)?;
+    fn enter_overview(&mut self) -> Result<(), ReplyOrIdError> {
+        if self.overview.is_some() || !self.hints.is_empty() || self.resize_mode {
+            return Ok(());
         }
-        self.focus()?;
+        let Some(text_renderer) = self.text_renderer(self.selmon).cloned() else {
+            return Ok(());
+        };
+        self.hide_tag_preview()?;
 
-        self.conn.flush()?;
-        self.conn.sync()?;
+        let m = &self.monitors[self.selmon];
+        let overview = WOverview::new(
+            self.conn,
+            &text_renderer,
+            &self.config.theme,
+            m.rect,
+            self.selmon,
+            self.config.tag_count,
+            m.bar_height,
+        )?;
+        self.draw_overview(&overview);
+        self.overview = Some(overview);
 
+        // any key leaves the overview, so the whole keyboard is needed
+        if !self.grab_keyboard()? {
+            return self.exit_overview();
+        }
         Ok(())
     }
 
-    fn win_to_client(&self, win: Window) -> Option<(usize, usize)> {
-        for (mi, m) in self.monitors.iter().enumerate() {
-            for (ci, c) in m.clients.iter().enumerate() {
-                if c.window == win {
-                    return Some((mi, ci));
-                }
-            }
+    fn exit_overview(&mut self) -> Result<(), ReplyOrIdError> {
+        if let Some(overview) = self.overview.take() {
+            overview.overlay.destroy(self.conn)?;
+            self.conn.ungrab_keyboard(CURRENT_TIME)?;
         }
-        None
+        Ok(())
     }
 
-    fn move_adjacent(&mut self, dir: WDirection) -> Result<(), ReplyOrIdError> {
-        let m = &mut self.monitors[self.selmon];
-        m.swap_clients(dir);
-        self.ignore_enter = true;
-        self.recompute_layout(self.selmon)?;
-        self.warp_pointer_to_focused_client()?;
-        Ok(())
+    fn draw_overview(&self, overview: &WOverview) {
+        let Some(text_renderer) = self.text_renderer(overview.mon_idx) else {
+            return;
+        };
+        let m = &self.monitors[overview.mon_idx];
+        for tag in 0..overview.tag_count() {
+            let clients = self.miniature_clients(overview.mon_idx, tag);
+            let res = overview
+                .draw_label(
+                    text_renderer,
+                    &self.config.theme,
+                    tag,
+                    &self.config.tag_name(tag),
+                    tag == m.tag,
+                )
+                .and_then(|_| {
+                    tag_preview::draw_miniature(
+                        &overview.overlay,
+                        text_renderer,
+                        &self.config.theme,
+                        m.rect,
+                        overview.body(tag),
+                        &clients,
+                    )
+                });
+            if let Err(e) = res {
+                eprintln!("ERROR: failed to draw tag {} in the overview: {e}", tag + 1);
+            }
+        }
     }
 
-    fn recompute_layout(&mut self, mon_idx: usize) -> Result<(), ReplyOrIdError> {
-        let mon = &mut self.monitors[mon_idx];
-        let client_indices = mon.clients_in_tag(mon.tag);
-        let client_indices: Vec<_> = client_indices
+    // switches to the clicked tag and focuses the clicked client, if any
+    fn click_overview(&mut self, x: i16, y: i16) -> Result<(), ReplyOrIdError> {
+        let Some(overview) = &self.overview else {
+            return Ok(());
+        };
+        let mon_idx = overview.mon_idx;
+        let Some(tag) = overview.tag_at(x, y) else {
+            return Ok(());
+        };
+        let area = self.monitors[mon_idx].rect;
+        let body = overview.body(tag);
+        // floating clients are drawn last, on top of the others
+        let client = self
+            .tag_layout(mon_idx, tag)
             .into_iter()
-            .filter(|i| !mon.clients[*i].is_floating)
-            .collect();
+            .rev()
+            .find(|(_, rect)| tag_preview::miniature(*rect, area, body).has_pointer(x, y))
+            .map(|(i, _)| i);
+
+        self.exit_overview()?;
+        match client {
+            Some(client_idx) => self.activate_client(mon_idx, client_idx),
+            None => {
+                self.set_selmon(mon_idx)?;
+                self.select_tag(tag, true)
+            }
+        }
+    }
+
+    // the number keys switch to their tag, any other key just leaves the overview
+    fn select_overview_tag(&mut self, sym: u32) -> Result<(), ReplyOrIdError> {
+        let tag_count = self.overview.as_ref().map_or(0, |o| o.tag_count());
+        self.exit_overview()?;
+
+        // the keysyms of digits match their character codes
+        let tag = char::from_u32(sym)
+            .and_then(|c| c.to_digit(10))
+            .and_then(|d| (d as usize).checked_sub(1))
+            .filter(|tag| *tag < tag_count);
+        match tag {
+            Some(tag) => self.select_tag(tag, true),
+            None => Ok(()),
+        }
+    }
 
-        let rects = layout_clients(&mon.layout, mon.width_factor, &mon, client_indices.len());
+    fn toggle_title_bars(&mut self) -> Result<(), ReplyOrIdError> {
+        self.monitors[self.selmon].toggle_title_bars();
+        self.recompute_layout(self.selmon)
+    }
 
-        if rects.is_none() {
-            return Ok(());
+    // runs `f` with window configurations collected into a single batch that is sent
+    // once it returns. nested calls add to the batch of the outermost one.
+    fn batched(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<(), ReplyOrIdError>,
+    ) -> Result<(), ReplyOrIdError> {
+        if self.batch.is_some() {
+            return f(self);
         }
 
-        for (i, rect) in client_indices.iter().zip(rects.unwrap()) {
-            self.resize(*i, mon_idx, rect.x, rect.y, rect.w, rect.h, false)?;
+        self.batch = Some(WConfigureBatch::default());
+        let res = f(self);
+        if let Some(batch) = self.batch.take() {
+            batch.send(self.conn)?;
         }
-        self.conn.sync()?;
-        Ok(())
+        res
     }
 
-    fn run_auto_start_commands() -> Result<(), std::io::Error> {
-        for cmd in AUTO_START_COMMANDS {
-            if let Some((bin, args)) = wwm_core::util::cmd::format(cmd) {
-                Command::new(bin).args(args).spawn()?;
-            }
+    fn schedule_restart(&mut self, at: Option<Instant>) {
+        match at {
+            Some(at) => self.timers.schedule(
+                WTimerEvent::RestartChildren,
+                at.saturating_duration_since(Instant::now()),
+            ),
+            None => self.timers.cancel(WTimerEvent::RestartChildren),
         }
-        Ok(())
     }
 
     fn scan_windows(&mut self) -> Result<(), ReplyOrIdError> {
@@ -1242,25 +3669,36 @@ impl<'a, C: Connection> WinMan<'a, C> {
         if self.monitors[self.selmon].tag == new_tag {
             return Ok(());
         }
+        self.hide_tag_preview()?;
 
-        {
-            self.unfocus(self.selmon)?;
-            let m = &mut self.monitors[self.selmon];
-            m.hide_clients(&self.conn, m.tag)?;
-
+        self.unfocus(self.selmon)?;
+        self.batched(|wwm| {
+            let m = &mut wwm.monitors[wwm.selmon];
+            let old_tag = m.tag;
             m.set_tag(new_tag).unwrap();
+            m.move_pip_clients(old_tag, new_tag);
+            m.hide_clients(wwm.batch.get_or_insert_with(Default::default), old_tag);
             m.bar.update_tags(new_tag);
-        }
+            m.bar.update_layout_symbol(m.layout);
 
-        let title = if let Some(ci) = self.monitors[self.selmon].client {
-            self.get_window_title(self.monitors[self.selmon].clients[ci].window)?
-        } else {
-            String::new()
-        };
-        self.monitors[self.selmon].bar.update_title(title);
+            wwm.recompute_layout(wwm.selmon)
+        })?;
+        // picture-in-picture clients came along to the new tag
+        let m = &self.monitors[self.selmon];
+        for i in m.clients_in_tag(new_tag) {
+            if m.clients[i].pip.is_some() {
+                self.set_client_desktop(m.clients[i].window, new_tag)?;
+            }
+        }
 
-        self.recompute_layout(self.selmon)?;
         self.focus()?;
+        let (tag, layout) = (
+            self.config.tag_name(new_tag),
+            self.monitors[self.selmon].layout,
+        );
+        self.emit(event::tag(self.selmon, new_tag, &tag));
+        self.emit(event::layout(self.selmon, layout));
+        self.show_osd(format!("tag {tag}"))?;
 
         if warp_pointer {
             self.warp_pointer_to_focused_client()?;
@@ -1269,6 +3707,88 @@ impl<'a, C: Connection> WinMan<'a, C> {
         Ok(())
     }
 
+    // the picture-in-picture client shown on the focused monitor
+    fn pip_client(&self) -> Option<(usize, WCorner)> {
+        let m = &self.monitors[self.selmon];
+        m.clients_in_tag(m.tag)
+            .into_iter()
+            .find_map(|i| Some((i, m.clients[i].pip?)))
+    }
+
+    fn resize_pip(&mut self, dir: WDirection) -> Result<(), ReplyOrIdError> {
+        let Some((ci, corner)) = self.pip_client() else {
+            return Ok(());
+        };
+        let m = &self.monitors[self.selmon];
+        let c = &m.clients[ci];
+        let step = (m.rect.w as f32 * pip::STEP) as u16;
+        let width = match dir {
+            WDirection::Prev => c.rect.w.saturating_sub(step).max(step),
+            WDirection::Next => (c.rect.w + step).min(m.rect.w / 2),
+        };
+        let size = crate::pip::scaled((c.rect.w, c.rect.h), width);
+        self.place_pip(ci, corner, size)
+    }
+
+    fn move_pip(&mut self, dir: WDirection) -> Result<(), ReplyOrIdError> {
+        let Some((ci, corner)) = self.pip_client() else {
+            return Ok(());
+        };
+        let corner = corner.adjacent(dir);
+        let c = &mut self.monitors[self.selmon].clients[ci];
+        c.pip = Some(corner);
+        let size = (c.rect.w, c.rect.h);
+        self.place_pip(ci, corner, size)
+    }
+
+    fn place_pip(
+        &mut self,
+        ci: usize,
+        corner: WCorner,
+        size: (u16, u16),
+    ) -> Result<(), ReplyOrIdError> {
+        let area = self.monitors[self.selmon].rect;
+        let rect = crate::pip::place(area, corner, size, pip::MARGIN);
+        self.resize(ci, self.selmon, rect.x, rect.y, rect.w, rect.h, false)
+    }
+
+    // keeps the clients of the shown tag that are kept above or below the others,
+    // like picture-in-picture videos, in their place after anything was raised
+    // brings the window to the front and then puts the clients kept above or below the
+    // others back in their place
+    fn raise(&self, mon_idx: usize, win: Window) -> Result<(), ReplyOrIdError> {
+        self.conn
+            .configure_window(win, &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE))?;
+        self.restack(mon_idx)
+    }
+
+    fn restack(&self, mon_idx: usize) -> Result<(), ReplyOrIdError> {
+        let m = &self.monitors[mon_idx];
+        for i in m.clients_in_tag(m.tag) {
+            let stack_mode = match m.clients[i].stack_layer {
+                WStackLayer::Above => StackMode::ABOVE,
+                WStackLayer::Below => StackMode::BELOW,
+                WStackLayer::Normal => continue,
+            };
+            self.conn.configure_window(
+                m.clients[i].window,
+                &ConfigureWindowAux::new().stack_mode(stack_mode),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn move_tag(&mut self, mon_idx: usize, from: usize, to: usize) -> Result<(), ReplyOrIdError> {
+        self.hide_tag_preview()?;
+        self.monitors[mon_idx].move_tag(from, to);
+        self.update_current_desktop()?;
+        let m = &self.monitors[mon_idx];
+        for c in m.clients.iter() {
+            self.set_client_desktop(c.window, c.tag)?;
+        }
+        self.show_osd(format!("tag {} -> {}", from + 1, to + 1))
+    }
+
     fn send_event(&self, window: Window, proto: u32) -> Result<(), ReplyError> {
         let event = ClientMessageEvent::new(
             32,
@@ -1294,35 +3814,201 @@ impl<'a, C: Connection> WinMan<'a, C> {
         Ok(())
     }
 
-    fn spawn_program(&self, cmd: &'static [&'static str]) {
-        if let Some((bin, args)) = wwm_core::util::cmd::format(cmd) {
-            Command::new(bin).args(args).spawn().unwrap();
+    fn check_idle(&mut self) -> Result<(), ReplyOrIdError> {
+        let inhibited = idle::INHIBIT_FULLSCREEN
+            && self.monitors.iter().any(|m| {
+                m.clients_in_tag(m.tag)
+                    .into_iter()
+                    .any(|i| m.clients[i].is_fullscreen)
+            });
+
+        let idle_ms = if inhibited {
+            // also keeps the server from blanking the screen on its own
+            self.conn.force_screen_saver(ScreenSaver::RESET)?;
+            0
+        } else {
+            let info = screensaver::query_info(self.conn, self.screen.root)?.reply()?;
+            info.ms_since_user_input as u64
+        };
+
+        let (due, next) = self.idle.update(idle_ms, &self.config.idle_actions);
+        for action in due {
+            self.run_idle_action(action);
         }
+        if let Some(next) = next {
+            self.timers.schedule(WTimerEvent::IdleCheck, next);
+        }
+        Ok(())
     }
 
-    fn try_exit(&mut self) {
-        if self.should_exit.load(Ordering::Relaxed) {
-            exit(0)
+    fn run_idle_action(&mut self, action: WIdleAction) {
+        match action {
+            WIdleAction::Run(cmd) => self.spawn_program(&cmd),
+            WIdleAction::DpmsOff => {
+                if self.dpms_was_enabled.is_none() {
+                    let info = dpms::info(self.conn).map_err(ReplyError::from);
+                    match info.and_then(|c| c.reply()) {
+                        Ok(info) => self.dpms_was_enabled = Some(info.state),
+                        Err(e) => eprintln!("ERROR: Failed to query dpms: {e}"),
+                    }
+                }
+                // the server turns the monitors back on by itself on the next input
+                let res = dpms::enable(self.conn)
+                    .and_then(|_| dpms::force_level(self.conn, dpms::DPMSMode::OFF));
+                if let Err(e) = res {
+                    eprintln!("ERROR: Failed to turn off the monitors: {e}");
+                }
+            }
         }
+    }
 
-        let should_exit = Arc::clone(&self.should_exit);
+    fn spawn_program(&mut self, cmd: &[impl AsRef<str>]) {
+        if let Err(e) = self.children.spawn(cmd, WRestart::Never) {
+            eprintln!("ERROR: Failed to spawn {}: {e}", cmd[0].as_ref());
+        }
+    }
 
-        thread::spawn(move || {
-            should_exit.store(true, Ordering::Relaxed);
-            thread::sleep(Duration::from_secs(2));
-            should_exit.store(false, Ordering::Relaxed);
-        });
+    fn run_media_command(&mut self, cmd: WMediaCommand) -> Result<(), ReplyOrIdError> {
+        let volume = Some(("vol", osd::VOLUME_CMD));
+        let brightness = Some(("bright", osd::BRIGHTNESS_CMD));
+        // the value the command changes, which is shown by the bar and the osd
+        let (command, value) = match cmd {
+            WMediaCommand::VolumeUp => (media::VOLUME_UP_CMD, volume),
+            WMediaCommand::VolumeDown => (media::VOLUME_DOWN_CMD, volume),
+            WMediaCommand::ToggleMute => (media::TOGGLE_MUTE_CMD, volume),
+            WMediaCommand::BrightnessUp => (media::BRIGHTNESS_UP_CMD, brightness),
+            WMediaCommand::BrightnessDown => (media::BRIGHTNESS_DOWN_CMD, brightness),
+            WMediaCommand::PlayPause => (media::PLAY_PAUSE_CMD, None),
+            WMediaCommand::Next => (media::NEXT_CMD, None),
+            WMediaCommand::Prev => (media::PREV_CMD, None),
+        };
+
+        let Some((label, query)) = value else {
+            self.spawn_program(command);
+            return Ok(());
+        };
+
+        // the new value is shown once the command exits
+        match self.children.spawn(command, WRestart::Never) {
+            Ok(Some(pid)) => self.media_change = Some(WMediaChange { pid, label, query }),
+            Ok(None) => {}
+            Err(e) => eprintln!("ERROR: Failed to spawn {}: {e}", command[0]),
+        }
+        Ok(())
+    }
+
+    // shows the value left by a media command in the bar and the osd once it exited
+    fn handle_media_exits(&mut self) -> Result<(), ReplyOrIdError> {
+        let children = &self.children;
+        if let Some(change) = self.media_change.take_if(|c| !children.running(c.pid)) {
+            self.status.refresh(WModuleKind::Vol);
+            if osd::ENABLED {
+                if let Some(query) = self.osd_query.take() {
+                    query.cancel();
+                }
+                self.osd_query = WOsdQuery::spawn(change.label, change.query);
+            }
+        }
+        if self.osd_query.as_mut().is_some_and(WOsdQuery::exited) {
+            if let Some(text) = self.osd_query.take().and_then(WOsdQuery::text) {
+                self.show_osd(text)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn show_osd(&mut self, text: String) -> Result<(), ReplyOrIdError> {
+        if !osd::ENABLED {
+            return Ok(());
+        }
+        let mon_idx = self.selmon;
+        let Some(text_renderer) = self.text_renderer(mon_idx).cloned() else {
+            return Ok(());
+        };
+        let area = self.monitors[mon_idx].rect;
+
+        match &mut self.osd {
+            Some(o) if o.mon_idx == mon_idx => {
+                let opacity = self.atoms._NET_WM_WINDOW_OPACITY;
+                o.update(
+                    self.conn,
+                    &text_renderer,
+                    &self.config.theme,
+                    area,
+                    text,
+                    opacity,
+                )?;
+            }
+            _ => {
+                self.hide_osd()?;
+                let o = WOsd::new(
+                    self.conn,
+                    &text_renderer,
+                    &self.config.theme,
+                    area,
+                    mon_idx,
+                    text,
+                )?;
+                self.osd = Some(o);
+            }
+        }
+        if let Some(o) = &self.osd {
+            self.draw_osd(o);
+        }
+        self.timers
+            .schedule(WTimerEvent::OsdFade, Duration::from_millis(osd::TIMEOUT));
+        Ok(())
+    }
+
+    fn fade_osd(&mut self) -> Result<(), ReplyOrIdError> {
+        let Some(o) = self.osd.as_mut() else {
+            return Ok(());
+        };
+        if o.fade(self.conn, self.atoms._NET_WM_WINDOW_OPACITY)? {
+            let step = osd::FADE / osd_overlay::FADE_STEPS as u64;
+            self.timers
+                .schedule(WTimerEvent::OsdFade, Duration::from_millis(step));
+        } else {
+            self.hide_osd()?;
+        }
+        Ok(())
+    }
+
+    fn hide_osd(&mut self) -> Result<(), ReplyOrIdError> {
+        self.timers.cancel(WTimerEvent::OsdFade);
+        if let Some(o) = self.osd.take() {
+            o.overlay.destroy(self.conn)?;
+        }
+        Ok(())
+    }
+
+    fn draw_osd(&self, osd: &WOsd) {
+        let Some(text_renderer) = self.text_renderer(osd.mon_idx) else {
+            return;
+        };
+        if let Err(e) = osd.draw(text_renderer, &self.config.theme) {
+            eprintln!("ERROR: failed to draw osd: {e}");
+        }
+    }
+
+    fn try_exit(&mut self) {
+        // the exit keybind has to be pressed twice within the timeout
+        if self.timers.is_scheduled(WTimerEvent::ExitTimeout) {
+            self.exiting = true;
+            return;
+        }
+        self.timers
+            .schedule(WTimerEvent::ExitTimeout, Duration::from_secs(2));
     }
 
-    fn unfocus(&mut self, mon_idx: usize) -> Result<(), ReplyError> {
+    fn unfocus(&mut self, mon_idx: usize) -> Result<(), ReplyOrIdError> {
         let m = &mut self.monitors[mon_idx];
         if let Some(ci) = m.client {
-            let unfocus_aux =
-                ChangeWindowAttributesAux::new().border_pixel(theme::window::BORDER_UNFOCUSED);
-            self.conn
-                .change_window_attributes(m.clients[ci].window, &unfocus_aux)?;
+            border::paint(self.conn, &self.config.theme, &m.clients[ci], false)?;
             self.conn
                 .delete_property(m.clients[ci].window, self.atoms._NET_ACTIVE_WINDOW)?;
+            self.mouse
+                .update_client_grab(self.conn, m.clients[ci].window, false)?;
         }
 
         Ok(())
@@ -1331,6 +4017,7 @@ impl<'a, C: Connection> WinMan<'a, C> {
     fn unmanage(&mut self, win: Window, destroyed: bool) -> Result<(), ReplyOrIdError> {
         if let Some((mon_idx, _)) = self.win_to_client(win) {
             self.detach(win, mon_idx);
+            self.emit(event::client("unmapped", win));
             if !destroyed {
                 self.conn.grab_server()?;
                 self.conn
@@ -1356,6 +4043,70 @@ impl<'a, C: Connection> WinMan<'a, C> {
         Ok(())
     }
 
+    // docks and panels aren't clients. they are shown where they want to be and
+    // only take their struts off of the space clients are laid out in.
+    fn manage_dock(&mut self, win: Window) -> Result<(), ReplyOrIdError> {
+        self.conn.change_window_attributes(
+            win,
+            &ChangeWindowAttributesAux::new()
+                .event_mask(EventMask::PROPERTY_CHANGE | EventMask::STRUCTURE_NOTIFY),
+        )?;
+        self.conn.map_window(win)?;
+        self.update_strut(win)
+    }
+
+    fn update_strut(&mut self, win: Window) -> Result<(), ReplyOrIdError> {
+        let strut = self.read_strut(win)?;
+        self.struts.retain(|(w, _)| *w != win);
+        if let Some(strut) = strut {
+            self.struts.push((win, strut));
+        }
+        self.apply_struts()
+    }
+
+    fn remove_strut(&mut self, win: Window) -> Result<(), ReplyOrIdError> {
+        if !self.struts.iter().any(|(w, _)| *w == win) {
+            return Ok(());
+        }
+        self.struts.retain(|(w, _)| *w != win);
+        self.apply_struts()
+    }
+
+    // _NET_WM_STRUT_PARTIAL takes precedence over _NET_WM_STRUT
+    fn read_strut(&self, win: Window) -> Result<Option<WStrut>, ReplyOrIdError> {
+        for prop in [self.atoms._NET_WM_STRUT_PARTIAL, self.atoms._NET_WM_STRUT] {
+            let reply = self
+                .conn
+                .get_property(false, win, prop, AtomEnum::CARDINAL, 0, 12)?
+                .reply()?;
+            let strut = reply
+                .value32()
+                .and_then(|values| WStrut::parse(&values.collect::<Vec<_>>()));
+            if strut.is_some() {
+                return Ok(strut);
+            }
+        }
+        Ok(None)
+    }
+
+    // takes the struts off of the area of each monitor and lays out the monitors
+    // whose area changed
+    fn apply_struts(&mut self) -> Result<(), ReplyOrIdError> {
+        let screen = (self.screen.width_in_pixels, self.screen.height_in_pixels);
+        for i in 0..self.monitors.len() {
+            let m = &mut self.monitors[i];
+            let rect = self
+                .struts
+                .iter()
+                .fold(m.full_rect, |rect, (_, s)| s.apply(rect, screen));
+            if (rect.x, rect.y, rect.w, rect.h) != (m.rect.x, m.rect.y, m.rect.w, m.rect.h) {
+                m.rect = rect;
+                self.recompute_layout(i)?;
+            }
+        }
+        self.update_workarea()
+    }
+
     fn update_client_list(&self) -> Result<(), ReplyOrIdError> {
         self.conn
             .delete_property(self.screen.root, self.atoms._NET_CLIENT_LIST)?;
@@ -1376,17 +4127,20 @@ impl<'a, C: Connection> WinMan<'a, C> {
         Ok(())
     }
 
-    fn update_layout(&mut self, layout: WLayout) {
+    fn update_layout(&mut self, layout: WLayout) -> Result<(), ReplyOrIdError> {
         let m = &mut self.monitors[self.selmon];
         if m.set_layout(layout) {
             m.bar.update_layout_symbol(m.layout);
-            self.recompute_layout(self.selmon).unwrap();
+            self.recompute_layout(self.selmon)?;
+            self.emit(event::layout(self.selmon, layout));
+            self.show_osd(format!("layout {layout}"))?;
         }
+        Ok(())
     }
 
     fn warp_pointer_to_focused_client(&self) -> Result<(), ReplyOrIdError> {
         if let Some(ci) = self.monitors[self.selmon].client {
-            let c = self.monitors[self.selmon].clients[ci];
+            let c = &self.monitors[self.selmon].clients[ci];
             if let Ok(pointer_reply) = self.conn.query_pointer(c.window) {
                 if let Ok(pointer) = pointer_reply.reply() {
                     if !pointer.same_screen {
@@ -1423,6 +4177,26 @@ impl<'a, C: Connection> WinMan<'a, C> {
         Ok(())
     }
 
+    // the _NET_WM_WINDOW_TYPE of a window, which lists its types in order of preference
+    fn window_types(&self, window: Window) -> Result<Vec<Atom>, ReplyOrIdError> {
+        let reply = self
+            .conn
+            .get_property(
+                false,
+                window,
+                self.atoms._NET_WM_WINDOW_TYPE,
+                AtomEnum::ATOM,
+                0,
+                u32::MAX,
+            )?
+            .reply();
+        let types = match reply {
+            Ok(reply) => reply.value32().map(|v| v.collect()).unwrap_or_default(),
+            Err(_) => vec![],
+        };
+        Ok(types)
+    }
+
     fn window_property_exists(
         &mut self,
         window: Window,
@@ -1447,3 +4221,18 @@ impl<'a, C: Connection> WinMan<'a, C> {
         Ok(false)
     }
 }
+
+// clients without WM_HINTS or its input field get the focus like any other
+fn accepts_input(hints: Option<&WmHints>) -> bool {
+    hints.and_then(|h| h.input).unwrap_or(true)
+}
+
+// whether a state ends up set by a _NET_WM_STATE request, whose action is
+// 0 to remove, 1 to add or 2 to toggle it
+fn wm_state_requested(action: u32, is_set: bool) -> bool {
+    match action {
+        1 => true,
+        2 => !is_set,
+        _ => false,
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    path::Path,
+    process::{Child, Command, Stdio},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{
+        AtomEnum, ConnectionExt, CreateWindowAux, EventMask, GetGeometryReply, MapState, PropMode,
+        Window, WindowClass,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+    COPY_DEPTH_FROM_PARENT,
+};
+
+pub const SCREEN_WIDTH: u16 = 1280;
+pub const SCREEN_HEIGHT: u16 = 800;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// tests run in parallel, so every test gets a display of its own
+static NEXT_DISPLAY: Mutex<u32> = Mutex::new(90);
+
+fn free_display() -> u32 {
+    let mut next = NEXT_DISPLAY.lock().unwrap();
+    while Path::new(&format!("/tmp/.X{}-lock", *next)).exists() {
+        *next += 1;
+    }
+    *next += 1;
+    *next - 1
+}
+
+// a nested x server with wwm managing it.
+// Xvfb is used unless `WWM_TEST_XSERVER=Xephyr` is set, which is handy for
+// watching what a test does.
+pub struct TestSession {
+    pub conn: RustConnection,
+    pub root: Window,
+    wwm: Child,
+    server: Child,
+}
+
+impl TestSession {
+    // panics if the x server can't be started, so that a broken setup fails the tests
+    pub fn start() -> Self {
+        let display = format!(":{}", free_display());
+        let server = std::env::var("WWM_TEST_XSERVER").unwrap_or_else(|_| "Xvfb".into());
+        let screen = format!("{SCREEN_WIDTH}x{SCREEN_HEIGHT}");
+
+        let mut cmd = Command::new(&server);
+        cmd.arg(&display);
+        match server.as_str() {
+            "Xephyr" => cmd.args(["-screen", &screen, "-ac", "-br", "-noreset"]),
+            _ => cmd.args(["-screen", "0", &format!("{screen}x24"), "-ac"]),
+        };
+        let server = match cmd.stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+            Ok(child) => child,
+            Err(e) => panic!("could not start {server}: {e}"),
+        };
+
+        let (conn, screen_num) = match wait_for(|| x11rb::connect(Some(&display)).ok()) {
+            Some(c) => c,
+            None => {
+                kill(server);
+                panic!("x server on {display} never came up");
+            }
+        };
+        let root = conn.setup().roots[screen_num].root;
+
+        let wwm = Command::new(env!("CARGO_BIN_EXE_wwm"))
+            .env("DISPLAY", &display)
+            // keeps the config file of whoever runs the tests out of them
+            .env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"))
+            // and the ipc socket away from the session running them
+            .env("XDG_RUNTIME_DIR", env!("CARGO_TARGET_TMPDIR"))
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("failed to start wwm");
+
+        let session = Self {
+            conn,
+            root,
+            wwm,
+            server,
+        };
+
+        // wwm has taken over once it selects substructure redirect on the root
+        let managed = wait_for(|| {
+            let attrs = session
+                .conn
+                .get_window_attributes(root)
+                .ok()?
+                .reply()
+                .ok()?;
+            (u32::from(attrs.all_event_masks) & u32::from(EventMask::SUBSTRUCTURE_REDIRECT) != 0)
+                .then_some(())
+        });
+        assert!(managed.is_some(), "wwm never became the window manager");
+
+        session
+    }
+
+    // creates and maps a plain top level window like a client would
+    pub fn spawn_client(&self, name: &str) -> Window {
+        let conn = &self.conn;
+        let screen = &conn.setup().roots[0];
+        let win = conn.generate_id().unwrap();
+
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            win,
+            self.root,
+            0,
+            0,
+            100,
+            100,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            screen.root_visual,
+            &CreateWindowAux::new().event_mask(EventMask::STRUCTURE_NOTIFY),
+        )
+        .unwrap();
+        conn.change_property8(
+            PropMode::REPLACE,
+            win,
+            AtomEnum::WM_NAME,
+            AtomEnum::STRING,
+            name.as_bytes(),
+        )
+        .unwrap();
+        conn.map_window(win).unwrap();
+        conn.flush().unwrap();
+
+        let mapped = wait_for(|| {
+            let attrs = conn.get_window_attributes(win).ok()?.reply().ok()?;
+            (attrs.map_state == MapState::VIEWABLE && self.client_list().contains(&win))
+                .then_some(())
+        });
+        assert!(mapped.is_some(), "client {name} was never managed");
+
+        win
+    }
+
+    pub fn destroy_client(&self, win: Window) {
+        self.conn.destroy_window(win).unwrap();
+        self.conn.flush().unwrap();
+        let gone = wait_for(|| (!self.client_list().contains(&win)).then_some(()));
+        assert!(gone.is_some(), "client was never unmanaged");
+    }
+
+    pub fn geometry(&self, win: Window) -> GetGeometryReply {
+        self.conn.get_geometry(win).unwrap().reply().unwrap()
+    }
+
+    pub fn client_list(&self) -> Vec<Window> {
+        self.root_windows("_NET_CLIENT_LIST")
+    }
+
+    pub fn active_window(&self) -> Option<Window> {
+        self.root_windows("_NET_ACTIVE_WINDOW").first().copied()
+    }
+
+    pub fn input_focus(&self) -> Window {
+        self.conn.get_input_focus().unwrap().reply().unwrap().focus
+    }
+
+    // the `_NET_SUPPORTING_WM_CHECK` window, if it points at itself like it should
+    pub fn wm_check(&self) -> Option<Window> {
+        let check = *self.root_windows("_NET_SUPPORTING_WM_CHECK").first()?;
+        (self.windows(check, "_NET_SUPPORTING_WM_CHECK") == [check]).then_some(check)
+    }
+
+    fn root_windows(&self, prop: &str) -> Vec<Window> {
+        self.windows(self.root, prop)
+    }
+
+    fn windows(&self, win: Window, prop: &str) -> Vec<Window> {
+        let atom = self
+            .conn
+            .intern_atom(false, prop.as_bytes())
+            .unwrap()
+            .reply()
+            .unwrap()
+            .atom;
+        self.conn
+            .get_property(false, win, atom, AtomEnum::WINDOW, 0, u32::MAX)
+            .unwrap()
+            .reply()
+            .ok()
+            .and_then(|r| r.value32().map(|v| v.collect()))
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for TestSession {
+    fn drop(&mut self) {
+        let _ = self.wwm.kill();
+        let _ = self.wwm.wait();
+        let _ = self.server.kill();
+        let _ = self.server.wait();
+    }
+}
+
+fn kill(mut child: Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+// polls `f` until it yields something or the timeout is reached
+pub fn wait_for<T>(mut f: impl FnMut() -> Option<T>) -> Option<T> {
+    let start = Instant::now();
+    while start.elapsed() < TIMEOUT {
+        if let Some(v) = f() {
+            return Some(v);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    None
+}
+
+// polls until `f` holds, panicking with `msg` if it never does
+pub fn assert_eventually(msg: &str, mut f: impl FnMut() -> bool) {
+    assert!(wait_for(|| f().then_some(())).is_some(), "{msg}");
+}
@@ -0,0 +1,106 @@
+// runs wwm against a real x server and checks what clients end up with.
+// the tests need Xvfb, so they only run with `cargo test -- --ignored`, and fail when
+// no x server can be started.
+
+mod common;
+
+use common::{assert_eventually, TestSession, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+#[test]
+#[ignore = "needs Xvfb, run with --ignored"]
+fn single_client_fills_monitor_below_bar() {
+    let s = TestSession::start();
+    let win = s.spawn_client("single");
+
+    assert_eventually("client does not fill the monitor", || {
+        let g = s.geometry(win);
+        let border = g.border_width * 2;
+        g.x == 0
+            && g.y > 0
+            && g.width + border == SCREEN_WIDTH
+            && g.y as u16 + g.height + border == SCREEN_HEIGHT
+    });
+}
+
+#[test]
+#[ignore = "needs Xvfb, run with --ignored"]
+fn second_client_is_stacked_next_to_main() {
+    let s = TestSession::start();
+    let main = s.spawn_client("main");
+    let stack = s.spawn_client("stack");
+
+    assert_eventually("clients are not tiled side by side", || {
+        let (m, st) = (s.geometry(main), s.geometry(stack));
+        m.x == 0
+            && st.x == m.x + (m.width + m.border_width * 2) as i16
+            && st.x as u16 + st.width + st.border_width * 2 == SCREEN_WIDTH
+            && m.y == st.y
+    });
+}
+
+#[test]
+#[ignore = "needs Xvfb, run with --ignored"]
+fn closing_a_client_retiles_the_rest() {
+    let s = TestSession::start();
+    let main = s.spawn_client("main");
+    let stack = s.spawn_client("stack");
+
+    s.destroy_client(main);
+
+    assert_eventually("remaining client does not fill the monitor", || {
+        let g = s.geometry(stack);
+        g.x == 0 && g.width + g.border_width * 2 == SCREEN_WIDTH
+    });
+}
+
+#[test]
+#[ignore = "needs Xvfb, run with --ignored"]
+fn new_client_is_focused() {
+    let s = TestSession::start();
+    let first = s.spawn_client("first");
+    assert_eventually("first client is not focused", || {
+        s.input_focus() == first && s.active_window() == Some(first)
+    });
+
+    let second = s.spawn_client("second");
+    assert_eventually("second client is not focused", || {
+        s.input_focus() == second && s.active_window() == Some(second)
+    });
+}
+
+#[test]
+#[ignore = "needs Xvfb, run with --ignored"]
+fn focus_returns_after_closing_focused_client() {
+    let s = TestSession::start();
+    let first = s.spawn_client("first");
+    let second = s.spawn_client("second");
+
+    s.destroy_client(second);
+
+    assert_eventually("focus did not return to the remaining client", || {
+        s.input_focus() == first && s.active_window() == Some(first)
+    });
+}
+
+#[test]
+#[ignore = "needs Xvfb, run with --ignored"]
+fn client_list_tracks_managed_windows() {
+    let s = TestSession::start();
+    let a = s.spawn_client("a");
+    let b = s.spawn_client("b");
+
+    let list = s.client_list();
+    assert!(list.contains(&a) && list.contains(&b));
+
+    s.destroy_client(a);
+    assert_eq!(s.client_list(), vec![b]);
+}
+
+#[test]
+#[ignore = "needs Xvfb, run with --ignored"]
+fn ewmh_support_is_advertised() {
+    let s = TestSession::start();
+    assert_eventually("no _NET_SUPPORTING_WM_CHECK window", || {
+        s.wm_check().is_some()
+    });
+}
@@ -0,0 +1,216 @@
+use std::{
+    ffi::CStr,
+    io,
+    os::{
+        raw::{c_char, c_int, c_long, c_uint, c_ushort, c_void},
+        unix::io::RawFd,
+    },
+    ptr,
+};
+
+// the mixer and control amixer would use for `amixer sget Master`, which goes through
+// pulseaudio or pipewire when alsa is set up for them
+const CARD: &CStr = c"default";
+const CONTROL: &CStr = c"Master";
+// `SND_MIXER_SCHN_FRONT_LEFT`
+const LEFT: c_int = 0;
+
+#[repr(C)]
+struct SndMixer(c_void);
+#[repr(C)]
+struct SndMixerElem(c_void);
+#[repr(C)]
+struct SndMixerSelemId(c_void);
+
+#[link(name = "asound")]
+extern "C" {
+    fn snd_mixer_open(mixer: *mut *mut SndMixer, mode: c_int) -> c_int;
+    fn snd_mixer_close(mixer: *mut SndMixer) -> c_int;
+    fn snd_mixer_attach(mixer: *mut SndMixer, name: *const c_char) -> c_int;
+    fn snd_mixer_selem_register(
+        mixer: *mut SndMixer,
+        options: *mut c_void,
+        classp: *mut *mut c_void,
+    ) -> c_int;
+    fn snd_mixer_load(mixer: *mut SndMixer) -> c_int;
+    fn snd_mixer_handle_events(mixer: *mut SndMixer) -> c_int;
+    fn snd_mixer_poll_descriptors_count(mixer: *mut SndMixer) -> c_int;
+    fn snd_mixer_poll_descriptors(
+        mixer: *mut SndMixer,
+        pfds: *mut libc::pollfd,
+        space: c_uint,
+    ) -> c_int;
+    fn snd_mixer_poll_descriptors_revents(
+        mixer: *mut SndMixer,
+        pfds: *mut libc::pollfd,
+        nfds: c_uint,
+        revents: *mut c_ushort,
+    ) -> c_int;
+    fn snd_mixer_selem_id_malloc(id: *mut *mut SndMixerSelemId) -> c_int;
+    fn snd_mixer_selem_id_free(id: *mut SndMixerSelemId);
+    fn snd_mixer_selem_id_set_index(id: *mut SndMixerSelemId, index: c_uint);
+    fn snd_mixer_selem_id_set_name(id: *mut SndMixerSelemId, name: *const c_char);
+    fn snd_mixer_find_selem(mixer: *mut SndMixer, id: *const SndMixerSelemId) -> *mut SndMixerElem;
+    fn snd_mixer_selem_get_playback_volume_range(
+        elem: *mut SndMixerElem,
+        min: *mut c_long,
+        max: *mut c_long,
+    ) -> c_int;
+    fn snd_mixer_selem_get_playback_volume(
+        elem: *mut SndMixerElem,
+        channel: c_int,
+        value: *mut c_long,
+    ) -> c_int;
+    fn snd_mixer_selem_has_playback_switch(elem: *mut SndMixerElem) -> c_int;
+    fn snd_mixer_selem_get_playback_switch(
+        elem: *mut SndMixerElem,
+        channel: c_int,
+        value: *mut c_int,
+    ) -> c_int;
+}
+
+// the master control of the default mixer, read through alsa-lib instead of amixer
+pub struct WMixer {
+    mixer: *mut SndMixer,
+    elem: *mut SndMixerElem,
+}
+
+// the handle is only ever used by the thread that owns it
+unsafe impl Send for WMixer {}
+
+impl WMixer {
+    pub fn open() -> Option<Self> {
+        let mut mixer = ptr::null_mut();
+        if unsafe { snd_mixer_open(&mut mixer, 0) } < 0 {
+            return None;
+        }
+        // closed along with the mixer when anything below fails
+        let mut this = Self {
+            mixer,
+            elem: ptr::null_mut(),
+        };
+        unsafe {
+            if snd_mixer_attach(mixer, CARD.as_ptr()) < 0
+                || snd_mixer_selem_register(mixer, ptr::null_mut(), ptr::null_mut()) < 0
+                || snd_mixer_load(mixer) < 0
+            {
+                return None;
+            }
+            let mut id = ptr::null_mut();
+            if snd_mixer_selem_id_malloc(&mut id) < 0 {
+                return None;
+            }
+            snd_mixer_selem_id_set_index(id, 0);
+            snd_mixer_selem_id_set_name(id, CONTROL.as_ptr());
+            this.elem = snd_mixer_find_selem(mixer, id);
+            snd_mixer_selem_id_free(id);
+        }
+        (!this.elem.is_null()).then_some(this)
+    }
+
+    // the volume in percent and whether it is muted
+    pub fn volume(&self) -> Option<(u8, bool)> {
+        let (mut min, mut max, mut value, mut on) = (0, 0, 0, 1);
+        unsafe {
+            // the values only change once the events telling about it are handled
+            snd_mixer_handle_events(self.mixer);
+            if snd_mixer_selem_get_playback_volume_range(self.elem, &mut min, &mut max) < 0
+                || snd_mixer_selem_get_playback_volume(self.elem, LEFT, &mut value) < 0
+            {
+                return None;
+            }
+            if snd_mixer_selem_has_playback_switch(self.elem) != 0 {
+                snd_mixer_selem_get_playback_switch(self.elem, LEFT, &mut on);
+            }
+        }
+        Some((percent(value, min, max), on == 0))
+    }
+
+    // blocks until the mixer changes, returning false if it can't be waited on anymore,
+    // like when the sound server went away, or once `stop` becomes readable
+    pub fn wait(&self, stop: RawFd) -> bool {
+        let count = unsafe { snd_mixer_poll_descriptors_count(self.mixer) };
+        if count <= 0 {
+            return false;
+        }
+        // the mixer's descriptors come after the one of `stop`
+        let mut fds = vec![
+            libc::pollfd {
+                fd: -1,
+                events: 0,
+                revents: 0,
+            };
+            count as usize + 1
+        ];
+        fds[0] = libc::pollfd {
+            fd: stop,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let mixer_fds = &mut fds[1..];
+        let count = unsafe {
+            snd_mixer_poll_descriptors(self.mixer, mixer_fds.as_mut_ptr(), count as c_uint)
+        };
+        if count <= 0 {
+            return false;
+        }
+        let count = count as usize;
+        loop {
+            let res = unsafe { libc::poll(fds.as_mut_ptr(), (count + 1) as libc::nfds_t, -1) };
+            if res < 0 {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return false;
+            }
+            if fds[0].revents != 0 {
+                return false;
+            }
+            let mut revents = 0;
+            let res = unsafe {
+                snd_mixer_poll_descriptors_revents(
+                    self.mixer,
+                    fds[1..].as_mut_ptr(),
+                    count as c_uint,
+                    &mut revents,
+                )
+            };
+            let revents = revents as libc::c_short;
+            if res < 0 || revents & (libc::POLLERR | libc::POLLHUP | libc::POLLNVAL) != 0 {
+                return false;
+            }
+            if revents == 0 {
+                continue;
+            }
+            return unsafe { snd_mixer_handle_events(self.mixer) } >= 0;
+        }
+    }
+}
+
+impl Drop for WMixer {
+    fn drop(&mut self) {
+        unsafe { snd_mixer_close(self.mixer) };
+    }
+}
+
+// rounded like amixer shows it
+fn percent(value: c_long, min: c_long, max: c_long) -> u8 {
+    if max <= min {
+        return 0;
+    }
+    let percent = ((value - min) * 100 + (max - min) / 2) / (max - min);
+    percent.clamp(0, 100) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volumes_in_percent() {
+        assert_eq!(percent(32768, 0, 65536), 50);
+        assert_eq!(percent(-20, -40, 0), 50);
+        assert_eq!(percent(87, 0, 127), 69);
+        assert_eq!(percent(5, 5, 5), 0);
+    }
+}
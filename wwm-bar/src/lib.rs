@@ -1,27 +1,37 @@
-use std::{
-    rc::Rc,
-    sync::{Arc, Mutex},
-    thread,
-    time::Duration,
-};
+use std::{mem, rc::Rc};
 
-use status_module::{WBarModMask, WBarModule};
 use wwm_core::{
-    text::TextRenderer,
-    util::{bar::WBarOptions, primitives::WRect, WLayout},
+    icon::{WIcon, WIconPicture},
+    text::{FontError, TextDrawOptions, TextRenderer, TextStyle},
+    util::{
+        bar::{self, WBarOptions},
+        primitives::WRect,
+        WLayout,
+    },
 };
 use x11rb::{
     connection::Connection,
     protocol::{
-        render::{ConnectionExt as _, CreatePictureAux, Picture, PolyEdge, PolyMode},
+        render::{Color, ConnectionExt as _, CreatePictureAux, Picture, PolyEdge, PolyMode},
         xproto::{
-            BackingStore, ConnectionExt, CreateGCAux, CreateWindowAux, EventMask, Gcontext,
-            LineStyle, Rectangle, Window, WindowClass,
+            BackingStore, ConnectionExt, CreateGCAux, CreateWindowAux, EventMask, ExposeEvent,
+            Gcontext, LineStyle, Pixmap, Rectangle, Window, WindowClass,
         },
     },
+    rust_connection::{ConnectionError, ReplyOrIdError},
 };
 
+mod alsa;
+mod markup;
+mod mpd;
 pub mod status_module;
+pub mod status_worker;
+
+// the status modules are drawn next to each other with this in between
+const STATUS_SEPARATOR: &str = " | ";
+
+// a shown status section: its index, its colored spans and its width
+type StatusSpan<'s> = (usize, Vec<(&'s str, Color)>, u16);
 
 #[derive(Debug)]
 enum Redraw {
@@ -29,25 +39,49 @@ enum Redraw {
     LayoutSymbol,
     Title,
     Modules,
+    // a single status module, whose text changed
+    Module(usize),
 }
 
 pub struct WBar<'b, C: Connection> {
-    window: Window,
-    picture: Picture,
-    text_renderer: Rc<TextRenderer<'b, C>>,
+    // the window and everything needed to draw to it. bars are created before the font
+    // is loaded so that nothing is drawn until a text renderer is attached.
+    surface: Option<WBarSurface<'b, C>>,
+    damage: Vec<WRect>,
     bar_options: WBarOptions,
     tags: Vec<WBarTag>,
     layout_symbol: WLayout,
+    mode: Option<&'static str>,
+    kb_layout: Option<String>,
     title: String,
+    // the icon of the focused client, shown in front of its title
+    icon: Option<(WIcon, WIconPicture)>,
     layout_rect: WRect,
     title_rect: WRect,
     status_width: u16,
-    redraw_queue: Arc<Mutex<Vec<Redraw>>>,
+    redraw_queue: Vec<Redraw>,
+    is_focused: bool,
+    // unmapped while a fullscreen client covers it
+    hidden: bool,
+    // the texts the status is made of, one for each status module or a single one
+    status_sections: Vec<String>,
+    // whether the sections are the texts of the status modules, which can be clicked
+    status_modules: bool,
+    // the index and width of the sections as they were last drawn. a section that
+    // changes without changing any width is drawn on its own.
+    drawn_sections: Vec<(usize, u16)>,
+}
+
+struct WBarSurface<'b, C: Connection> {
+    window: Window,
+    // everything is drawn to this pixmap first and only the changed
+    // regions are copied to the window, which keeps the bar from flickering
+    buffer: Pixmap,
+    picture: Picture,
+    text_renderer: Rc<TextRenderer<'b, C>>,
     has_client_gc: Gcontext,
     has_client_gc_selected: Gcontext,
-    clear_gc: Gcontext,
-    is_focused: bool,
-    modules: Vec<WBarModule>,
+    copy_gc: Gcontext,
 }
 
 #[derive(Debug)]
@@ -57,6 +91,7 @@ pub struct WBarTag {
     rect: WRect,
     selected: bool,
     has_clients: bool,
+    urgent: bool,
 }
 
 impl WBarTag {
@@ -67,21 +102,58 @@ impl WBarTag {
             rect,
             selected,
             has_clients,
+            urgent: false,
         }
     }
 }
 
 impl<'b, C: Connection> WBar<'b, C> {
-    // FIXME: properly handle/propagate errors
-    pub fn new(
+    pub fn new(bar_options: WBarOptions) -> Self {
+        let mut x_offset = 0;
+
+        let tags = Self::init_tags(&bar_options, &mut x_offset);
+
+        x_offset += bar_options.section_padding;
+
+        // the layout section gets its width once there is a font to measure it with
+        let layout_rect = WRect::new(x_offset, 0, 0, bar_options.rect.h);
+        let title_rect = WRect::new(
+            x_offset,
+            0,
+            bar_options.rect.w - x_offset as u16,
+            bar_options.rect.h,
+        );
+
+        Self {
+            surface: None,
+            damage: Vec::new(),
+            tags,
+            bar_options,
+            layout_symbol: WLayout::MainStack,
+            mode: None,
+            kb_layout: None,
+            layout_rect,
+            title: String::new(),
+            icon: None,
+            title_rect,
+            status_width: 0,
+            redraw_queue: Vec::new(),
+            is_focused: false,
+            hidden: false,
+            status_sections: Vec::new(),
+            drawn_sections: Vec::new(),
+            status_modules: false,
+        }
+    }
+
+    // creates and maps the bar window, after which the bar is drawn in full
+    pub fn attach(
+        &mut self,
         conn: &C,
         text_renderer: Rc<TextRenderer<'b, C>>,
-        bar_options: WBarOptions,
-        mod_mask: WBarModMask,
-        status_interval: u64,
-    ) -> Self {
-        let layout_symbol = WLayout::MainStack;
-        let bar_win = conn.generate_id().unwrap();
+    ) -> Result<(), ReplyOrIdError> {
+        let bar_options = self.bar_options.clone();
+        let bar_win = conn.generate_id()?;
         conn.create_window(
             text_renderer.visual_info.root.depth,
             bar_win,
@@ -95,15 +167,30 @@ impl<'b, C: Connection> WBar<'b, C> {
             0,
             &CreateWindowAux::new()
                 .background_pixel(bar_options.colors.bg.0)
-                .event_mask(EventMask::BUTTON_PRESS)
+                .event_mask(
+                    EventMask::BUTTON_PRESS
+                        | EventMask::BUTTON_RELEASE
+                        | EventMask::EXPOSURE
+                        | EventMask::POINTER_MOTION
+                        | EventMask::LEAVE_WINDOW,
+                )
                 .backing_store(BackingStore::WHEN_MAPPED)
                 .override_redirect(1),
-        )
-        .unwrap();
+        )?;
 
-        let has_client_gc = conn.generate_id().unwrap();
-        let has_client_gc_selected = conn.generate_id().unwrap();
-        let clear_gc = conn.generate_id().unwrap();
+        let buffer = conn.generate_id()?;
+        conn.create_pixmap(
+            text_renderer.visual_info.root.depth,
+            buffer,
+            bar_win,
+            bar_options.rect.w,
+            bar_options.rect.h,
+        )?;
+
+        let has_client_gc = conn.generate_id()?;
+        let has_client_gc_selected = conn.generate_id()?;
+        let clear_gc = conn.generate_id()?;
+        let copy_gc = conn.generate_id()?;
 
         conn.create_gc(
             has_client_gc,
@@ -112,8 +199,7 @@ impl<'b, C: Connection> WBar<'b, C> {
                 .foreground(bar_options.colors.fg.0)
                 .line_width(1)
                 .line_style(LineStyle::SOLID),
-        )
-        .unwrap();
+        )?;
 
         conn.create_gc(
             has_client_gc_selected,
@@ -122,8 +208,7 @@ impl<'b, C: Connection> WBar<'b, C> {
                 .foreground(bar_options.colors.selected_fg.0)
                 .line_width(1)
                 .line_style(LineStyle::SOLID),
-        )
-        .unwrap();
+        )?;
 
         conn.create_gc(
             clear_gc,
@@ -132,81 +217,121 @@ impl<'b, C: Connection> WBar<'b, C> {
                 .foreground(bar_options.colors.bg.0)
                 .line_width(1)
                 .line_style(LineStyle::SOLID),
-        )
-        .unwrap();
+        )?;
+
+        conn.create_gc(copy_gc, bar_win, &CreateGCAux::new().graphics_exposures(0))?;
 
-        let picture = conn.generate_id().unwrap();
+        // the contents of a new pixmap are undefined
+        let buffer_rect = WRect::new(0, 0, bar_options.rect.w, bar_options.rect.h);
+        conn.poly_fill_rectangle(buffer, clear_gc, &[buffer_rect.into()])?;
+        conn.free_gc(clear_gc)?;
+
+        let picture = conn.generate_id()?;
         conn.render_create_picture(
             picture,
-            bar_win,
+            buffer,
             text_renderer.visual_info.root.pict_format,
             &CreatePictureAux::new()
                 .polyedge(PolyEdge::SMOOTH)
                 .polymode(PolyMode::IMPRECISE),
-        )
-        .unwrap();
+        )?;
 
-        let mut x_offset = 0;
-
-        let tags = Self::init_tags(bar_options, &mut x_offset);
+        self.surface = Some(WBarSurface {
+            window: bar_win,
+            buffer,
+            picture,
+            text_renderer,
+            has_client_gc,
+            has_client_gc_selected,
+            copy_gc,
+        });
+        self.layout_tags();
+        self.layout_rect.w = self.padded_width(&self.layout_text());
+        self.title_rect.x = self.layout_rect.x + self.layout_rect.w as i16;
+        self.title_rect.w = bar_options.rect.w - self.title_rect.x as u16;
 
-        x_offset += bar_options.section_padding as i16;
+        if !self.hidden {
+            conn.map_window(bar_win)?;
+        }
 
-        let layout_symbol_width =
-            text_renderer.text_width(layout_symbol) + (bar_options.padding * 2);
+        self.redraw_all();
+        Ok(())
+    }
 
-        let layout_rect = WRect::new(x_offset, 0, layout_symbol_width, bar_options.rect.h);
+    // builds the bar again with new options, like new colors or a new height. the window
+    // is created anew with the same text renderer, keeping what the bar shows.
+    pub fn reconfigure(
+        &mut self,
+        conn: &C,
+        bar_options: WBarOptions,
+    ) -> Result<(), ReplyOrIdError> {
+        let mut bar = Self::new(bar_options);
+        for (tag, old) in bar.tags.iter_mut().zip(&self.tags) {
+            tag.selected = old.selected;
+            tag.has_clients = old.has_clients;
+            tag.urgent = old.urgent;
+        }
+        bar.layout_symbol = self.layout_symbol;
+        bar.mode = self.mode;
+        bar.kb_layout = self.kb_layout.take();
+        bar.title = mem::take(&mut self.title);
+        bar.icon = self.icon.take();
+        bar.is_focused = self.is_focused;
+        bar.hidden = self.hidden;
+        bar.status_sections = mem::take(&mut self.status_sections);
+        bar.status_modules = self.status_modules;
+
+        let text_renderer = self.detach(conn)?;
+        *self = bar;
+        match text_renderer {
+            Some(text_renderer) => self.attach(conn, text_renderer),
+            None => Ok(()),
+        }
+    }
 
-        x_offset += layout_symbol_width as i16;
+    // destroys the window, handing back the text renderer it was drawn with
+    fn detach(&mut self, conn: &C) -> Result<Option<Rc<TextRenderer<'b, C>>>, ReplyOrIdError> {
+        let Some(s) = self.surface.take() else {
+            return Ok(None);
+        };
+        conn.render_free_picture(s.picture)?;
+        conn.free_pixmap(s.buffer)?;
+        conn.free_gc(s.has_client_gc)?;
+        conn.free_gc(s.has_client_gc_selected)?;
+        conn.free_gc(s.copy_gc)?;
+        conn.destroy_window(s.window)?;
+        Ok(Some(s.text_renderer))
+    }
 
-        let title_rect = WRect::new(
-            x_offset,
-            0,
-            bar_options.rect.w - x_offset as u16,
-            bar_options.rect.h,
-        );
+    // measures the sections again and redraws everything once the font of the
+    // text renderer has been swapped
+    pub fn font_changed(&mut self) {
+        // forces the title to make room for the status again
+        self.status_width = 0;
+        self.layout_tags();
+        self.resize_layout_section();
+        self.redraw_all();
+    }
 
-        conn.map_window(bar_win).unwrap();
+    fn redraw_all(&mut self) {
+        self.redraw_queue = (0..self.tags.len()).map(Redraw::Tag).collect();
+        self.redraw_queue
+            .extend([Redraw::LayoutSymbol, Redraw::Title, Redraw::Modules]);
+    }
 
-        let mut bar = Self {
-            window: bar_win,
-            picture,
-            tags,
-            text_renderer,
-            bar_options,
-            layout_symbol,
-            layout_rect,
-            title: String::new(),
-            title_rect,
-            status_width: 0,
-            redraw_queue: Arc::new(Mutex::new(vec![
-                Redraw::Tag(0),
-                Redraw::Tag(1),
-                Redraw::Tag(2),
-                Redraw::Tag(3),
-                Redraw::Tag(4),
-                Redraw::Tag(5),
-                Redraw::Tag(6),
-                Redraw::Tag(7),
-                Redraw::Tag(8),
-                Redraw::LayoutSymbol,
-                Redraw::Title,
-                Redraw::Modules,
-            ])),
-            has_client_gc,
-            has_client_gc_selected,
-            clear_gc,
-            is_focused: false,
-            modules: Self::init_modules(mod_mask),
-        };
-        bar.run_status_loop(status_interval);
-        bar
+    // the width of a section that fits the text along with the padding on both sides
+    fn padded_width(&self, text: &str) -> u16 {
+        let text_width = self
+            .surface
+            .as_ref()
+            .map_or(0, |s| s.text_renderer.measure(text).width);
+        text_width + self.bar_options.padding * 2
     }
 
-    fn init_tags(bar_options: WBarOptions, x_offset: &mut i16) -> Vec<WBarTag> {
+    fn init_tags(bar_options: &WBarOptions, x_offset: &mut i16) -> Vec<WBarTag> {
         let mut tags = Vec::with_capacity(bar_options.tag_count);
         for i in 0..bar_options.tag_count {
-            let text = i + 1;
+            let text = bar::tag_label(&bar_options.tag_names, i);
             let tag_rect = WRect::new(
                 *x_offset,
                 bar_options.rect.y,
@@ -220,67 +345,179 @@ impl<'b, C: Connection> WBar<'b, C> {
         tags
     }
 
-    fn init_modules(mod_mask: WBarModMask) -> Vec<WBarModule> {
-        let mut modules = vec![];
+    // fits each tag to its label, which can only be measured once there is a font
+    fn layout_tags(&mut self) {
+        let mut x_offset = 0;
+        for i in 0..self.tags.len() {
+            let w = self
+                .padded_width(&self.tags[i].text)
+                .max(self.bar_options.tag_width);
+            let rect = &mut self.tags[i].rect;
+            rect.x = x_offset;
+            rect.w = w;
+            x_offset += w as i16;
+        }
+        self.layout_rect.x = x_offset + self.bar_options.section_padding;
+    }
 
-        if mod_mask & WBarModMask::VOL {
-            modules.push(WBarModule::vol());
+    pub fn set_status(&mut self, status: impl ToString) {
+        self.status_sections = vec![status.to_string()];
+        self.status_modules = false;
+        self.redraw_queue.push(Redraw::Modules);
+    }
+
+    // sets the text of one of the status modules, which are shown next to each other
+    // leaving out the empty ones
+    pub fn set_status_section(&mut self, idx: usize, text: String) {
+        if !self.status_modules {
+            self.status_sections.clear();
+            self.status_modules = true;
         }
-        if mod_mask & WBarModMask::RAM {
-            modules.push(WBarModule::ram());
+        if self.status_sections.len() <= idx {
+            self.status_sections.resize(idx + 1, String::new());
         }
-        if mod_mask & WBarModMask::CPU {
-            modules.push(WBarModule::cpu());
+        self.status_sections[idx] = text;
+        self.redraw_queue.push(Redraw::Module(idx));
+    }
+
+    // the index of the status module under `x`, which is relative to the bar
+    pub fn status_section_at(&self, x: i16) -> Option<usize> {
+        if !self.status_modules {
+            return None;
         }
-        if mod_mask & WBarModMask::DATE {
-            modules.push(WBarModule::date());
+        let mut right = self.status_sections_x();
+        if x < right {
+            return None;
         }
-        if mod_mask & WBarModMask::VOL {
-            modules.push(WBarModule::time());
+        for (i, _, w) in self.status_spans(&self.status_sections) {
+            right += w as i16;
+            if x < right {
+                return Some(i);
+            }
         }
+        None
+    }
 
-        modules
+    // where the first status section starts, after the padding of the status
+    fn status_sections_x(&self) -> i16 {
+        self.bar_options.rect.w as i16 - self.status_width as i16 - self.bar_options.section_padding
+            + self.bar_options.padding as i16
     }
 
-    fn run_status_loop(&mut self, interval: u64) {
-        let queue = Arc::clone(&self.redraw_queue);
-        thread::spawn(move || loop {
-            if let Ok(mut queue) = queue.lock() {
-                queue.push(Redraw::Modules);
-            }
-            thread::sleep(Duration::from_millis(interval))
-        });
+    // the index, spans and width of the status sections that are shown. the ones after
+    // the first start with the separator.
+    fn status_spans<'s>(&self, sections: &'s [String]) -> Vec<StatusSpan<'s>> {
+        let fg = self.bar_options.colors.fg.1;
+        let shown = sections.iter().enumerate().filter(|(_, s)| !s.is_empty());
+        shown
+            .enumerate()
+            .map(|(n, (i, section))| {
+                let mut spans = markup::parse(section, fg);
+                if n > 0 {
+                    spans.insert(0, (STATUS_SEPARATOR, fg));
+                }
+                let text: String = spans.iter().map(|(t, _)| *t).collect();
+                let w = self
+                    .surface
+                    .as_ref()
+                    .map_or(0, |s| s.text_renderer.measure(&text).width);
+                (i, spans, w)
+            })
+            .collect()
     }
 
     pub fn has_pointer(&self, px: i16, py: i16) -> bool {
-        self.bar_options.rect.has_pointer(px, py)
+        !self.hidden && self.bar_options.rect.has_pointer(px, py)
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    // unmaps the bar while `hidden`. nothing is drawn until it is shown again,
+    // at which point it is drawn in full.
+    pub fn set_hidden(&mut self, conn: &C, hidden: bool) -> Result<(), ConnectionError> {
+        if self.hidden == hidden {
+            return Ok(());
+        }
+        self.hidden = hidden;
+        if let Some(s) = &self.surface {
+            if hidden {
+                conn.unmap_window(s.window)?;
+            } else {
+                conn.map_window(s.window)?;
+                self.redraw_all();
+            }
+        }
+        Ok(())
+    }
+
+    // only set once the bar is attached
+    pub fn window(&self) -> Option<Window> {
+        self.surface.as_ref().map(|s| s.window)
+    }
+
+    pub fn rect(&self) -> WRect {
+        self.bar_options.rect
     }
 
     pub fn select_tag_at_pos(&mut self, x: i16, y: i16) -> Option<usize> {
         if y > self.bar_options.rect.y + self.bar_options.rect.h as i16 {
             return None;
         }
+        self.tag_at_pos(x, y)
+    }
 
-        let mut tag_idx = None;
-        for (i, t) in self.tags.iter_mut().enumerate() {
-            if t.rect.has_pointer(x, y) {
-                tag_idx = Some(i);
-                break;
-            }
-        }
-        tag_idx
+    // the tag under `x` and `y`, which are relative to the bar
+    pub fn tag_at_pos(&self, x: i16, y: i16) -> Option<usize> {
+        self.tags.iter().position(|t| t.rect.has_pointer(x, y))
+    }
+
+    // where the tag is drawn, relative to the bar
+    pub fn tag_rect(&self, tag_idx: usize) -> WRect {
+        self.tags[tag_idx].rect
     }
 
     pub fn update_layout_symbol(&mut self, layout_symbol: WLayout) {
         self.layout_symbol = layout_symbol;
 
         // update the width of the layout symbol rect
-        self.layout_rect.w = self.text_renderer.text_width(layout_symbol);
+        self.layout_rect.w = self.padded_width(&self.layout_text());
+
+        self.redraw_queue.push(Redraw::Title);
+        self.redraw_queue.push(Redraw::LayoutSymbol);
+    }
+
+    pub fn set_mode(&mut self, mode: Option<&'static str>) {
+        self.mode = mode;
+        self.resize_layout_section();
+    }
+
+    pub fn set_kb_layout(&mut self, kb_layout: Option<String>) {
+        self.kb_layout = kb_layout;
+        self.resize_layout_section();
+    }
+
+    fn resize_layout_section(&mut self) {
+        // the keyboard layout and mode are shown next to the layout symbol so both
+        // the layout and title sections need to make room for them
+        self.layout_rect.w = self.padded_width(&self.layout_text());
+        self.title_rect.x =
+            self.layout_rect.x + self.layout_rect.w as i16 + self.bar_options.section_padding;
+
+        self.redraw_queue.push(Redraw::Title);
+        self.redraw_queue.push(Redraw::LayoutSymbol);
+    }
 
-        if let Ok(mut queue) = self.redraw_queue.lock() {
-            queue.push(Redraw::Title);
-            queue.push(Redraw::LayoutSymbol);
+    fn layout_text(&self) -> String {
+        let mut text = self.layout_symbol.to_string();
+        if let Some(kb_layout) = &self.kb_layout {
+            text.push_str(&format!(" {kb_layout}"));
         }
+        if let Some(mode) = self.mode {
+            text.push_str(&format!(" {mode}"));
+        }
+        text
     }
 
     pub fn update_title(&mut self, title: impl ToString) {
@@ -292,21 +529,39 @@ impl<'b, C: Connection> WBar<'b, C> {
 
         self.title_rect.x = new_x;
 
-        if let Ok(mut queue) = self.redraw_queue.lock() {
-            queue.push(Redraw::Title);
+        self.redraw_queue.push(Redraw::Title);
+    }
+
+    // the size icons fit in, which leaves a margin to the edges of the bar
+    pub fn icon_size(&self) -> u16 {
+        self.bar_options.rect.h * 3 / 4
+    }
+
+    // the icon is scaled to fit if it doesn't already
+    pub fn set_icon(&mut self, conn: &C, icon: Option<&WIcon>) -> Result<(), ReplyOrIdError> {
+        let icon = icon.map(|i| i.resized(self.icon_size()));
+        if self.icon.as_ref().map(|(i, _)| i) == icon.as_ref() {
+            return Ok(());
+        }
+        if let Some((_, picture)) = self.icon.take() {
+            picture.free(conn)?;
         }
+        if let (Some(icon), Some(s)) = (icon, &self.surface) {
+            let picture = WIconPicture::new(conn, &s.text_renderer.visual_info, &icon)?;
+            self.icon = Some((icon, picture));
+        }
+        self.redraw_queue.push(Redraw::Title);
+        Ok(())
     }
 
     pub fn update_tags(&mut self, selected: usize) {
-        if let Ok(mut queue) = self.redraw_queue.lock() {
-            for (i, tag) in self.tags.iter_mut().enumerate() {
-                if tag.id == selected {
-                    tag.selected = true;
-                    queue.push(Redraw::Tag(i));
-                } else if tag.id != selected && tag.selected {
-                    tag.selected = false;
-                    queue.push(Redraw::Tag(i));
-                }
+        for (i, tag) in self.tags.iter_mut().enumerate() {
+            if tag.id == selected {
+                tag.selected = true;
+                self.redraw_queue.push(Redraw::Tag(i));
+            } else if tag.id != selected && tag.selected {
+                tag.selected = false;
+                self.redraw_queue.push(Redraw::Tag(i));
             }
         }
     }
@@ -316,159 +571,278 @@ impl<'b, C: Connection> WBar<'b, C> {
         // queue redrawing of the newly focused bar
         // because we want to fill the focused tags client indicator
         // rectangle
-        let idx = self.tags.iter().position(|t| t.selected).unwrap();
-        if let Ok(mut queue) = self.redraw_queue.lock() {
-            queue.push(Redraw::Tag(idx));
+        if let Some(idx) = self.tags.iter().position(|t| t.selected) {
+            self.redraw_queue.push(Redraw::Tag(idx));
         }
     }
 
     pub fn set_has_clients(&mut self, tag_idx: usize, has_clients: bool) {
-        if let Ok(mut queue) = self.redraw_queue.lock() {
-            let tag = &mut self.tags[tag_idx];
-            if tag.has_clients != has_clients {
-                queue.push(Redraw::Tag(tag_idx))
-            }
-            tag.has_clients = has_clients;
+        let tag = &mut self.tags[tag_idx];
+        if tag.has_clients != has_clients {
+            self.redraw_queue.push(Redraw::Tag(tag_idx))
         }
+        tag.has_clients = has_clients;
     }
 
-    pub fn draw(&mut self, conn: &C) {
-        if let Ok(mut queue) = self.redraw_queue.lock() {
-            if queue.is_empty() {
-                return;
-            }
+    pub fn set_urgent(&mut self, tag_idx: usize, urgent: bool) {
+        let tag = &mut self.tags[tag_idx];
+        if tag.urgent != urgent {
+            self.redraw_queue.push(Redraw::Tag(tag_idx))
+        }
+        tag.urgent = urgent;
+    }
 
-            for redraw_item in queue.drain(..) {
-                match redraw_item {
-                    Redraw::Tag(i) => {
-                        let tag = &self.tags[i];
-                        let (fg, bg) = if tag.selected {
-                            (
-                                self.bar_options.colors.selected_fg.1,
-                                self.bar_options.colors.selected_bg.1,
-                            )
-                        } else {
-                            (self.bar_options.colors.fg.1, self.bar_options.colors.bg.1)
-                        };
-                        self.text_renderer
-                            .draw(
-                                tag.rect,
-                                &tag.text,
-                                self.bar_options.padding,
-                                self.picture,
-                                self.window,
-                                bg,
-                                fg,
-                                true,
-                            )
-                            .unwrap();
-
-                        let client_rect: Rectangle =
-                            WRect::new(tag.rect.x + 1, tag.rect.y + 1, 3, 3).into();
-                        let client_rect_fill: Rectangle =
-                            WRect::new(tag.rect.x + 1, tag.rect.y + 1, 4, 4).into();
-
-                        if !tag.has_clients {
-                            continue;
-                        }
-
-                        if tag.selected && self.is_focused {
-                            conn.poly_fill_rectangle(
-                                self.window,
-                                self.has_client_gc_selected,
-                                &[client_rect_fill],
-                            )
-                            .unwrap();
-                        } else if tag.selected && !self.is_focused {
-                            conn.poly_rectangle(
-                                self.window,
-                                self.has_client_gc_selected,
-                                &[client_rect],
-                            )
-                            .unwrap();
-                        } else if !tag.selected {
-                            conn.poly_rectangle(self.window, self.has_client_gc, &[client_rect])
-                                .unwrap();
-                        }
-                    }
-                    Redraw::LayoutSymbol => {
-                        self.text_renderer
-                            .draw(
-                                self.layout_rect,
-                                &self.layout_symbol.to_string(),
-                                self.bar_options.padding,
-                                self.picture,
-                                self.window,
-                                self.bar_options.colors.bg.1,
-                                self.bar_options.colors.fg.1,
-                                false,
-                            )
-                            .unwrap();
-                    }
-                    Redraw::Title => {
-                        self.text_renderer
-                            .draw(
-                                self.title_rect,
-                                &self.title,
-                                self.bar_options.padding,
-                                self.picture,
-                                self.window,
-                                self.bar_options.colors.bg.1,
-                                self.bar_options.colors.fg.1,
-                                false,
-                            )
-                            .unwrap();
-                    }
-                    Redraw::Modules => {
-                        let mut strings = vec![];
-                        for module in self.modules.iter() {
-                            strings.push(module.0.update());
-                        }
-
-                        let text = strings.join(" | ");
-
-                        let new_status_width = self.text_renderer.text_width(&text);
-
-                        let mut rect = WRect::new(
-                            (self.bar_options.rect.w - self.status_width) as i16,
-                            0,
-                            self.status_width,
-                            self.bar_options.rect.h,
-                        );
-
-                        if new_status_width < self.status_width {
-                            // clear previous status section size
-                            // otherwise, if the current text size is smaller,
-                            // there will be remnants of the previous update's text
-                            // in the bar.
-                            conn.poly_fill_rectangle(self.window, self.clear_gc, &[rect.into()])
-                                .unwrap();
-                        }
-
-                        rect.x = (self.bar_options.rect.w
-                            - new_status_width
-                            - self.bar_options.section_padding as u16)
-                            as i16;
-                        rect.w = new_status_width;
-                        self.status_width = new_status_width;
-
-                        self.title_rect.w = self.title_rect.x.abs_diff(rect.x);
-                        self.text_renderer
-                            .draw(
-                                rect,
-                                &text,
-                                self.bar_options.padding,
-                                self.picture,
-                                self.window,
-                                self.bar_options.colors.bg.1,
-                                self.bar_options.colors.fg.1,
-                                false,
-                            )
-                            .unwrap();
-                    }
-                }
+    // queues the exposed region of the bar window to be copied from the buffer again
+    pub fn handle_expose(&mut self, evt: &ExposeEvent) -> bool {
+        if self.surface.as_ref().map(|s| s.window) != Some(evt.window) {
+            return false;
+        }
+        self.damage.push(WRect::new(
+            evt.x as i16,
+            evt.y as i16,
+            evt.width,
+            evt.height,
+        ));
+        true
+    }
+
+    pub fn draw(&mut self, conn: &C) -> Result<(), FontError> {
+        if self.surface.is_none() || self.hidden {
+            // everything gets queued once the bar is attached or shown anyway
+            self.redraw_queue.clear();
+            return Ok(());
+        }
+
+        for redraw_item in std::mem::take(&mut self.redraw_queue) {
+            match redraw_item {
+                Redraw::Tag(i) => self.draw_tag(conn, i)?,
+                Redraw::LayoutSymbol => self.draw_layout_symbol()?,
+                Redraw::Title => self.draw_title(conn)?,
+                Redraw::Modules => self.draw_status(conn)?,
+                Redraw::Module(i) => self.draw_status_section(conn, i)?,
             }
-            conn.flush().unwrap();
         }
+
+        let Some(s) = &self.surface else {
+            return Ok(());
+        };
+        if self.damage.is_empty() {
+            return Ok(());
+        }
+
+        for rect in self.damage.drain(..) {
+            conn.copy_area(
+                s.buffer, s.window, s.copy_gc, rect.x, rect.y, rect.x, rect.y, rect.w, rect.h,
+            )?;
+        }
+        conn.flush()?;
+        Ok(())
+    }
+
+    fn draw_tag(&mut self, conn: &C, i: usize) -> Result<(), FontError> {
+        let Some(s) = &self.surface else {
+            return Ok(());
+        };
+        let tag = &self.tags[i];
+        let (fg, bg) = if tag.selected {
+            (
+                self.bar_options.colors.selected_fg.1,
+                self.bar_options.colors.selected_bg.1,
+            )
+        } else if tag.urgent {
+            (
+                self.bar_options.colors.selected_fg.1,
+                self.bar_options.colors.urgent_bg.1,
+            )
+        } else {
+            (self.bar_options.colors.fg.1, self.bar_options.colors.bg.1)
+        };
+        let style = if tag.selected && self.is_focused {
+            self.bar_options.focused_tag_style
+        } else {
+            TextStyle::default()
+        };
+        s.text_renderer.draw(
+            tag.rect,
+            &[(&tag.text, fg)],
+            self.bar_options.padding,
+            s.picture,
+            s.window,
+            TextDrawOptions {
+                bg,
+                style,
+                centered: true,
+            },
+        )?;
+        self.damage.push(tag.rect);
+
+        let client_rect: Rectangle = WRect::new(tag.rect.x + 1, tag.rect.y + 1, 3, 3).into();
+        let client_rect_fill: Rectangle = WRect::new(tag.rect.x + 1, tag.rect.y + 1, 4, 4).into();
+
+        if !tag.has_clients {
+            return Ok(());
+        }
+
+        if tag.selected && self.is_focused {
+            conn.poly_fill_rectangle(s.buffer, s.has_client_gc_selected, &[client_rect_fill])?;
+        } else if tag.selected && !self.is_focused {
+            conn.poly_rectangle(s.buffer, s.has_client_gc_selected, &[client_rect])?;
+        } else if !tag.selected {
+            conn.poly_rectangle(s.buffer, s.has_client_gc, &[client_rect])?;
+        }
+        Ok(())
+    }
+
+    fn draw_layout_symbol(&mut self) -> Result<(), FontError> {
+        let Some(s) = &self.surface else {
+            return Ok(());
+        };
+        s.text_renderer.draw(
+            self.layout_rect,
+            &[(&self.layout_text(), self.bar_options.colors.fg.1)],
+            self.bar_options.padding,
+            s.picture,
+            s.window,
+            TextDrawOptions {
+                bg: self.bar_options.colors.bg.1,
+                ..Default::default()
+            },
+        )?;
+        self.damage.push(self.layout_rect);
+        Ok(())
+    }
+
+    fn draw_title(&mut self, conn: &C) -> Result<(), FontError> {
+        let Some(s) = &self.surface else {
+            return Ok(());
+        };
+        let mut text_rect = self.title_rect;
+        if let Some((_, icon)) = &self.icon {
+            let size = self.icon_size();
+            let padding = self.bar_options.padding;
+            let rect = WRect::new(
+                text_rect.x,
+                text_rect.y,
+                (size + padding).min(text_rect.w),
+                text_rect.h,
+            );
+            // nothing but the background behind the icon
+            s.text_renderer.draw(
+                rect,
+                &[],
+                padding,
+                s.picture,
+                s.window,
+                TextDrawOptions {
+                    bg: self.bar_options.colors.bg.1,
+                    ..Default::default()
+                },
+            )?;
+            let y = rect.y + (rect.h.saturating_sub(size) / 2) as i16;
+            icon.draw(conn, s.picture, rect.x + padding as i16, y, size)?;
+            text_rect.x += rect.w as i16;
+            text_rect.w -= rect.w;
+        }
+        s.text_renderer.draw(
+            text_rect,
+            &[(&self.title, self.bar_options.colors.fg.1)],
+            self.bar_options.padding,
+            s.picture,
+            s.window,
+            TextDrawOptions {
+                bg: self.bar_options.colors.bg.1,
+                ..Default::default()
+            },
+        )?;
+        self.damage.push(self.title_rect);
+        Ok(())
+    }
+
+    fn draw_status(&mut self, conn: &C) -> Result<(), FontError> {
+        // the title might have to be redrawn below, which needs all of self
+        let status = self.status_sections.clone();
+        let sections = self.status_spans(&status);
+        let padding = self.bar_options.padding;
+        let status_width = sections.iter().map(|(_, _, w)| w).sum::<u16>() + padding * 2;
+        let rect = WRect::new(
+            (self.bar_options.rect.w - status_width - self.bar_options.section_padding as u16)
+                as i16,
+            0,
+            status_width,
+            self.bar_options.rect.h,
+        );
+
+        // the title fills the space up to the status, so it has to be redrawn
+        // to cover what is left of a wider status or to make room for a wider one.
+        // it is drawn first so that a long title can't end up on top of the status.
+        if status_width != self.status_width {
+            self.status_width = status_width;
+            self.title_rect.w = self.title_rect.x.abs_diff(rect.x);
+            self.draw_title(conn)?;
+        }
+
+        let Some(s) = &self.surface else {
+            return Ok(());
+        };
+        // each section is drawn on its own, so the ones that didn't change come from the
+        // text cache instead of the whole status being rendered again
+        let mut x = rect.x;
+        let mut draw = |spans: &[(&str, Color)], w: u16| {
+            let section = WRect::new(x, 0, w, rect.h);
+            x += w as i16;
+            s.text_renderer.draw(
+                section,
+                spans,
+                0,
+                s.picture,
+                s.window,
+                TextDrawOptions {
+                    bg: self.bar_options.colors.bg.1,
+                    ..Default::default()
+                },
+            )
+        };
+        draw(&[], padding)?;
+        for (_, spans, w) in &sections {
+            draw(spans, *w)?;
+        }
+        draw(&[], padding)?;
+        self.damage.push(rect);
+        self.drawn_sections = sections.iter().map(|(i, _, w)| (*i, *w)).collect();
+        Ok(())
+    }
+
+    // draws just the section at `idx` when the others can stay where they are, and the
+    // whole status otherwise
+    fn draw_status_section(&mut self, conn: &C, idx: usize) -> Result<(), FontError> {
+        let status = self.status_sections.clone();
+        let sections = self.status_spans(&status);
+        let drawn = sections.iter().map(|(i, _, w)| (*i, *w));
+        if !drawn.eq(self.drawn_sections.iter().copied()) {
+            return self.draw_status(conn);
+        }
+        let Some(n) = sections.iter().position(|(i, _, _)| *i == idx) else {
+            // it was empty and still is
+            return Ok(());
+        };
+        let Some(s) = &self.surface else {
+            return Ok(());
+        };
+        let x: i16 = sections[..n].iter().map(|(_, _, w)| *w as i16).sum();
+        let (_, spans, w) = &sections[n];
+        let rect = WRect::new(self.status_sections_x() + x, 0, *w, self.bar_options.rect.h);
+        s.text_renderer.draw(
+            rect,
+            spans,
+            0,
+            s.picture,
+            s.window,
+            TextDrawOptions {
+                bg: self.bar_options.colors.bg.1,
+                ..Default::default()
+            },
+        )?;
+        self.damage.push(rect);
+        Ok(())
     }
 }
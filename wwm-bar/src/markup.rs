@@ -0,0 +1,80 @@
+use wwm_core::util::color::hex_to_rgba;
+use x11rb::protocol::render::Color;
+
+// status text can color parts of itself like dwm's status2d. `^c#rrggbb^` switches to a
+// color until the next one and `^d^` goes back to the default. anything else between
+// carets is left as it is.
+pub fn parse(text: &str, default: Color) -> Vec<(&str, Color)> {
+    let mut spans = vec![];
+    let mut color = default;
+    let mut rest = text;
+
+    while let Some(start) = rest.find('^') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('^') else {
+            break;
+        };
+
+        let tag = &after[..end];
+        let new_color = match tag.strip_prefix("c#") {
+            Some(hex) if hex.len() == 6 => u32::from_str_radix(hex, 16).ok().map(hex_to_rgba),
+            _ if tag == "d" => Some(default),
+            _ => None,
+        };
+        let Some(new_color) = new_color else {
+            // the closing caret might open a tag of its own
+            spans.push((&rest[..start + 1], color));
+            rest = after;
+            continue;
+        };
+
+        if start > 0 {
+            spans.push((&rest[..start], color));
+        }
+        color = new_color;
+        rest = &after[end + 1..];
+    }
+
+    if !rest.is_empty() {
+        spans.push((rest, color));
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FG: Color = Color {
+        red: 0xffff,
+        green: 0xffff,
+        blue: 0xffff,
+        alpha: 0xffff,
+    };
+
+    #[test]
+    fn plain_text_is_a_single_span() {
+        assert_eq!(parse("cpu: 4%", FG), vec![("cpu: 4%", FG)]);
+        assert!(parse("", FG).is_empty());
+    }
+
+    #[test]
+    fn colors_last_until_reset() {
+        let red = hex_to_rgba(0xff0000);
+        assert_eq!(
+            parse("bat: ^c#ff0000^9%^d^ | 12:00", FG),
+            vec![("bat: ", FG), ("9%", red), (" | 12:00", FG)]
+        );
+    }
+
+    #[test]
+    fn unknown_tags_are_kept() {
+        let green = hex_to_rgba(0x00ff00);
+        assert_eq!(parse("a^b^c", FG), vec![("a^", FG), ("b^c", FG)]);
+        assert_eq!(
+            parse("^x^^c#00ff00^ok", FG),
+            vec![("^", FG), ("x^", FG), ("ok", green)]
+        );
+        assert_eq!(parse("^c#zz^", FG), vec![("^", FG), ("c#zz^", FG)]);
+    }
+}
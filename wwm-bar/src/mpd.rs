@@ -0,0 +1,118 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+// mpd is asked on the status thread, which shouldn't hang on an mpd that doesn't answer
+const TIMEOUT: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct WSong {
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub paused: bool,
+}
+
+// a connection that got past the greeting of mpd
+struct WMpd(BufReader<TcpStream>);
+
+impl WMpd {
+    fn connect(address: &str) -> io::Result<Self> {
+        let addr = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, address.to_string()))?;
+        let stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let mut mpd = Self(BufReader::new(stream));
+        let mut greeting = String::new();
+        mpd.0.read_line(&mut greeting)?;
+        if !greeting.starts_with("OK MPD") {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, greeting));
+        }
+        Ok(mpd)
+    }
+
+    // sends the command and returns the lines of the answer up to the closing `OK`
+    fn command(&mut self, command: &str) -> io::Result<Vec<String>> {
+        writeln!(self.0.get_mut(), "{command}")?;
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            if self.0.read_line(&mut line)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let line = line.trim_end();
+            if line == "OK" {
+                return Ok(lines);
+            }
+            if line.starts_with("ACK") {
+                return Err(io::Error::other(line.to_string()));
+            }
+            lines.push(line.to_string());
+        }
+    }
+}
+
+// the song mpd is playing or paused on, if it isn't stopped
+pub fn current_song(address: &str) -> io::Result<Option<WSong>> {
+    let mut mpd = WMpd::connect(address)?;
+    let reply = mpd.command("command_list_begin\nstatus\ncurrentsong\ncommand_list_end")?;
+    Ok(parse_song(&reply))
+}
+
+pub fn toggle_pause(address: &str) -> io::Result<()> {
+    let mut mpd = WMpd::connect(address)?;
+    let playing = mpd.command("status")?.iter().any(|l| l == "state: play");
+    mpd.command(if playing { "pause 1" } else { "pause 0" })?;
+    Ok(())
+}
+
+// the song in the answers to `status` and `currentsong`, which are `key: value` lines
+fn parse_song(lines: &[String]) -> Option<WSong> {
+    let mut song = WSong::default();
+    let mut file = None;
+    for line in lines {
+        let Some((key, value)) = line.split_once(": ") else {
+            continue;
+        };
+        match key {
+            "state" if value == "stop" => return None,
+            "state" => song.paused = value == "pause",
+            "Artist" => song.artist = Some(value.to_string()),
+            "Title" => song.title = Some(value.to_string()),
+            "file" => file = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    // untagged files go by their name
+    if song.title.is_none() {
+        song.title = file.map(|f| f.rsplit('/').next().unwrap_or_default().to_string());
+    }
+    Some(song)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn songs_from_status_and_currentsong() {
+        let reply = lines("volume: 50\nstate: pause\nfile: a/b.flac\nArtist: Low\nTitle: Lazy");
+        let song = WSong {
+            artist: Some("Low".into()),
+            title: Some("Lazy".into()),
+            paused: true,
+        };
+        assert_eq!(parse_song(&reply), Some(song));
+        assert_eq!(parse_song(&lines("state: stop")), None);
+        let untagged = parse_song(&lines("state: play\nfile: music/track 01.mp3")).unwrap();
+        assert_eq!(untagged.title.as_deref(), Some("track 01.mp3"));
+    }
+}
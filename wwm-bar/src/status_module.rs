@@ -1,50 +1,129 @@
 use chrono::{DateTime, Local};
 use lazy_static::lazy_static;
 use std::{
+    cell::RefCell,
+    io::{self, Read},
+    os::unix::{io::AsRawFd, net::UnixStream},
     process::{Command, Stdio},
-    sync::Mutex,
-    time::SystemTime,
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+use sysinfo::{ComponentExt, CpuExt, System, SystemExt};
+
+use crate::{
+    alsa::WMixer,
+    mpd::{self, WSong},
 };
-use sysinfo::{CpuExt, System, SystemExt};
 
 lazy_static! {
     static ref SYS: Mutex<System> = Mutex::new(System::new_all());
 }
 
+// how long to wait before opening the mixer again when it can't be watched
+const MIXER_RETRY: Duration = Duration::from_secs(30);
+// how often a running script is checked on
+const SCRIPT_POLL: Duration = Duration::from_millis(50);
+
 const SUFFIX: [&str; 9] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB", "ZiB", "YiB"];
 
-pub trait WBarModuleTrait {
+pub trait WBarModuleTrait: Send {
     fn update(&self) -> String;
+    // the module was clicked in the bar
+    fn click(&self) {}
+    // for modules that can tell when they change rather than waiting for their interval.
+    // `changed` updates the module and returns false once it is gone, and `stop` becomes
+    // readable as soon as the worker is dropped.
+    fn watch(&self, _changed: Box<dyn Fn() -> bool + Send>, _stop: UnixStream) {}
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
-pub struct WBarModMask(u32);
-impl WBarModMask {
-    pub const VOL: Self = Self(1 << 0);
-    pub const RAM: Self = Self(1 << 1);
-    pub const CPU: Self = Self(1 << 2);
-    pub const DATE: Self = Self(1 << 3);
-    pub const TIME: Self = Self(1 << 4);
+// waits up to `timeout` for `stop` to become readable, returning whether it did
+fn stopped(stop: &UnixStream, timeout: Duration) -> bool {
+    let mut fd = libc::pollfd {
+        fd: stop.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let deadline = Instant::now() + timeout;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        let ms = left.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+        match unsafe { libc::poll(&mut fd, 1, ms) } {
+            0 => return false,
+            n if n > 0 => return true,
+            _ if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => {}
+            _ => return true,
+        }
+    }
 }
 
-impl std::ops::BitOr for WBarModMask {
-    type Output = WBarModMask;
-    fn bitor(self, rhs: Self) -> Self::Output {
-        Self(self.0 | rhs.0)
-    }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WModuleKind {
+    Music,
+    Vol,
+    Ram,
+    Cpu,
+    Temp,
+    Date,
+    Time,
+    // runs the command and shows the first line it prints, like a dwmblocks block
+    Script(Vec<String>),
 }
 
-impl std::ops::BitAnd for WBarModMask {
-    type Output = bool;
-    fn bitand(self, rhs: Self) -> Self::Output {
-        Self(self.0 & rhs.0) == rhs
+// a module of the status and how often it is updated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WModuleSpec {
+    pub kind: WModuleKind,
+    pub interval: Duration,
+}
+
+impl WModuleSpec {
+    pub const fn new(kind: WModuleKind, interval_ms: u64) -> Self {
+        Self {
+            kind,
+            interval: Duration::from_millis(interval_ms),
+        }
     }
 }
 
+// where the music module gets the song that is playing from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WMusicSource {
+    // any player with an mpris interface, through playerctl
+    Mpris,
+    Mpd,
+}
+
+// settings of the modules that have any
+#[derive(Debug, Clone, PartialEq)]
+pub struct WModuleOptions {
+    // the temperature in celsius from which on it is shown in `warning_color`
+    pub temp_warning: f32,
+    pub warning_color: u32,
+    pub music: WMusicSource,
+    // like "127.0.0.1:6600"
+    pub mpd_address: String,
+    // longer songs are cut off after this many characters
+    pub music_width: usize,
+}
+
 pub struct WBarModule(pub Box<dyn WBarModuleTrait>);
 impl WBarModule {
+    pub fn new(spec: &WModuleSpec, options: WModuleOptions) -> Self {
+        match &spec.kind {
+            WModuleKind::Music => Self::music(options),
+            WModuleKind::Vol => Self::vol(),
+            WModuleKind::Ram => Self::ram(),
+            WModuleKind::Cpu => Self::cpu(),
+            WModuleKind::Temp => Self::temp(options),
+            WModuleKind::Date => Self::date(),
+            WModuleKind::Time => Self::time(),
+            WModuleKind::Script(cmd) => Self::script(cmd.clone(), spec.interval),
+        }
+    }
+
     pub fn vol() -> Self {
-        Self(Box::new(WBarVol))
+        Self(Box::new(WBarVol::default()))
     }
 
     pub fn ram() -> Self {
@@ -62,35 +141,64 @@ impl WBarModule {
     pub fn time() -> Self {
         Self(Box::new(WBarTime("%I:%M %p")))
     }
+
+    pub fn temp(options: WModuleOptions) -> Self {
+        Self(Box::new(WBarTemp(options)))
+    }
+
+    pub fn music(options: WModuleOptions) -> Self {
+        Self(Box::new(WBarMusic(options)))
+    }
+
+    pub fn script(cmd: Vec<String>, interval: Duration) -> Self {
+        Self(Box::new(WBarScript {
+            cmd,
+            interval,
+            text: Arc::default(),
+        }))
+    }
 }
 
 // TODO: more modules
 
-pub struct WBarVol;
+// the volume of the master control, which is updated as soon as it changes
+#[derive(Default)]
+pub struct WBarVol {
+    // opened once there is a mixer to open
+    mixer: RefCell<Option<WMixer>>,
+}
 
 impl WBarModuleTrait for WBarVol {
     fn update(&self) -> String {
-        let amixer = Command::new("amixer")
-            .args(["sget", "Master"])
-            .stdout(Stdio::piped())
-            .spawn()
-            .unwrap();
-        let awk = Command::new("awk")
-            .args(["-F", "[][]", "/Left:/ { print $2 }"])
-            .stdin(Stdio::from(amixer.stdout.unwrap()))
-            .stdout(Stdio::piped())
-            .spawn()
-            .unwrap();
-        let result = if let Ok(output) = awk.wait_with_output() {
-            if output.stdout.is_empty() {
-                " 0%".into()
-            } else {
-                format!("{: >3}", String::from_utf8(output.stdout).unwrap())
+        let mut mixer = self.mixer.borrow_mut();
+        if mixer.is_none() {
+            *mixer = WMixer::open();
+        }
+        match mixer.as_ref().and_then(WMixer::volume) {
+            Some((_, true)) => "vol: muted".into(),
+            Some((percent, false)) => format!("vol: {percent: >2}%"),
+            None => {
+                // the sound server might not be up yet
+                *mixer = None;
+                "vol: N/A".into()
             }
-        } else {
-            "N/A".into()
-        };
-        format!("vol: {result}")
+        }
+    }
+
+    fn watch(&self, changed: Box<dyn Fn() -> bool + Send>, stop: UnixStream) {
+        thread::spawn(move || loop {
+            // waits for the sound server to come up or back
+            if let Some(mixer) = WMixer::open() {
+                while mixer.wait(stop.as_raw_fd()) {
+                    if !changed() {
+                        return;
+                    }
+                }
+            }
+            if stopped(&stop, MIXER_RETRY) || !changed() {
+                return;
+            }
+        });
     }
 }
 
@@ -98,7 +206,7 @@ pub struct WBarRAM;
 
 impl WBarModuleTrait for WBarRAM {
     fn update(&self) -> String {
-        let mut sys = SYS.lock().unwrap();
+        let mut sys = SYS.lock().unwrap_or_else(PoisonError::into_inner);
         sys.refresh_memory();
         let total = sys.total_memory();
         let used = sys.used_memory();
@@ -128,13 +236,179 @@ pub struct WBarCPU;
 
 impl WBarModuleTrait for WBarCPU {
     fn update(&self) -> String {
-        let mut sys = SYS.lock().unwrap();
+        let mut sys = SYS.lock().unwrap_or_else(PoisonError::into_inner);
         sys.refresh_cpu();
         let used = sys.global_cpu_info().cpu_usage() as u8;
         format!("cpu: {used: >2}%")
     }
 }
 
+// the hottest of the hwmon and thermal zone sensors
+pub struct WBarTemp(WModuleOptions);
+
+impl WBarTemp {
+    fn text(&self, celsius: f32) -> String {
+        let text = format!("temp: {celsius:.0}°C");
+        if celsius < self.0.temp_warning {
+            return text;
+        }
+        format!("^c#{:06x}^{text}^d^", self.0.warning_color & 0xffffff)
+    }
+}
+
+impl WBarModuleTrait for WBarTemp {
+    fn update(&self) -> String {
+        let mut sys = SYS.lock().unwrap_or_else(PoisonError::into_inner);
+        sys.refresh_components();
+        let hottest = sys
+            .components()
+            .iter()
+            .map(|c| c.temperature())
+            .filter(|t| t.is_finite())
+            .max_by(f32::total_cmp);
+        match hottest {
+            Some(celsius) => self.text(celsius),
+            None => "temp: N/A".into(),
+        }
+    }
+}
+
+// the artist and title of the song that is playing. clicking it pauses or resumes it.
+pub struct WBarMusic(WModuleOptions);
+
+impl WBarMusic {
+    fn mpris_song() -> Option<WSong> {
+        let output = Command::new("playerctl")
+            .args(["metadata", "--format", "{{status}}\t{{artist}}\t{{title}}"])
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        let output = String::from_utf8_lossy(&output.stdout);
+        let mut fields = output.trim_end().split('\t');
+        let status = fields.next()?;
+        let mut field = || fields.next().filter(|f| !f.is_empty()).map(String::from);
+        let (artist, title) = (field(), field());
+        match status {
+            "Playing" | "Paused" => Some(WSong {
+                artist,
+                title,
+                paused: status == "Paused",
+            }),
+            _ => None,
+        }
+    }
+
+    fn text(&self, song: Option<WSong>) -> String {
+        let Some(song) = song else {
+            return "music: -".into();
+        };
+        let name = match (song.artist, song.title) {
+            (Some(artist), Some(title)) => format!("{artist} - {title}"),
+            (artist, title) => title.or(artist).unwrap_or_else(|| "unknown".into()),
+        };
+        let name = truncate(&name, self.0.music_width);
+        if song.paused {
+            format!("music: {name} (paused)")
+        } else {
+            format!("music: {name}")
+        }
+    }
+}
+
+impl WBarModuleTrait for WBarMusic {
+    fn update(&self) -> String {
+        let song = match self.0.music {
+            WMusicSource::Mpris => Self::mpris_song(),
+            WMusicSource::Mpd => mpd::current_song(&self.0.mpd_address).ok().flatten(),
+        };
+        self.text(song)
+    }
+
+    fn click(&self) {
+        let res = match self.0.music {
+            WMusicSource::Mpris => Command::new("playerctl")
+                .arg("play-pause")
+                .status()
+                .map(|_| ()),
+            WMusicSource::Mpd => mpd::toggle_pause(&self.0.mpd_address),
+        };
+        if let Err(e) = res {
+            eprintln!("ERROR: Failed to pause or resume the music: {e}");
+        }
+    }
+}
+
+// cuts `text` off after `width` characters, ending it with an ellipsis
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+// runs the command on a thread of its own every `interval`, so that a slow one doesn't
+// hold up the other modules, and shows what it printed last
+pub struct WBarScript {
+    cmd: Vec<String>,
+    interval: Duration,
+    text: Arc<Mutex<String>>,
+}
+
+impl WBarModuleTrait for WBarScript {
+    fn update(&self) -> String {
+        self.text
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn watch(&self, changed: Box<dyn Fn() -> bool + Send>, stop: UnixStream) {
+        let (cmd, interval, text) = (self.cmd.clone(), self.interval, self.text.clone());
+        thread::spawn(move || loop {
+            let line = run_script(&cmd, interval);
+            *text.lock().unwrap_or_else(PoisonError::into_inner) = line;
+            if !changed() || stopped(&stop, interval) {
+                return;
+            }
+        });
+    }
+}
+
+// the first line the command prints. nothing is shown while it fails, or when it is
+// still running after `timeout` and gets killed.
+fn run_script(cmd: &[String], timeout: Duration) -> String {
+    let Some((bin, args)) = wwm_core::util::cmd::format(cmd) else {
+        return String::new();
+    };
+    let child = Command::new(bin)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else {
+        return String::new();
+    };
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if Instant::now() < deadline => thread::sleep(SCRIPT_POLL),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return String::new();
+            }
+        }
+    }
+    let mut stdout = String::new();
+    if let Some(mut out) = child.stdout.take() {
+        let _ = out.read_to_string(&mut stdout);
+    }
+    stdout.lines().next().unwrap_or_default().trim().to_string()
+}
+
 pub struct WBarDate(&'static str);
 
 impl WBarModuleTrait for WBarDate {
@@ -152,3 +426,58 @@ impl WBarModuleTrait for WBarTime {
         now.format(self.0).to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> WModuleOptions {
+        WModuleOptions {
+            temp_warning: 80.0,
+            warning_color: 0xe78284,
+            music: WMusicSource::Mpd,
+            mpd_address: "127.0.0.1:6600".into(),
+            music_width: 12,
+        }
+    }
+
+    #[test]
+    fn temperatures_past_the_warning_are_colored() {
+        let temp = WBarTemp(options());
+        assert_eq!(temp.text(54.6), "temp: 55°C");
+        assert_eq!(temp.text(80.0), "^c#e78284^temp: 80°C^d^");
+    }
+
+    #[test]
+    fn long_songs_are_cut_off() {
+        let music = WBarMusic(options());
+        let song = WSong {
+            artist: Some("Sufjan".into()),
+            title: Some("Chicago".into()),
+            paused: true,
+        };
+        assert_eq!(music.text(Some(song)), "music: Sufjan - Ch… (paused)");
+        assert_eq!(truncate("Low - Lazy", 12), "Low - Lazy");
+        assert_eq!(music.text(None), "music: -");
+    }
+
+    fn script(cmd: &[&str], timeout: Duration) -> String {
+        let cmd: Vec<String> = cmd.iter().map(|s| s.to_string()).collect();
+        run_script(&cmd, timeout)
+    }
+
+    #[test]
+    fn scripts_show_their_first_line() {
+        let timeout = Duration::from_secs(5);
+        assert_eq!(script(&["printf", " 21°C \\nrain"], timeout), "21°C");
+        assert_eq!(script(&["false"], timeout), "");
+        assert_eq!(script(&["/nonexistent/script"], timeout), "");
+    }
+
+    #[test]
+    fn hanging_scripts_are_killed() {
+        let start = Instant::now();
+        assert_eq!(script(&["sleep", "5"], Duration::from_millis(200)), "");
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
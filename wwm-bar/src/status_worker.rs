@@ -0,0 +1,324 @@
+use std::{
+    io::{self, Read, Write},
+    mem,
+    os::unix::{
+        io::{AsRawFd, RawFd},
+        net::UnixStream,
+    },
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::status_module::{WBarModule, WModuleKind, WModuleOptions, WModuleSpec};
+
+// where the status text at the right of the bar comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WStatusSource {
+    // the built-in status modules
+    Modules,
+    // the name of the root window, as set by `xsetroot -name` or slstatus
+    RootName,
+}
+
+enum WStatusRequest {
+    // updates every module and sends all of their texts
+    Update,
+    // updates the modules of the kind right away, like the volume after changing it
+    Refresh(WModuleKind),
+    // the module at this index was clicked
+    Click(usize),
+    // the module at this index told that it changed
+    Changed(usize),
+    // nothing is updated while paused
+    Pause(bool),
+    // the worker was dropped
+    Stop,
+}
+
+// a module along with what it showed last and when it is due to be updated again
+struct WModuleState {
+    kind: WModuleKind,
+    module: WBarModule,
+    interval: Duration,
+    due: Instant,
+    text: String,
+}
+
+// updates every module on its own interval
+struct WSchedule {
+    modules: Vec<WModuleState>,
+    paused: bool,
+    // the texts of every module are sent after the next update, not only the changed ones
+    send_all: bool,
+}
+
+impl WSchedule {
+    fn new(specs: &[WModuleSpec], options: WModuleOptions, now: Instant) -> Self {
+        let modules = specs
+            .iter()
+            .map(|spec| WModuleState {
+                kind: spec.kind.clone(),
+                module: WBarModule::new(spec, options.clone()),
+                interval: spec.interval,
+                due: now,
+                text: String::new(),
+            })
+            .collect();
+        Self {
+            modules,
+            paused: false,
+            send_all: true,
+        }
+    }
+
+    fn handle(&mut self, request: WStatusRequest, now: Instant) {
+        match request {
+            WStatusRequest::Update => {
+                self.modules.iter_mut().for_each(|m| m.due = now);
+                self.send_all = true;
+            }
+            WStatusRequest::Refresh(kind) => {
+                for m in self.modules.iter_mut().filter(|m| m.kind == kind) {
+                    m.due = now;
+                }
+            }
+            WStatusRequest::Click(i) => {
+                if let Some(m) = self.modules.get_mut(i) {
+                    m.module.0.click();
+                    m.due = now;
+                }
+            }
+            WStatusRequest::Changed(i) => {
+                if let Some(m) = self.modules.get_mut(i) {
+                    m.due = now;
+                }
+            }
+            WStatusRequest::Pause(paused) => self.paused = paused,
+            WStatusRequest::Stop => {}
+        }
+    }
+
+    // how long until the next module is due, if any is going to be
+    fn timeout(&self, now: Instant) -> Option<Duration> {
+        if self.paused {
+            return None;
+        }
+        self.modules
+            .iter()
+            .map(|m| m.due.saturating_duration_since(now))
+            .min()
+    }
+
+    // updates the modules that are due and returns the index and text of the ones that
+    // changed
+    fn update(&mut self, now: Instant) -> Vec<(usize, String)> {
+        if self.paused {
+            return Vec::new();
+        }
+        let mut changes = Vec::new();
+        for (i, m) in self.modules.iter_mut().enumerate() {
+            if m.due > now {
+                continue;
+            }
+            let text = m.module.0.update();
+            m.due = now + m.interval;
+            if text != m.text {
+                m.text = text;
+                changes.push((i, m.text.clone()));
+            }
+        }
+        if mem::take(&mut self.send_all) {
+            changes = self
+                .modules
+                .iter()
+                .map(|m| m.text.clone())
+                .enumerate()
+                .collect();
+        }
+        changes
+    }
+}
+
+// runs the status modules on a separate thread since some of them spawn
+// processes, which would otherwise stall the event loop while drawing the bar.
+// each module is updated on its own interval, the texts of the ones that changed are
+// sent back and `fd` becomes readable once they are ready.
+pub struct WStatusWorker {
+    requests: Sender<WStatusRequest>,
+    results: Receiver<Vec<(usize, String)>>,
+    waker: UnixStream,
+    // wakes the threads of the modules that watch themselves once the worker is dropped
+    stop: UnixStream,
+    paused: bool,
+}
+
+impl WStatusWorker {
+    pub fn new(specs: &[WModuleSpec], options: WModuleOptions) -> io::Result<Self> {
+        let (requests, request_rx) = mpsc::channel();
+        let (result_tx, results) = mpsc::channel();
+        let (waker, mut wake_tx) = UnixStream::pair()?;
+        waker.set_nonblocking(true)?;
+        let (stop, stopped) = UnixStream::pair()?;
+
+        let mut schedule = WSchedule::new(specs, options, Instant::now());
+        for (i, m) in schedule.modules.iter().enumerate() {
+            let requests: Sender<WStatusRequest> = requests.clone();
+            m.module.0.watch(
+                Box::new(move || requests.send(WStatusRequest::Changed(i)).is_ok()),
+                stopped.try_clone()?,
+            );
+        }
+
+        thread::spawn(move || loop {
+            let request = match schedule.timeout(Instant::now()) {
+                Some(timeout) => request_rx.recv_timeout(timeout),
+                None => request_rx.recv().map_err(RecvTimeoutError::from),
+            };
+            match request {
+                Ok(request) => {
+                    let now = Instant::now();
+                    // requests that piled up while updating are served by a single update
+                    for request in [request].into_iter().chain(request_rx.try_iter()) {
+                        // the modules that watch themselves keep the channel open
+                        if matches!(request, WStatusRequest::Stop) {
+                            return;
+                        }
+                        schedule.handle(request, now);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            let changes = schedule.update(Instant::now());
+            if changes.is_empty() {
+                continue;
+            }
+            if result_tx.send(changes).is_err() || wake_tx.write_all(&[0]).is_err() {
+                break;
+            }
+        });
+
+        Ok(Self {
+            requests,
+            results,
+            waker,
+            stop,
+            paused: false,
+        })
+    }
+
+    pub fn fd(&self) -> RawFd {
+        self.waker.as_raw_fd()
+    }
+
+    // asks the worker for a fresh status text
+    pub fn request(&self) {
+        let _ = self.requests.send(WStatusRequest::Update);
+    }
+
+    pub fn refresh(&self, kind: WModuleKind) {
+        let _ = self.requests.send(WStatusRequest::Refresh(kind));
+    }
+
+    // lets the module at `idx` know it was clicked, after which the status is updated
+    pub fn click(&self, idx: usize) {
+        let _ = self.requests.send(WStatusRequest::Click(idx));
+    }
+
+    // stops updating the modules while nobody would see them
+    pub fn set_paused(&mut self, paused: bool) {
+        if paused != self.paused {
+            self.paused = paused;
+            let _ = self.requests.send(WStatusRequest::Pause(paused));
+        }
+    }
+
+    // returns the index and new text of the modules that changed, in the order they did
+    pub fn take_result(&mut self) -> Option<Vec<(usize, String)>> {
+        let mut buf = [0; 64];
+        while matches!(self.waker.read(&mut buf), Ok(n) if n > 0) {}
+
+        let changes: Vec<_> = self.results.try_iter().flatten().collect();
+        (!changes.is_empty()).then_some(changes)
+    }
+}
+
+impl Drop for WStatusWorker {
+    fn drop(&mut self) {
+        let _ = self.requests.send(WStatusRequest::Stop);
+        let _ = self.stop.write_all(&[0]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status_module::{WBarModuleTrait, WMusicSource};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    // counts its updates, showing the count divided by `step`
+    struct WCounter(Arc<AtomicUsize>, usize);
+
+    impl WBarModuleTrait for WCounter {
+        fn update(&self) -> String {
+            (self.0.fetch_add(1, Ordering::Relaxed) / self.1).to_string()
+        }
+    }
+
+    fn schedule(modules: &[(u64, usize)], now: Instant) -> (WSchedule, Vec<Arc<AtomicUsize>>) {
+        let options = WModuleOptions {
+            temp_warning: 80.0,
+            warning_color: 0,
+            music: WMusicSource::Mpris,
+            mpd_address: String::new(),
+            music_width: 0,
+        };
+        let specs: Vec<_> = modules
+            .iter()
+            .map(|(ms, _)| WModuleSpec::new(WModuleKind::Time, *ms))
+            .collect();
+        let mut schedule = WSchedule::new(&specs, options, now);
+        let counts = modules
+            .iter()
+            .zip(&mut schedule.modules)
+            .map(|((_, step), m)| {
+                let count = Arc::new(AtomicUsize::new(0));
+                m.module = WBarModule(Box::new(WCounter(count.clone(), *step)));
+                count
+            })
+            .collect();
+        (schedule, counts)
+    }
+
+    #[test]
+    fn modules_update_on_their_own_interval() {
+        let start = Instant::now();
+        let (mut schedule, counts) = schedule(&[(1000, 1), (5000, 1)], start);
+        assert_eq!(schedule.update(start).len(), 2);
+        assert_eq!(schedule.timeout(start), Some(Duration::from_secs(1)));
+
+        let later = start + Duration::from_secs(3);
+        assert_eq!(schedule.update(later), [(0, "1".to_string())]);
+        assert_eq!(counts[0].load(Ordering::Relaxed), 2);
+        assert_eq!(counts[1].load(Ordering::Relaxed), 1);
+
+        schedule.handle(WStatusRequest::Pause(true), later);
+        assert_eq!(schedule.timeout(later), None);
+    }
+
+    #[test]
+    fn only_changed_texts_are_sent() {
+        let start = Instant::now();
+        let (mut schedule, _) = schedule(&[(1000, 2)], start);
+        assert_eq!(schedule.update(start), [(0, "0".to_string())]);
+        let later = start + Duration::from_secs(1);
+        assert!(schedule.update(later).is_empty());
+        schedule.handle(WStatusRequest::Update, later);
+        assert_eq!(schedule.update(later), [(0, "1".to_string())]);
+    }
+}
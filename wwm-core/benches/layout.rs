@@ -0,0 +1,44 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use wwm_core::{
+    layout::{layout_clients, WGaps},
+    util::{primitives::WRect, WLayout},
+};
+
+const AREA: WRect = WRect {
+    x: 0,
+    y: 21,
+    w: 2560,
+    h: 1419,
+};
+
+fn bench_layouts(c: &mut Criterion) {
+    let factors = vec![1.0; 500];
+    for layout in [
+        WLayout::MainStack,
+        WLayout::BottomStack,
+        WLayout::Column,
+        WLayout::Tabbed,
+        WLayout::Floating,
+    ] {
+        let mut group = c.benchmark_group(format!("layout_clients/{layout:?}"));
+        for clients in [1, 10, 100, 500] {
+            group.bench_with_input(BenchmarkId::from_parameter(clients), &clients, |b, n| {
+                b.iter(|| {
+                    layout_clients(
+                        &layout,
+                        black_box(AREA),
+                        0.55,
+                        1,
+                        WGaps::default(),
+                        1,
+                        black_box(&factors[..*n]),
+                    )
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_layouts);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use wwm_core::{
+    text::{TextDrawOptions, TextRenderer},
+    util::{color::hex_to_rgba, primitives::WRect},
+};
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        render::{ConnectionExt as _, CreatePictureAux},
+        xproto::ConnectionExt,
+    },
+    wrapper::ConnectionExt as _,
+};
+
+const TITLE: &str =
+    "nvim ~/dev/wwm/wwm-core/src/text.rs - the quick brown fox jumps over the lazy dog";
+const RECT: WRect = WRect {
+    x: 0,
+    y: 0,
+    w: 400,
+    h: 21,
+};
+
+// drawing needs a live x server, so these only run when one can be reached through $DISPLAY
+fn bench_text(c: &mut Criterion) {
+    let Ok((conn, screen_num)) = x11rb::connect(None) else {
+        eprintln!("skipping text benchmarks, could not connect to an x server");
+        return;
+    };
+    let screen = &conn.setup().roots[screen_num];
+    let renderer = TextRenderer::new(&conn, screen, "", 15.0, 1.0, &[], Default::default())
+        .expect("failed to load font");
+
+    c.bench_function("encode", |b| {
+        b.iter(|| renderer.encode(black_box(TITLE), RECT.w as i16))
+    });
+
+    c.bench_function("encode_ellipsized", |b| {
+        b.iter(|| renderer.encode_ellipsized(black_box(TITLE), RECT.w as i16 / 4))
+    });
+
+    // a pixmap stands in for the bar's back buffer
+    let pixmap = conn.generate_id().unwrap();
+    conn.create_pixmap(screen.root_depth, pixmap, screen.root, RECT.w, RECT.h)
+        .unwrap();
+    let picture = conn.generate_id().unwrap();
+    conn.render_create_picture(
+        picture,
+        pixmap,
+        renderer.visual_info.root.pict_format,
+        &CreatePictureAux::new(),
+    )
+    .unwrap();
+
+    let (bg, fg) = (hex_to_rgba(0x303446), hex_to_rgba(0xc6d0f5));
+    let draw = |text: &str| {
+        renderer
+            .draw(
+                RECT,
+                &[(text, fg)],
+                3,
+                picture,
+                screen.root,
+                TextDrawOptions {
+                    bg,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        // wait for the server so the rendering itself is measured too
+        conn.sync().unwrap();
+    };
+
+    c.bench_function("draw/cached", |b| b.iter(|| draw(TITLE)));
+
+    // a new string on every iteration misses the cache each time
+    let mut n = 0u64;
+    c.bench_function("draw/uncached", |b| {
+        b.iter(|| {
+            n += 1;
+            draw(&format!("{TITLE} {n}"))
+        })
+    });
+}
+
+criterion_group!(benches, bench_text);
+criterion_main!(benches);
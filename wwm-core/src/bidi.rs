@@ -0,0 +1,212 @@
+use std::borrow::Cow;
+
+// a pared down version of the unicode bidirectional algorithm, enough to show window
+// titles in hebrew or arabic the right way around. there are no explicit embeddings
+// and arabic is not shaped, the font's isolated forms are drawn as they are.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    L,
+    R,
+    Number,
+    Neutral,
+}
+
+fn is_rtl(c: char) -> bool {
+    matches!(c as u32,
+        0x0590..=0x08ff | 0xfb1d..=0xfdff | 0xfe70..=0xfeff | 0x10800..=0x10fff | 0x1e800..=0x1efff)
+}
+
+// combining marks stay behind the character they belong to when a run is reversed
+fn is_mark(c: char) -> bool {
+    matches!(c as u32,
+        0x0300..=0x036f
+            | 0x0591..=0x05bd
+            | 0x05bf
+            | 0x05c1..=0x05c2
+            | 0x05c4..=0x05c5
+            | 0x05c7
+            | 0x0610..=0x061a
+            | 0x064b..=0x065f
+            | 0x0670
+            | 0x06d6..=0x06dc
+            | 0x06df..=0x06e4
+            | 0x06e7..=0x06e8
+            | 0x06ea..=0x06ed)
+}
+
+fn class(c: char) -> Class {
+    if c.is_ascii_digit() || matches!(c as u32, 0x0660..=0x0669 | 0x06f0..=0x06f9) {
+        Class::Number
+    } else if is_rtl(c) {
+        Class::R
+    } else if c.is_alphanumeric() {
+        Class::L
+    } else {
+        Class::Neutral
+    }
+}
+
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        _ => c,
+    }
+}
+
+// reorders the text from the order it is written in to the order it is drawn in
+pub fn visual_order(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_rtl) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut clusters: Vec<&str> = vec![];
+    let mut start = 0;
+    for (i, c) in text.char_indices().skip(1) {
+        if !is_mark(c) {
+            clusters.push(&text[start..i]);
+            start = i;
+        }
+    }
+    clusters.push(&text[start..]);
+
+    let mut classes: Vec<Class> = clusters
+        .iter()
+        .map(|c| class(c.chars().next().unwrap_or(' ')))
+        .collect();
+
+    // the first strong character decides the direction of the whole text
+    let rtl = classes.iter().find(|c| matches!(c, Class::L | Class::R)) == Some(&Class::R);
+    let base = if rtl { Class::R } else { Class::L };
+
+    // numbers that follow left to right text are part of it
+    let mut last_strong = base;
+    for class in classes.iter_mut() {
+        match *class {
+            Class::L | Class::R => last_strong = *class,
+            Class::Number if last_strong == Class::L => *class = Class::L,
+            _ => {}
+        }
+    }
+
+    // neutrals between text of the same direction take that direction and the base otherwise.
+    // numbers count as right to left here.
+    let strong = |c: Class| match c {
+        Class::L => Some(Class::L),
+        Class::R | Class::Number => Some(Class::R),
+        Class::Neutral => None,
+    };
+    let mut i = 0;
+    while i < classes.len() {
+        if classes[i] != Class::Neutral {
+            i += 1;
+            continue;
+        }
+        let end = (i..classes.len())
+            .find(|j| classes[*j] != Class::Neutral)
+            .unwrap_or(classes.len());
+        let before = i.checked_sub(1).and_then(|j| strong(classes[j]));
+        let after = classes.get(end).and_then(|c| strong(*c));
+        let resolved = match (before.unwrap_or(base), after.unwrap_or(base)) {
+            (b, a) if b == a => b,
+            _ => base,
+        };
+        // trailing neutrals always go with the base direction
+        let resolved = if end == classes.len() { base } else { resolved };
+        classes[i..end].fill(resolved);
+        i = end;
+    }
+
+    // every neutral has been resolved by now
+    let levels: Vec<u8> = classes
+        .iter()
+        .map(|c| match (c, rtl) {
+            (Class::L, false) => 0,
+            (Class::R, _) => 1,
+            _ => 2,
+        })
+        .collect();
+
+    // from the highest level down, every run at that level or above is reversed
+    let max = levels.iter().copied().max().unwrap_or(0);
+    let mut order: Vec<usize> = (0..clusters.len()).collect();
+    for level in (1..=max).rev() {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] < level {
+                i += 1;
+                continue;
+            }
+            let end = (i..order.len())
+                .find(|j| levels[order[*j]] < level)
+                .unwrap_or(order.len());
+            order[i..end].reverse();
+            i = end;
+        }
+    }
+
+    let mut visual = String::with_capacity(text.len());
+    for i in order {
+        if levels[i] % 2 == 1 {
+            visual.extend(clusters[i].chars().map(mirror));
+        } else {
+            visual.push_str(clusters[i]);
+        }
+    }
+    Cow::Owned(visual)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn left_to_right_text_is_untouched() {
+        assert!(matches!(
+            visual_order("hello (1)"),
+            Cow::Borrowed("hello (1)")
+        ));
+    }
+
+    #[test]
+    fn right_to_left_text_is_reversed() {
+        assert_eq!(visual_order("שלום עולם"), "םלוע םולש");
+    }
+
+    #[test]
+    fn numbers_keep_their_order() {
+        assert_eq!(visual_order("שלום 123"), "123 םולש");
+    }
+
+    #[test]
+    fn right_to_left_run_in_left_to_right_text() {
+        assert_eq!(visual_order("firefox - שלום עולם"), "firefox - םלוע םולש");
+    }
+
+    #[test]
+    fn left_to_right_run_in_right_to_left_text() {
+        assert_eq!(visual_order("שלום abc עולם"), "םלוע abc םולש");
+    }
+
+    #[test]
+    fn brackets_are_mirrored() {
+        assert_eq!(visual_order("שלום (עולם)"), "(םלוע) םולש");
+    }
+
+    #[test]
+    fn marks_stay_behind_their_character() {
+        // shin with qamats and shin dot, lamed, vav with holam, final mem
+        let text = "\u{5e9}\u{5b8}\u{5c1}\u{5dc}\u{5d5}\u{5b9}\u{5dd}";
+        let visual = "\u{5dd}\u{5d5}\u{5b9}\u{5dc}\u{5e9}\u{5b8}\u{5c1}";
+        assert_eq!(visual_order(text), visual);
+    }
+}
@@ -0,0 +1,126 @@
+use png::{ColorType, Decoder, Transformations};
+use ttf_parser::Face;
+
+// a font that stores its glyphs as png images in CBDT or sbix tables, which is how
+// color emoji fonts work. fontdue only rasterizes outlines, so these are decoded here.
+pub struct ColorFont {
+    data: Vec<u8>,
+}
+
+pub struct ColorGlyph {
+    pub width: u16,
+    pub height: u16,
+    // offset of the left edge from the pen position
+    pub left: i16,
+    // distance from the top edge to the baseline
+    pub top: i16,
+    pub advance: i16,
+    // premultiplied rgba
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl ColorFont {
+    // hands the data back if the font has no bitmap glyphs
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, Vec<u8>> {
+        let has_bitmaps = Face::from_slice(&data, 0)
+            .map(|face| face.tables().cbdt.is_some() || face.tables().sbix.is_some())
+            .unwrap_or(false);
+        if has_bitmaps {
+            Ok(Self { data })
+        } else {
+            Err(data)
+        }
+    }
+
+    pub fn rasterize(&self, c: char, size: f32) -> Option<ColorGlyph> {
+        // the face borrows the data, so it is parsed again for every glyph.
+        // this only happens once per character.
+        let face = Face::from_slice(&self.data, 0).ok()?;
+        let id = face.glyph_index(c)?;
+        let image = face.glyph_raster_image(id, size.round() as u16)?;
+        let (w, h, pixels) = decode_png(image.data)?;
+
+        // strikes only come in a few sizes, so the closest one is scaled to the font size
+        let scale = size / image.pixels_per_em.max(1) as f32;
+        let (width, height, pixels) = downscale(w, h, &pixels, scale);
+
+        // `y` is the offset of the bottom edge from the baseline
+        let top = ((image.y as f32 + h as f32) * scale).round() as i16;
+        let advance = face
+            .glyph_hor_advance(id)
+            .map(|a| (a as f32 * size / face.units_per_em() as f32).round() as i16)
+            .unwrap_or(width as i16);
+
+        Some(ColorGlyph {
+            width,
+            height,
+            left: (image.x as f32 * scale).round() as i16,
+            top,
+            advance,
+            pixels,
+        })
+    }
+}
+
+// decodes the image into premultiplied rgba
+fn decode_png(data: &[u8]) -> Option<(usize, usize, Vec<[u8; 4]>)> {
+    let mut decoder = Decoder::new(data);
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).ok()?;
+    let buf = &buf[..info.buffer_size()];
+
+    let rgba: Vec<[u8; 4]> = match info.color_type {
+        ColorType::Rgba => buf.chunks(4).map(|p| [p[0], p[1], p[2], p[3]]).collect(),
+        ColorType::Rgb => buf.chunks(3).map(|p| [p[0], p[1], p[2], 255]).collect(),
+        ColorType::GrayscaleAlpha => buf.chunks(2).map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        ColorType::Grayscale => buf.iter().map(|p| [*p, *p, *p, 255]).collect(),
+        // expanded by the transformations
+        ColorType::Indexed => return None,
+    };
+
+    let premultiplied = rgba
+        .into_iter()
+        .map(|[r, g, b, a]| {
+            let mul = |c: u8| (c as u16 * a as u16 / 255) as u8;
+            [mul(r), mul(g), mul(b), a]
+        })
+        .collect();
+    Some((info.width as usize, info.height as usize, premultiplied))
+}
+
+// averages the source pixels that end up in each pixel of the scaled image
+pub(crate) fn downscale(
+    w: usize,
+    h: usize,
+    pixels: &[[u8; 4]],
+    scale: f32,
+) -> (u16, u16, Vec<[u8; 4]>) {
+    let sw = ((w as f32 * scale).ceil() as usize).max(1);
+    let sh = ((h as f32 * scale).ceil() as usize).max(1);
+    let span = |o: usize, len: usize| {
+        let start = ((o as f32 / scale) as usize).min(len - 1);
+        let end = (((o + 1) as f32 / scale) as usize).clamp(start + 1, len);
+        start..end
+    };
+
+    let mut scaled = Vec::with_capacity(sw * sh);
+    for oy in 0..sh {
+        let rows = span(oy, h);
+        for ox in 0..sw {
+            let cols = span(ox, w);
+            let mut sum = [0u32; 4];
+            for y in rows.clone() {
+                for p in &pixels[y * w + cols.start..y * w + cols.end] {
+                    for (s, c) in sum.iter_mut().zip(p) {
+                        *s += *c as u32;
+                    }
+                }
+            }
+            let n = (rows.len() * cols.len()) as u32;
+            scaled.push(sum.map(|s| (s / n) as u8));
+        }
+    }
+    (sw as u16, sh as u16, scaled)
+}
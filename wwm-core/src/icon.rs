@@ -0,0 +1,194 @@
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        render::{ConnectionExt as _, CreatePictureAux, PictOp, Picture},
+        xproto::{ConnectionExt, CreateGCAux, ImageFormat, Pixmap},
+    },
+    rust_connection::{ConnectionError, ReplyOrIdError},
+    NONE,
+};
+
+use crate::{color_font::downscale, visual::RenderVisualInfo};
+
+// an icon scaled to fit in a square, like the bar's title section
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct WIcon {
+    pub width: u16,
+    pub height: u16,
+    // premultiplied rgba
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl WIcon {
+    // picks the image of _NET_WM_ICON that is closest to `size` and scales it to fit.
+    // the property holds any number of images, each as its width and height followed
+    // by its argb pixels row by row.
+    pub fn from_net_wm_icon(data: &[u32], size: u16) -> Option<Self> {
+        let mut images = vec![];
+        let mut rest = data;
+        while let [w, h, tail @ ..] = rest {
+            let (w, h) = (*w as usize, *h as usize);
+            let Some(len) = w
+                .checked_mul(h)
+                .filter(|len| *len > 0 && *len <= tail.len())
+            else {
+                break;
+            };
+            images.push((w, h, &tail[..len]));
+            rest = &tail[len..];
+        }
+
+        // the smallest one that doesn't have to be scaled up, or else the largest one
+        let side = |(w, h, _): &&(usize, usize, &[u32])| *w.max(h);
+        let (w, h, argb) = images
+            .iter()
+            .filter(|i| side(i) >= size as usize)
+            .min_by_key(side)
+            .or_else(|| images.iter().max_by_key(side))?;
+
+        let pixels: Vec<[u8; 4]> = argb
+            .iter()
+            .map(|p| {
+                let [a, r, g, b] = p.to_be_bytes();
+                let mul = |c: u8| (c as u16 * a as u16 / 255) as u8;
+                [mul(r), mul(g), mul(b), a]
+            })
+            .collect();
+        Some(Self::scaled(*w, *h, &pixels, size))
+    }
+
+    pub fn resized(&self, size: u16) -> Self {
+        if self.width.max(self.height) == size {
+            return self.clone();
+        }
+        Self::scaled(
+            self.width as usize,
+            self.height as usize,
+            &self.pixels,
+            size,
+        )
+    }
+
+    fn scaled(w: usize, h: usize, pixels: &[[u8; 4]], size: u16) -> Self {
+        let scale = size as f32 / w.max(h) as f32;
+        let (width, height, pixels) = downscale(w, h, pixels, scale);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+// an icon uploaded to the server, ready to be composited onto any picture
+pub struct WIconPicture {
+    pixmap: Pixmap,
+    picture: Picture,
+    width: u16,
+    height: u16,
+}
+
+impl WIconPicture {
+    pub fn new<C: Connection>(
+        conn: &C,
+        visual_info: &RenderVisualInfo,
+        icon: &WIcon,
+    ) -> Result<Self, ReplyOrIdError> {
+        let depth = visual_info.render.depth;
+        let pixmap = conn.generate_id()?;
+        conn.create_pixmap(
+            depth,
+            pixmap,
+            visual_info.screen_root,
+            icon.width,
+            icon.height,
+        )?;
+
+        let gc = conn.generate_id()?;
+        conn.create_gc(gc, pixmap, &CreateGCAux::new())?;
+        let argb: Vec<u8> = icon
+            .pixels
+            .iter()
+            .flat_map(|[r, g, b, a]| u32::from_be_bytes([*a, *r, *g, *b]).to_ne_bytes())
+            .collect();
+        conn.put_image(
+            ImageFormat::Z_PIXMAP,
+            pixmap,
+            gc,
+            icon.width,
+            icon.height,
+            0,
+            0,
+            0,
+            depth,
+            &argb,
+        )?;
+        conn.free_gc(gc)?;
+
+        let picture = conn.generate_id()?;
+        conn.render_create_picture(
+            picture,
+            pixmap,
+            visual_info.render.pict_format,
+            &CreatePictureAux::new(),
+        )?;
+        Ok(Self {
+            pixmap,
+            picture,
+            width: icon.width,
+            height: icon.height,
+        })
+    }
+
+    // draws the icon centered in a `size` by `size` square at `x` and `y`
+    pub fn draw<C: Connection>(
+        &self,
+        conn: &C,
+        dst: Picture,
+        x: i16,
+        y: i16,
+        size: u16,
+    ) -> Result<(), ConnectionError> {
+        conn.render_composite(
+            PictOp::OVER,
+            self.picture,
+            NONE,
+            dst,
+            0,
+            0,
+            0,
+            0,
+            x + (size.saturating_sub(self.width) / 2) as i16,
+            y + (size.saturating_sub(self.height) / 2) as i16,
+            self.width,
+            self.height,
+        )?;
+        Ok(())
+    }
+
+    pub fn free<C: Connection>(&self, conn: &C) -> Result<(), ConnectionError> {
+        conn.render_free_picture(self.picture)?;
+        conn.free_pixmap(self.pixmap)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_closest_image_that_is_large_enough() {
+        let mut data = vec![2, 2];
+        data.extend([0xff00_00ff; 4]);
+        data.extend([4, 2]);
+        data.extend([0x8000_ff00; 8]);
+
+        let icon = WIcon::from_net_wm_icon(&data, 3).unwrap();
+        assert_eq!((icon.width, icon.height), (3, 2));
+        assert_eq!(icon.pixels[0], [0, 0x80, 0, 0x80]);
+
+        // truncated images are ignored along with everything after them
+        assert_eq!(WIcon::from_net_wm_icon(&[16, 16, 0], 3), None);
+    }
+}
@@ -0,0 +1,354 @@
+use crate::util::{primitives::WRect, WLayout};
+
+// space left empty around tiled clients, in pixels
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WGaps {
+    // between clients
+    pub inner: u16,
+    // between clients and the edges of the area
+    pub outer: u16,
+}
+
+// the rects of the tiled clients within `area`, accounting for their borders. `factors`
+// holds the size factor of each client, which in the main-stack layouts decides how
+// much of its side it gets compared to the others there and in the column layout how
+// much of the width.
+// `main_count` is how many clients share the main area of the main-stack layouts.
+// in the bottom-stack layout `width_factor` is the share of the height instead.
+// there are no rects in the floating layout, where clients keep their own geometry.
+pub fn layout_clients(
+    layout: &WLayout,
+    area: WRect,
+    width_factor: f32,
+    main_count: usize,
+    gaps: WGaps,
+    border_width: u16,
+    factors: &[f32],
+) -> Option<Vec<WRect>> {
+    let clients = factors.len();
+    if clients == 0 || *layout == WLayout::Floating {
+        return None;
+    }
+
+    let gaps = fit_gaps(gaps, area, clients, border_width);
+    let o = gaps.outer;
+    let area = WRect::new(
+        area.x + o as i16,
+        area.y + o as i16,
+        area.w.saturating_sub(o * 2),
+        area.h.saturating_sub(o * 2),
+    );
+
+    let mut rects = match layout {
+        WLayout::MainStack => tile(area, width_factor, main_count, border_width, factors),
+        WLayout::BottomStack => bstack(area, width_factor, main_count, border_width, factors),
+        WLayout::Column => columns(area, border_width, factors),
+        WLayout::Tabbed => vec![single_client(area, border_width)[0]; clients],
+        WLayout::Floating => vec![],
+    };
+    for rect in rects.iter_mut() {
+        inset(rect, area, gaps.inner, border_width);
+    }
+
+    Some(rects)
+}
+
+// the gaps shrunk as far as needed for `clients` side by side along the shorter side of
+// `area` to still get a pixel each besides their borders, which any layout fits into
+pub fn fit_gaps(gaps: WGaps, area: WRect, clients: usize, bw: u16) -> WGaps {
+    let clients = clients.max(1) as u32;
+    let free = (area.w.min(area.h) as u32).saturating_sub(clients * (bw as u32 * 2 + 1));
+    if gaps.outer as u32 * 2 + gaps.inner as u32 * (clients - 1) <= free {
+        return gaps;
+    }
+    let max = (free / (clients + 1)) as u16;
+    WGaps {
+        inner: gaps.inner.min(max),
+        outer: gaps.outer.min(max),
+    }
+}
+
+// moves the sides of `rect` that face another client in by half of `gap` each,
+// which leaves the whole gap between neighbours and the edges of `area` untouched
+fn inset(rect: &mut WRect, area: WRect, gap: u16, bw: u16) {
+    let right = rect.x + (rect.w + bw * 2) as i16;
+    let bottom = rect.y + (rect.h + bw * 2) as i16;
+    let (near, far) = (gap - gap / 2, gap / 2);
+
+    if rect.x > area.x {
+        rect.x += near as i16;
+        rect.w = rect.w.saturating_sub(near);
+    }
+    if right < area.x + area.w as i16 {
+        rect.w = rect.w.saturating_sub(far);
+    }
+    if rect.y > area.y {
+        rect.y += near as i16;
+        rect.h = rect.h.saturating_sub(near);
+    }
+    if bottom < area.y + area.h as i16 {
+        rect.h = rect.h.saturating_sub(far);
+    }
+}
+
+fn tile(area: WRect, width_factor: f32, main_count: usize, bw: u16, factors: &[f32]) -> Vec<WRect> {
+    let main_count = main_count.min(factors.len());
+    let stack_count = factors.len() - main_count;
+
+    // either side takes the whole width when the other one is empty
+    let main_width = match (main_count, stack_count) {
+        (0, _) => 0,
+        (_, 0) => area.w,
+        _ => (area.w as f32 * width_factor) as u16,
+    };
+
+    let main = WRect::new(area.x, area.y, main_width, area.h);
+    let stack = WRect::new(
+        area.x + main_width as i16,
+        area.y,
+        area.w - main_width,
+        area.h,
+    );
+
+    let (main_factors, stack_factors) = factors.split_at(main_count);
+    let mut rects = rows(main, bw, main_factors);
+    rects.extend(rows(stack, bw, stack_factors));
+    rects
+}
+
+fn bstack(
+    area: WRect,
+    height_factor: f32,
+    main_count: usize,
+    bw: u16,
+    factors: &[f32],
+) -> Vec<WRect> {
+    let main_count = main_count.min(factors.len());
+    let stack_count = factors.len() - main_count;
+
+    // either side takes the whole height when the other one is empty
+    let main_height = match (main_count, stack_count) {
+        (0, _) => 0,
+        (_, 0) => area.h,
+        _ => (area.h as f32 * height_factor) as u16,
+    };
+
+    let main = WRect::new(area.x, area.y, area.w, main_height);
+    let stack = WRect::new(
+        area.x,
+        area.y + main_height as i16,
+        area.w,
+        area.h - main_height,
+    );
+
+    let (main_factors, stack_factors) = factors.split_at(main_count);
+    let mut rects = columns(main, bw, main_factors);
+    rects.extend(columns(stack, bw, stack_factors));
+    rects
+}
+
+// splits `area` into a column per factor, each as wide as its share of all of them
+fn columns(area: WRect, bw: u16, factors: &[f32]) -> Vec<WRect> {
+    split(area.w, factors)
+        .into_iter()
+        .map(|(x, w)| {
+            let (w, h) = (w.saturating_sub(bw * 2), area.h.saturating_sub(bw * 2));
+            WRect::new(area.x + x as i16, area.y, w, h)
+        })
+        .collect()
+}
+
+// splits `area` into a row per factor, each as high as its share of all of them
+fn rows(area: WRect, bw: u16, factors: &[f32]) -> Vec<WRect> {
+    split(area.h, factors)
+        .into_iter()
+        .map(|(y, h)| {
+            let (w, h) = (area.w.saturating_sub(bw * 2), h.saturating_sub(bw * 2));
+            WRect::new(area.x, area.y + y as i16, w, h)
+        })
+        .collect()
+}
+
+// the offset and length of each factor's share of `len`
+fn split(len: u16, factors: &[f32]) -> Vec<(u16, u16)> {
+    let count = factors.len();
+    let total: f32 = factors.iter().sum();
+    let mut offset = 0;
+
+    (0..count)
+        .map(|i| {
+            let share = if total > 0.0 {
+                factors[i] / total
+            } else {
+                1.0 / count as f32
+            };
+            // the last one takes up whatever is left after rounding
+            let size = if i == count - 1 {
+                len - offset
+            } else {
+                (len as f32 * share) as u16
+            };
+            let part = (offset, size);
+            offset += size;
+            part
+        })
+        .collect()
+}
+
+// the narrowest a column can get through `resize_column`, as a share of the width
+const MIN_COLUMN_SHARE: f32 = 0.05;
+
+// grows column `idx` by `delta` of the total width and shrinks its neighbours by as
+// much, which leaves the other columns alone. a negative `delta` does the opposite.
+// nothing changes when a column would get narrower than `MIN_COLUMN_SHARE`.
+pub fn resize_column(weights: &mut [f32], idx: usize, delta: f32) {
+    let neighbours: Vec<usize> = [idx.checked_sub(1), Some(idx + 1)]
+        .into_iter()
+        .flatten()
+        .filter(|i| *i < weights.len())
+        .collect();
+    if neighbours.is_empty() {
+        return;
+    }
+
+    let total: f32 = weights.iter().sum();
+    let mut resized = weights.to_vec();
+    resized[idx] += delta * total;
+    for i in &neighbours {
+        resized[*i] -= delta * total / neighbours.len() as f32;
+    }
+    if resized.iter().all(|w| w / total >= MIN_COLUMN_SHARE) {
+        weights.copy_from_slice(&resized);
+    }
+}
+
+fn single_client(area: WRect, bw: u16) -> Vec<WRect> {
+    let (w, h) = (area.w.saturating_sub(bw * 2), area.h.saturating_sub(bw * 2));
+    vec![WRect::new(area.x, area.y, w, h)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: WRect = WRect {
+        x: 0,
+        y: 20,
+        w: 1000,
+        h: 600,
+    };
+
+    fn dims(rects: &[WRect]) -> Vec<(i16, i16, u16, u16)> {
+        rects.iter().map(|r| (r.x, r.y, r.w, r.h)).collect()
+    }
+
+    #[test]
+    fn tile_stacks_the_main_clients() {
+        let rects = tile(AREA, 0.5, 2, 1, &[1.0; 3]);
+        assert_eq!(
+            dims(&rects),
+            vec![(0, 20, 498, 298), (0, 320, 498, 298), (500, 20, 498, 598)]
+        );
+    }
+
+    #[test]
+    fn tile_shares_the_height_by_factor() {
+        let rects = tile(AREA, 0.5, 1, 1, &[1.0, 1.0, 3.0]);
+        assert_eq!(
+            dims(&rects),
+            vec![(0, 20, 498, 598), (500, 20, 498, 148), (500, 170, 498, 448)]
+        );
+    }
+
+    #[test]
+    fn bstack_puts_the_stack_below_the_main_clients() {
+        let rects = bstack(AREA, 0.5, 1, 1, &[1.0; 3]);
+        assert_eq!(
+            dims(&rects),
+            vec![(0, 20, 998, 298), (0, 320, 498, 298), (500, 320, 498, 298)]
+        );
+    }
+
+    #[test]
+    fn resize_column_takes_from_the_neighbours() {
+        let mut weights = [1.0; 4];
+        resize_column(&mut weights, 1, 0.125);
+        assert_eq!(weights, [0.75, 1.5, 0.75, 1.0]);
+        resize_column(&mut weights, 3, 0.125);
+        assert_eq!(weights, [0.75, 1.5, 0.25, 1.5]);
+
+        // the first column can't get any narrower
+        let mut weights = [0.25, 1.75];
+        resize_column(&mut weights, 0, -0.125);
+        assert_eq!(weights, [0.25, 1.75]);
+    }
+
+    #[test]
+    fn columns_share_the_width_by_weight() {
+        let rects = layout_clients(
+            &WLayout::Column,
+            AREA,
+            0.5,
+            1,
+            WGaps::default(),
+            1,
+            &[1.0, 3.0],
+        )
+        .unwrap();
+        assert_eq!(dims(&rects), vec![(0, 20, 248, 598), (250, 20, 748, 598)]);
+    }
+
+    #[test]
+    fn floating_layout_leaves_clients_alone() {
+        let rects = layout_clients(
+            &WLayout::Floating,
+            AREA,
+            0.5,
+            1,
+            WGaps::default(),
+            1,
+            &[1.0],
+        );
+        assert!(rects.is_none());
+    }
+
+    #[test]
+    fn gaps_are_kept_between_clients_and_to_the_edges() {
+        let gaps = WGaps {
+            inner: 10,
+            outer: 20,
+        };
+        let rects = layout_clients(&WLayout::Column, AREA, 0.5, 1, gaps, 1, &[1.0; 2]).unwrap();
+        assert_eq!(dims(&rects), vec![(20, 40, 473, 558), (505, 40, 473, 558)]);
+    }
+
+    #[test]
+    fn gaps_shrink_to_leave_room_for_every_client() {
+        let gaps = WGaps {
+            inner: 10,
+            outer: 20,
+        };
+        assert_eq!(fit_gaps(gaps, AREA, 4, 1), gaps);
+        let huge = WGaps {
+            inner: 400,
+            outer: 40_000,
+        };
+        // 600 - 4 * 3 pixels are left for the gaps
+        let fitted = fit_gaps(huge, AREA, 4, 1);
+        assert_eq!((fitted.inner, fitted.outer), (117, 117));
+        let rects = layout_clients(&WLayout::MainStack, AREA, 0.5, 1, huge, 1, &[1.0; 4]).unwrap();
+        assert!(rects.iter().all(|r| r.w > 0 && r.h > 0));
+    }
+
+    #[test]
+    fn tile_without_a_stack_or_main_area_takes_the_whole_width() {
+        assert_eq!(
+            dims(&tile(AREA, 0.5, 1, 1, &[1.0])),
+            vec![(0, 20, 998, 598)]
+        );
+        assert_eq!(
+            dims(&tile(AREA, 0.5, 0, 1, &[1.0; 2])),
+            vec![(0, 20, 998, 298), (0, 320, 998, 298)]
+        );
+    }
+}
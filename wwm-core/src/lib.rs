@@ -1,3 +1,8 @@
+mod bidi;
+mod color_font;
+pub mod icon;
+pub mod layout;
+pub mod overlay;
 pub mod text;
 pub mod util;
 pub mod visual;
@@ -0,0 +1,129 @@
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        render::{Color, ConnectionExt as _, CreatePictureAux, Picture, PolyEdge, PolyMode},
+        xproto::{
+            BackingStore, ConfigureWindowAux, ConnectionExt, CreateWindowAux, EventMask, Window,
+            WindowClass,
+        },
+    },
+    rust_connection::{ConnectionError, ReplyOrIdError},
+};
+
+use crate::{
+    text::{FontError, TextDrawOptions, TextRenderer},
+    util::primitives::WRect,
+};
+
+// a small override-redirect window used to draw text on top of clients
+pub struct WOverlay {
+    pub window: Window,
+    picture: Picture,
+    pub rect: WRect,
+}
+
+impl WOverlay {
+    pub fn new<C: Connection>(
+        conn: &C,
+        text_renderer: &TextRenderer<C>,
+        rect: WRect,
+        bg: u32,
+    ) -> Result<Self, ReplyOrIdError> {
+        let window = conn.generate_id()?;
+        conn.create_window(
+            text_renderer.visual_info.root.depth,
+            window,
+            text_renderer.visual_info.screen_root,
+            rect.x,
+            rect.y,
+            rect.w,
+            rect.h,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            0,
+            &CreateWindowAux::new()
+                .background_pixel(bg)
+                .event_mask(EventMask::EXPOSURE)
+                .backing_store(BackingStore::WHEN_MAPPED)
+                .override_redirect(1),
+        )?;
+
+        let picture = conn.generate_id()?;
+        conn.render_create_picture(
+            picture,
+            window,
+            text_renderer.visual_info.root.pict_format,
+            &CreatePictureAux::new()
+                .polyedge(PolyEdge::SMOOTH)
+                .polymode(PolyMode::IMPRECISE),
+        )?;
+
+        conn.map_window(window)?;
+
+        Ok(Self {
+            window,
+            picture,
+            rect,
+        })
+    }
+
+    // draws the text centered in the overlay
+    pub fn draw<C: Connection>(
+        &self,
+        text_renderer: &TextRenderer<C>,
+        text: &str,
+        padding: u16,
+        fg: Color,
+        bg: Color,
+    ) -> Result<(), FontError> {
+        let rect = WRect::new(0, 0, self.rect.w, self.rect.h);
+        self.draw_in(text_renderer, rect, text, padding, (fg, bg), true)
+    }
+
+    // draws the text into `rect`, which is relative to the overlay
+    pub fn draw_in<C: Connection>(
+        &self,
+        text_renderer: &TextRenderer<C>,
+        rect: WRect,
+        text: &str,
+        padding: u16,
+        (fg, bg): (Color, Color),
+        centered: bool,
+    ) -> Result<(), FontError> {
+        let spans = [(text, fg)];
+        let (picture, window) = (self.picture, self.window);
+        text_renderer.draw(
+            rect,
+            &spans,
+            padding,
+            picture,
+            window,
+            TextDrawOptions {
+                bg,
+                centered,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn configure<C: Connection>(
+        &mut self,
+        conn: &C,
+        rect: WRect,
+    ) -> Result<(), ConnectionError> {
+        self.rect = rect;
+        let aux = ConfigureWindowAux::new()
+            .x(rect.x as i32)
+            .y(rect.y as i32)
+            .width(rect.w as u32)
+            .height(rect.h as u32);
+        conn.configure_window(self.window, &aux)?;
+        Ok(())
+    }
+
+    pub fn destroy<C: Connection>(self, conn: &C) -> Result<(), ConnectionError> {
+        conn.render_free_picture(self.picture)?;
+        conn.destroy_window(self.window)?;
+        Ok(())
+    }
+}
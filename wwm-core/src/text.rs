@@ -1,20 +1,31 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{hash_map::Entry, HashMap, HashSet},
+};
+
 use font_loader::system_fonts as fonts;
 use fontdue::{Font as FontData, FontSettings, Metrics};
 use smallmap::Map;
 use thiserror::Error;
+use ttf_parser::Face;
 use x11rb::{
     connection::Connection,
     protocol::{
         render::{
             Color, ConnectionExt as _, CreatePictureAux, Glyphinfo, Glyphset, PictOp, Picture,
-            Repeat,
         },
-        xproto::{ConnectionExt, Rectangle, Screen, Window},
+        xproto::{ConnectionExt, Pixmap, Rectangle, Screen, Window},
     },
     rust_connection::{ConnectionError, ReplyOrIdError},
+    NONE,
 };
 
-use crate::{util::primitives::WRect, visual::VisualError};
+use crate::{
+    bidi,
+    color_font::{ColorFont, ColorGlyph},
+    util::primitives::{WPos, WRect},
+    visual::VisualError,
+};
 
 use crate::visual::RenderVisualInfo;
 
@@ -23,7 +34,7 @@ pub enum FontError {
     #[error("Failed to load font data: {0}")]
     LoadFromBytes(&'static str),
     #[error("Could not find font: {0}")]
-    NotFound(&'static str),
+    NotFound(String),
     #[error("Connection error: {0:#?}")]
     Connection(#[from] ConnectionError),
     #[error("Reply or ID error: {0:?}")]
@@ -32,18 +43,325 @@ pub enum FontError {
     Visual(#[from] VisualError),
 }
 
+// how many rendered strings are kept around before the least recently used one is freed
+const TEXT_CACHE_CAP: usize = 64;
+// how many bytes of glyph bitmaps are sent per request while uploading a font
+const GLYPH_UPLOAD_BATCH: usize = 32768;
+// what text that does not fit ends in
+const ELLIPSIS: &str = "…";
+
+const BLACK: Color = Color {
+    red: 0,
+    green: 0,
+    blue: 0,
+    alpha: 0xffff,
+};
+const WHITE: Color = Color {
+    red: 0xffff,
+    green: 0xffff,
+    blue: 0xffff,
+    alpha: 0xffff,
+};
+
 pub struct TextRenderer<'a, C: Connection> {
     conn: &'a C,
-    pub gsid: Glyphset,
-    char_map: Map<char, CharInfo>,
-    pub font_height: i16,
+    // everything that depends on the font is behind cells since the font can be
+    // swapped while the renderer is shared
+    gsid: Cell<Glyphset>,
+    // argb copies of color glyphs, under the same ids as their alpha in `gsid`
+    color_gsid: Cell<Glyphset>,
+    char_map: RefCell<Map<char, CharInfo>>,
+    fallbacks: RefCell<FallbackFonts>,
+    styled: RefCell<StyledFonts>,
+    decoration_metrics: Cell<DecorationMetrics>,
+    // the id the next glyph that is uploaded after the main font gets
+    next_id: Cell<u32>,
+    font_height: Cell<i16>,
+    // how far the font's lines reach above and below the baseline. the descent is negative.
+    ascent: Cell<i16>,
+    descent: Cell<i16>,
+    scale: Cell<f32>,
     pub visual_info: RenderVisualInfo,
+    cache: RefCell<HashMap<TextKey, CachedText>>,
+    draws: Cell<u64>,
+    // solid fill pictures by color. there are only a handful of text colors so these are kept
+    fills: RefCell<HashMap<Color, Picture>>,
+    // reused for encoding the glyphs of every composite request
+    glyph_buf: RefCell<Vec<u8>>,
+    coverage: CoverageTable,
 }
 
+// everything that affects how a string ends up looking
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct TextKey {
+    spans: Vec<(String, Color)>,
+    w: u16,
+    h: u16,
+    padding: u16,
+    bg: Color,
+    style: TextStyle,
+    centered: bool,
+}
+
+struct CachedText {
+    pixmap: Pixmap,
+    picture: Picture,
+    last_used: u64,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextMetrics {
+    pub width: u16,
+    pub height: u16,
+    // distances from the baseline, the descent being negative
+    pub ascent: i16,
+    pub descent: i16,
+}
+
+#[derive(Clone, Copy)]
 pub struct CharInfo {
     pub glyph_id: u32,
     pub horizontal_space: i16,
     pub height: u16,
+    pub color: bool,
+}
+
+impl CharInfo {
+    fn new(glyph_id: u32, metrics: &Metrics) -> Self {
+        Self {
+            glyph_id,
+            horizontal_space: metrics.advance_width as i16,
+            height: metrics.height as u16,
+            color: false,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Antialiasing {
+    #[default]
+    Grayscale,
+    // every pixel is either fully covered or not at all, which is the crispest on low dpi displays
+    None,
+}
+
+// how rasterized glyphs are turned into what ends up on screen. fontdue does not hint,
+// so these are the only knobs there are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextRenderOptions {
+    pub antialiasing: Antialiasing,
+    // values above 1 make glyphs heavier and values below 1 make them lighter
+    pub gamma: f32,
+}
+
+impl Default for TextRenderOptions {
+    fn default() -> Self {
+        Self {
+            antialiasing: Antialiasing::Grayscale,
+            gamma: 1.0,
+        }
+    }
+}
+
+type CoverageTable = [u8; 256];
+
+impl TextRenderOptions {
+    // maps the coverage fontdue rasterizes to the alpha that is uploaded
+    fn coverage_table(&self) -> CoverageTable {
+        let mut table = [0; 256];
+        for (i, alpha) in table.iter_mut().enumerate() {
+            *alpha = match self.antialiasing {
+                Antialiasing::Grayscale => {
+                    (255.0 * (i as f32 / 255.0).powf(1.0 / self.gamma)).round() as u8
+                }
+                Antialiasing::None if i >= 128 => 255,
+                Antialiasing::None => 0,
+            };
+        }
+        table
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontStyle {
+    #[default]
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+}
+
+// lines drawn along with text, in the color of the text they belong to
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextDecorations(u8);
+impl TextDecorations {
+    pub const NONE: Self = Self(0);
+    pub const UNDERLINE: Self = Self(1 << 0);
+    pub const OVERLINE: Self = Self(1 << 1);
+    pub const STRIKETHROUGH: Self = Self(1 << 2);
+}
+
+impl std::ops::BitOr for TextDecorations {
+    type Output = TextDecorations;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitAnd for TextDecorations {
+    type Output = bool;
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0) == rhs
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextStyle {
+    pub font: FontStyle,
+    pub decorations: TextDecorations,
+}
+
+// how text is drawn into its rect
+#[derive(Default, Debug, Clone, Copy)]
+pub struct TextDrawOptions {
+    pub bg: Color,
+    pub style: TextStyle,
+    // centered in the rect, rather than starting after the padding
+    pub centered: bool,
+}
+
+// where the lines of decorated text go, in pixels above the baseline
+#[derive(Debug, Clone, Copy)]
+struct DecorationMetrics {
+    thickness: u16,
+    underline: i16,
+    overline: i16,
+    strikethrough: i16,
+}
+
+impl DecorationMetrics {
+    // reads the metrics from the font and makes up any that it lacks
+    fn from_font(data: &[u8], size: f32) -> Self {
+        let guess = Self {
+            thickness: (size / 14.0).round().max(1.0) as u16,
+            underline: -(size / 10.0).round() as i16,
+            overline: (size * 0.8).round() as i16,
+            strikethrough: (size * 0.3).round() as i16,
+        };
+        let Ok(face) = Face::from_slice(data, 0) else {
+            return guess;
+        };
+
+        let px = |units: i16| (units as f32 * size / face.units_per_em() as f32).round() as i16;
+        let underline = face.underline_metrics();
+        Self {
+            thickness: underline.map_or(guess.thickness, |m| px(m.thickness).max(1) as u16),
+            underline: underline.map_or(guess.underline, |m| px(m.position)),
+            overline: px(face.ascender()),
+            strikethrough: face
+                .strikeout_metrics()
+                .map_or(guess.strikethrough, |m| px(m.position)),
+        }
+    }
+}
+
+// the bold and italic faces of the main font family. unlike the regular face these are
+// rasterized a glyph at a time since most text never uses them.
+struct StyledFonts {
+    family: String,
+    size: f32,
+    // `None` for faces that failed to load
+    loaded: HashMap<FontStyle, Option<FontData>>,
+    chars: HashMap<(FontStyle, char), CharInfo>,
+}
+
+impl StyledFonts {
+    fn new(family: String, size: f32) -> Self {
+        Self {
+            family,
+            size,
+            loaded: HashMap::new(),
+            chars: HashMap::new(),
+        }
+    }
+
+    fn font(&mut self, style: FontStyle) -> Option<&FontData> {
+        let (family, size) = (&self.family, self.size);
+        self.loaded
+            .entry(style)
+            .or_insert_with(|| {
+                let font = find_font(family, true, style).and_then(|data| parse_font(data, size));
+                if let Err(ref e) = font {
+                    eprintln!("ERROR: failed to load {style:?} font: {e}");
+                }
+                font.ok()
+            })
+            .as_ref()
+    }
+}
+
+enum FallbackFont {
+    Outline(FontData),
+    Color(ColorFont),
+}
+
+enum FallbackGlyph {
+    Outline(Metrics, Vec<u8>),
+    Color(ColorGlyph),
+}
+
+// fonts that characters missing from the main font are looked up in, in order.
+// each font is only loaded once a character needs it.
+struct FallbackFonts {
+    families: &'static [&'static str],
+    // `None` for fonts that failed to load
+    loaded: Vec<Option<FallbackFont>>,
+    size: f32,
+    // characters none of the fonts have, so that they are only looked up once
+    missing: HashSet<char>,
+}
+
+impl FallbackFonts {
+    fn new(families: &'static [&'static str], size: f32) -> Self {
+        Self {
+            families,
+            loaded: Vec::new(),
+            size,
+            missing: HashSet::new(),
+        }
+    }
+
+    fn rasterize(&mut self, c: char) -> Option<FallbackGlyph> {
+        if self.missing.contains(&c) {
+            return None;
+        }
+
+        for i in 0..self.families.len() {
+            if i == self.loaded.len() {
+                let font = load_fallback_font(self.families[i], self.size);
+                if let Err(ref e) = font {
+                    eprintln!("ERROR: failed to load fallback font: {e}");
+                }
+                self.loaded.push(font.ok());
+            }
+
+            match &self.loaded[i] {
+                Some(FallbackFont::Outline(font)) if font.lookup_glyph_index(c) != 0 => {
+                    let (metrics, bitmap) = font.rasterize(c, self.size);
+                    return Some(FallbackGlyph::Outline(metrics, bitmap));
+                }
+                Some(FallbackFont::Color(font)) => {
+                    if let Some(glyph) = font.rasterize(c, self.size) {
+                        return Some(FallbackGlyph::Color(glyph));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.missing.insert(c);
+        None
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -52,36 +370,51 @@ pub struct FontEncodedChunk {
     pub font_height: i16,
     glyph_set: Glyphset,
     glyph_ids: Vec<u32>,
+    color: bool,
 }
 
 type RasterizationData = Vec<(char, Metrics, Vec<u8>)>;
-type CharMapData = (Vec<u32>, Vec<Glyphinfo>, Vec<u8>, Map<char, CharInfo>);
 
-impl<'a, C: Connection> TextRenderer<'a, C> {
-    pub fn new(
-        conn: &'a C,
-        screen: &Screen,
-        font_family: &'static str,
-        font_size: f32,
-    ) -> Result<Self, FontError> {
-        let visual_info = RenderVisualInfo::new(conn, screen)?;
-        let gsid = conn.generate_id()?;
-        conn.render_create_glyph_set(gsid, visual_info.render.pict_format)?;
-
-        let font = Self::evaluate(font_family, font_size)?;
-        let (data, font_height) = Self::rasterize(&font, font_size);
-        let (ids, glyphs, raw_data, char_map) =
-            Self::generate_char_map(conn, gsid, data, font_height)?;
-
-        conn.render_add_glyphs(gsid, &ids, &glyphs, &raw_data)
-            .unwrap();
+// the glyphs of a font, rasterized without involving the x server.
+// this is the slow part of setting up a text renderer, so it can be done on another thread.
+pub struct RasterizedFont {
+    data: RasterizationData,
+    family: String,
+    height: i16,
+    ascent: i16,
+    descent: i16,
+    size: f32,
+    scale: f32,
+    decoration_metrics: DecorationMetrics,
+}
 
-        Ok(TextRenderer {
-            conn,
-            gsid,
-            char_map,
-            font_height,
-            visual_info,
+impl RasterizedFont {
+    // the font is rasterized at `size` times `scale` pixels, the scale being that of
+    // the monitor the text ends up on
+    pub fn load(family: &str, size: f32, scale: f32) -> Result<Self, FontError> {
+        let size = size * scale;
+        let family = if family.is_empty() {
+            "monospace"
+        } else {
+            family
+        };
+        let font_data = find_font(family, true, FontStyle::Regular)?;
+        let decoration_metrics = DecorationMetrics::from_font(&font_data, size);
+        let font = parse_font(font_data, size)?;
+        let (data, height) = Self::rasterize(&font, size);
+        // fonts without line metrics are assumed to sit on the baseline
+        let (ascent, descent) = font.horizontal_line_metrics(size).map_or((height, 0), |m| {
+            (m.ascent.round() as i16, m.descent.round() as i16)
+        });
+        Ok(Self {
+            data,
+            family: family.to_string(),
+            height,
+            ascent,
+            descent,
+            size,
+            scale,
+            decoration_metrics,
         })
     }
 
@@ -100,94 +433,351 @@ impl<'a, C: Connection> TextRenderer<'a, C> {
         }
         (data, max_height)
     }
+}
 
-    fn evaluate(family: &'static str, size: f32) -> Result<FontData, FontError> {
-        let family = if family.is_empty() {
-            "monospace"
-        } else {
-            family
-        };
-        let property = fonts::FontPropertyBuilder::new()
-            .monospace()
-            .family(family)
-            .build();
-        if let Some((font, _)) = fonts::get(&property) {
-            let settings = FontSettings {
-                scale: size,
-                ..Default::default()
-            };
-            FontData::from_bytes(font, settings).map_err(FontError::LoadFromBytes)
-        } else {
-            Err(FontError::NotFound(family))
+fn find_font(family: &str, monospace: bool, style: FontStyle) -> Result<Vec<u8>, FontError> {
+    let mut property = fonts::FontPropertyBuilder::new();
+    if monospace {
+        property = property.monospace();
+    }
+    if matches!(style, FontStyle::Bold | FontStyle::BoldItalic) {
+        property = property.bold();
+    }
+    if matches!(style, FontStyle::Italic | FontStyle::BoldItalic) {
+        property = property.italic();
+    }
+    let property = property.family(family).build();
+    fonts::get(&property)
+        .map(|(font, _)| font)
+        .ok_or_else(|| FontError::NotFound(family.to_string()))
+}
+
+fn parse_font(data: Vec<u8>, size: f32) -> Result<FontData, FontError> {
+    let settings = FontSettings {
+        scale: size,
+        ..Default::default()
+    };
+    FontData::from_bytes(data, settings).map_err(FontError::LoadFromBytes)
+}
+
+// bitmap fonts have to be told apart before fontdue gets them, since it would
+// happily load them and rasterize every glyph as empty
+fn load_fallback_font(family: &'static str, size: f32) -> Result<FallbackFont, FontError> {
+    match ColorFont::from_bytes(find_font(family, false, FontStyle::Regular)?) {
+        Ok(font) => Ok(FallbackFont::Color(font)),
+        Err(data) => parse_font(data, size).map(FallbackFont::Outline),
+    }
+}
+
+// every row of an a8 glyph is padded to four bytes. the coverage table is applied
+// to rasterized outlines, bitmaps that already are what they should look like go without.
+fn push_a8_rows(
+    raw_data: &mut Vec<u8>,
+    metrics: &Metrics,
+    bitmap: &[u8],
+    coverage: Option<&CoverageTable>,
+) {
+    let stride = (metrics.width + 3) & !3;
+    for row in bitmap.chunks(metrics.width.max(1)) {
+        match coverage {
+            Some(table) => raw_data.extend(row.iter().map(|c| table[*c as usize])),
+            None => raw_data.extend_from_slice(row),
         }
+        raw_data.resize(raw_data.len() + stride - row.len(), 0);
+    }
+}
+
+// glyphs are positioned relative to the baseline
+fn glyph_info(metrics: &Metrics) -> Glyphinfo {
+    Glyphinfo {
+        width: metrics.width as u16,
+        height: metrics.height as u16,
+        x: -metrics.xmin as i16,
+        y: metrics.height as i16 + metrics.ymin as i16,
+        x_off: metrics.advance_width as i16,
+        y_off: metrics.advance_height as i16,
     }
+}
 
-    fn generate_char_map(
+impl<'a, C: Connection> TextRenderer<'a, C> {
+    pub fn new(
+        conn: &'a C,
+        screen: &Screen,
+        font_family: &str,
+        font_size: f32,
+        scale: f32,
+        fallback_families: &'static [&'static str],
+        options: TextRenderOptions,
+    ) -> Result<Self, FontError> {
+        let font = RasterizedFont::load(font_family, font_size, scale)?;
+        Self::from_font(conn, screen, font, fallback_families, options)
+    }
+
+    // uploads an already rasterized font to the server
+    pub fn from_font(
+        conn: &'a C,
+        screen: &Screen,
+        font: RasterizedFont,
+        fallback_families: &'static [&'static str],
+        options: TextRenderOptions,
+    ) -> Result<Self, FontError> {
+        let visual_info = RenderVisualInfo::new(conn, screen)?;
+        let (gsid, color_gsid) = Self::create_glyph_sets(conn, &visual_info)?;
+
+        let coverage = options.coverage_table();
+        let glyph_count = font.data.len() as u32;
+        let char_map = Self::upload_glyphs(conn, gsid, font.data, &coverage)?;
+
+        Ok(TextRenderer {
+            conn,
+            gsid: Cell::new(gsid),
+            color_gsid: Cell::new(color_gsid),
+            char_map: RefCell::new(char_map),
+            fallbacks: RefCell::new(FallbackFonts::new(fallback_families, font.size)),
+            styled: RefCell::new(StyledFonts::new(font.family, font.size)),
+            decoration_metrics: Cell::new(font.decoration_metrics),
+            next_id: Cell::new(glyph_count),
+            font_height: Cell::new(font.height),
+            ascent: Cell::new(font.ascent),
+            descent: Cell::new(font.descent),
+            scale: Cell::new(font.scale),
+            visual_info,
+            cache: RefCell::new(HashMap::new()),
+            draws: Cell::new(0),
+            fills: RefCell::new(HashMap::new()),
+            glyph_buf: RefCell::new(Vec::new()),
+            coverage,
+        })
+    }
+
+    // swaps the main font for another one, like the same family at a new size.
+    // every glyph is uploaded again, so the old glyphsets and rendered strings are freed.
+    pub fn set_font(&self, font: RasterizedFont) -> Result<(), FontError> {
+        let (gsid, color_gsid) = Self::create_glyph_sets(self.conn, &self.visual_info)?;
+        let glyph_count = font.data.len() as u32;
+        let char_map = Self::upload_glyphs(self.conn, gsid, font.data, &self.coverage)?;
+
+        self.conn.render_free_glyph_set(self.gsid.replace(gsid))?;
+        self.conn
+            .render_free_glyph_set(self.color_gsid.replace(color_gsid))?;
+        for (_, cached) in self.cache.borrow_mut().drain() {
+            self.conn.render_free_picture(cached.picture)?;
+            self.conn.free_pixmap(cached.pixmap)?;
+        }
+
+        *self.char_map.borrow_mut() = char_map;
+        let families = self.fallbacks.borrow().families;
+        *self.fallbacks.borrow_mut() = FallbackFonts::new(families, font.size);
+        *self.styled.borrow_mut() = StyledFonts::new(font.family, font.size);
+        self.decoration_metrics.set(font.decoration_metrics);
+        self.next_id.set(glyph_count);
+        self.font_height.set(font.height);
+        self.ascent.set(font.ascent);
+        self.descent.set(font.descent);
+        self.scale.set(font.scale);
+        Ok(())
+    }
+
+    pub fn ascent(&self) -> i16 {
+        self.ascent.get()
+    }
+
+    pub fn descent(&self) -> i16 {
+        self.descent.get()
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale.get()
+    }
+
+    // one glyphset for the alpha of every glyph and one for the colors of color glyphs
+    fn create_glyph_sets(
+        conn: &C,
+        visual_info: &RenderVisualInfo,
+    ) -> Result<(Glyphset, Glyphset), ReplyOrIdError> {
+        let gsid = conn.generate_id()?;
+        conn.render_create_glyph_set(gsid, visual_info.glyph_format)?;
+        let color_gsid = conn.generate_id()?;
+        conn.render_create_glyph_set(color_gsid, visual_info.render.pict_format)?;
+        Ok((gsid, color_gsid))
+    }
+
+    // uploads the glyphs in batches, dropping the bitmap of each glyph once it is copied
+    fn upload_glyphs(
         conn: &C,
         glyphset_id: u32,
         data: RasterizationData,
-        font_height: i16,
-    ) -> Result<CharMapData, FontError> {
+        coverage: &CoverageTable,
+    ) -> Result<Map<char, CharInfo>, FontError> {
         let mut ids = vec![];
         let mut glyphs = vec![];
-        let mut raw_data = vec![];
+        let mut raw_data = Vec::with_capacity(GLYPH_UPLOAD_BATCH);
         let mut char_map: Map<char, CharInfo> = Map::new();
 
-        fn current_out_size(ids: usize, infos: usize, raw_data: usize) -> usize {
-            core::mem::size_of::<u32>()
-                + core::mem::size_of::<u32>() * ids
-                + core::mem::size_of::<u32>() * infos
-                + core::mem::size_of::<u32>() * raw_data
-        }
-
-        for (id, (c, metrics, bitmaps)) in data.into_iter().enumerate() {
+        for (id, (c, metrics, bitmap)) in data.into_iter().enumerate() {
             let id = id as u32;
-            for byte in bitmaps {
-                raw_data.extend_from_slice(&[byte, byte, byte, byte]);
-            }
-
-            let horizontal_space = metrics.advance_width as i16;
-            let glyph_info = Glyphinfo {
-                width: metrics.width as u16,
-                height: metrics.height as u16,
-                x: -metrics.xmin as i16,
-                y: metrics.height as i16 - font_height + metrics.ymin as i16,
-                x_off: horizontal_space,
-                y_off: metrics.advance_height as i16,
-            };
+            push_a8_rows(&mut raw_data, &metrics, &bitmap, Some(coverage));
 
             ids.push(id);
-            glyphs.push(glyph_info);
-            char_map.insert(
-                c,
-                CharInfo {
-                    glyph_id: id,
-                    horizontal_space,
-                    height: metrics.height as u16,
-                },
-            );
-
-            let current_out_size = current_out_size(ids.len(), glyphs.len(), raw_data.len());
-            if current_out_size >= 32768 {
+            glyphs.push(glyph_info(&metrics));
+            char_map.insert(c, CharInfo::new(id, &metrics));
+
+            if raw_data.len() >= GLYPH_UPLOAD_BATCH {
                 conn.render_add_glyphs(glyphset_id, &ids, &glyphs, &raw_data)?;
                 ids.clear();
                 glyphs.clear();
                 raw_data.clear();
             }
         }
-        Ok((ids, glyphs, raw_data, char_map))
+
+        if !ids.is_empty() {
+            conn.render_add_glyphs(glyphset_id, &ids, &glyphs, &raw_data)?;
+        }
+        Ok(char_map)
     }
 
-    pub fn text_width(&self, text: impl ToString) -> u16 {
-        text.to_string().chars().fold(0u16, |acc, c| {
-            if let Some(c) = self.char_map.get(&c) {
-                return acc + c.horizontal_space as u16;
+    // the glyph of a character, looking through the fallback fonts if the main font lacks it
+    fn glyph(&self, c: char) -> Option<CharInfo> {
+        if let Some(info) = self.char_map.borrow().get(&c) {
+            return Some(*info);
+        }
+
+        let mut fallbacks = self.fallbacks.borrow_mut();
+        let glyph = fallbacks.rasterize(c)?;
+
+        let id = self.next_id.get();
+        let uploaded = match glyph {
+            FallbackGlyph::Outline(metrics, bitmap) => {
+                self.upload_outline_glyph(id, &metrics, &bitmap)
+            }
+            FallbackGlyph::Color(glyph) => self.upload_color_glyph(id, &glyph),
+        };
+        let info = match uploaded {
+            Ok(info) => info,
+            Err(e) => {
+                eprintln!("ERROR: failed to upload fallback glyph for {c:?}: {e}");
+                fallbacks.missing.insert(c);
+                return None;
             }
-            acc
+        };
+        self.next_id.set(id + 1);
+
+        self.char_map.borrow_mut().insert(c, info);
+        Some(info)
+    }
+
+    // the glyph of a character in a bold or italic face, which falls back to the
+    // regular glyph if the face lacks the character
+    fn styled_glyph(&self, c: char, style: FontStyle) -> Option<CharInfo> {
+        if style == FontStyle::Regular {
+            return self.glyph(c);
+        }
+        if let Some(info) = self.styled.borrow().chars.get(&(style, c)) {
+            return Some(*info);
+        }
+
+        let mut styled = self.styled.borrow_mut();
+        let size = styled.size;
+        let rasterized = styled
+            .font(style)
+            .filter(|font| font.lookup_glyph_index(c) != 0)
+            .map(|font| font.rasterize(c, size));
+        let Some((metrics, bitmap)) = rasterized else {
+            drop(styled);
+            return self.glyph(c);
+        };
+
+        let id = self.next_id.get();
+        match self.upload_outline_glyph(id, &metrics, &bitmap) {
+            Ok(info) => {
+                self.next_id.set(id + 1);
+                styled.chars.insert((style, c), info);
+                Some(info)
+            }
+            Err(e) => {
+                eprintln!("ERROR: failed to upload {style:?} glyph for {c:?}: {e}");
+                drop(styled);
+                self.glyph(c)
+            }
+        }
+    }
+
+    fn upload_outline_glyph(
+        &self,
+        id: u32,
+        metrics: &Metrics,
+        bitmap: &[u8],
+    ) -> Result<CharInfo, ConnectionError> {
+        let mut raw_data = vec![];
+        push_a8_rows(&mut raw_data, metrics, bitmap, Some(&self.coverage));
+        let glyph_info = glyph_info(metrics);
+        self.conn
+            .render_add_glyphs(self.gsid.get(), &[id], &[glyph_info], &raw_data)?;
+        Ok(CharInfo::new(id, metrics))
+    }
+
+    // color glyphs go into both glyphsets. the alpha is needed on its own to
+    // cut out what is behind the glyph before its colors are added.
+    fn upload_color_glyph(&self, id: u32, glyph: &ColorGlyph) -> Result<CharInfo, ConnectionError> {
+        let glyph_info = Glyphinfo {
+            width: glyph.width,
+            height: glyph.height,
+            x: -glyph.left,
+            y: glyph.top,
+            x_off: glyph.advance,
+            y_off: 0,
+        };
+
+        let metrics = Metrics {
+            width: glyph.width as usize,
+            ..Default::default()
+        };
+        let alpha: Vec<u8> = glyph.pixels.iter().map(|p| p[3]).collect();
+        let mut raw_data = vec![];
+        push_a8_rows(&mut raw_data, &metrics, &alpha, None);
+        self.conn
+            .render_add_glyphs(self.gsid.get(), &[id], &[glyph_info], &raw_data)?;
+
+        let argb: Vec<u8> = glyph
+            .pixels
+            .iter()
+            .flat_map(|[r, g, b, a]| u32::from_be_bytes([*a, *r, *g, *b]).to_ne_bytes())
+            .collect();
+        self.conn
+            .render_add_glyphs(self.color_gsid.get(), &[id], &[glyph_info], &argb)?;
+
+        Ok(CharInfo {
+            glyph_id: id,
+            horizontal_space: glyph.advance,
+            height: glyph.height,
+            color: true,
         })
     }
 
+    // the space text takes up when drawn. the vertical metrics are those of the font's line
+    // so that text lines up no matter which characters it has.
+    pub fn measure(&self, text: &str) -> TextMetrics {
+        let width = text
+            .chars()
+            .filter_map(|c| self.glyph(c))
+            .map(|c| c.horizontal_space as u16)
+            .sum();
+        let (ascent, descent) = (self.ascent(), self.descent());
+        TextMetrics {
+            width,
+            height: (ascent - descent) as u16,
+            ascent,
+            descent,
+        }
+    }
+
     pub fn encode(&self, text: &str, max_width: i16) -> Vec<FontEncodedChunk> {
+        self.encode_styled(text, FontStyle::Regular, max_width)
+    }
+
+    fn encode_styled(&self, text: &str, style: FontStyle, max_width: i16) -> Vec<FontEncodedChunk> {
+        // glyphs are laid out left to right, so right to left runs are turned around first
+        let text = bidi::visual_order(text);
         let mut total_width = 0;
         let mut total_glyphs = 0;
         let mut cur_width = 0;
@@ -195,22 +785,24 @@ impl<'a, C: Connection> TextRenderer<'a, C> {
         let mut chunks = vec![];
         for char in text.chars() {
             total_glyphs += 1;
-            if let Some(lchar) = self.char_map.get(&char) {
+            if let Some(lchar) = self.styled_glyph(char, style) {
                 if !cur_glyphs.is_empty() {
                     chunks.push(FontEncodedChunk {
                         width: core::mem::take(&mut cur_width),
-                        font_height: self.font_height,
-                        glyph_set: self.gsid,
+                        font_height: self.font_height.get(),
+                        glyph_set: self.gsid.get(),
                         glyph_ids: core::mem::take(&mut cur_glyphs),
+                        color: false,
                     });
                 }
 
                 if total_width + lchar.horizontal_space > max_width && !cur_glyphs.is_empty() {
                     chunks.push(FontEncodedChunk {
                         width: cur_width,
-                        font_height: self.font_height,
-                        glyph_set: self.gsid,
+                        font_height: self.font_height.get(),
+                        glyph_set: self.gsid.get(),
                         glyph_ids: cur_glyphs,
+                        color: false,
                     });
                     return chunks;
                 }
@@ -218,9 +810,10 @@ impl<'a, C: Connection> TextRenderer<'a, C> {
                 total_width += lchar.horizontal_space;
                 chunks.push(FontEncodedChunk {
                     width: lchar.horizontal_space,
-                    font_height: self.font_height,
-                    glyph_set: self.gsid,
+                    font_height: self.font_height.get(),
+                    glyph_set: self.gsid.get(),
                     glyph_ids: vec![lchar.glyph_id],
+                    color: lchar.color,
                 })
             }
             if total_glyphs == 254 {
@@ -231,87 +824,278 @@ impl<'a, C: Connection> TextRenderer<'a, C> {
         if !cur_glyphs.is_empty() {
             chunks.push(FontEncodedChunk {
                 width: cur_width,
-                font_height: self.font_height,
-                glyph_set: self.gsid,
+                font_height: self.font_height.get(),
+                glyph_set: self.gsid.get(),
                 glyph_ids: cur_glyphs,
+                color: false,
             })
         }
         chunks
     }
 
+    // encodes as much of the text as fits into `max_width`, ending in an ellipsis if anything
+    // had to be cut off. returns the chunks along with the width they take up.
+    pub fn encode_ellipsized(&self, text: &str, max_width: i16) -> (Vec<FontEncodedChunk>, i16) {
+        let spans = [(text, ())];
+        let (chunks, width) = self.encode_spans_ellipsized(&spans, FontStyle::Regular, max_width);
+        (chunks.into_iter().map(|(c, _)| c).collect(), width)
+    }
+
+    // like `encode_ellipsized`, but every chunk is paired with the tag of the span it
+    // belongs to. the ellipsis takes the tag of the first chunk it replaces.
+    fn encode_spans_ellipsized<T: Copy>(
+        &self,
+        spans: &[(&str, T)],
+        style: FontStyle,
+        max_width: i16,
+    ) -> (Vec<(FontEncodedChunk, T)>, i16) {
+        let mut chunks: Vec<_> = spans
+            .iter()
+            .flat_map(|(text, tag)| {
+                let chunks = self.encode_styled(text, style, i16::MAX);
+                chunks.into_iter().map(move |c| (c, *tag))
+            })
+            .collect();
+        let width = chunks.iter().map(|(c, _)| c.width).sum();
+        if width <= max_width {
+            return (chunks, width);
+        }
+
+        // the text is cut off without an ellipsis if no font has one or it doesn't fit either
+        let ellipsis = self.encode_styled(ELLIPSIS, style, i16::MAX);
+        let ellipsis_width: i16 = ellipsis.iter().map(|c| c.width).sum();
+        let ellipsis = (ellipsis_width <= max_width).then_some(ellipsis);
+        let available = max_width - ellipsis.as_ref().map_or(0, |_| ellipsis_width);
+
+        let mut width = 0;
+        let shown = chunks
+            .iter()
+            .take_while(|(c, _)| {
+                width += c.width;
+                width <= available
+            })
+            .count();
+        let tag = chunks[shown].1;
+        chunks.truncate(shown);
+        let mut width = chunks.iter().map(|(c, _)| c.width).sum();
+
+        if let Some(ellipsis) = ellipsis {
+            chunks.extend(ellipsis.into_iter().map(|c| (c, tag)));
+            width += ellipsis_width;
+        }
+        (chunks, width)
+    }
+
+    // draws the spans of text one after another into the rect of the destination picture,
+    // each in its own color. rendered strings are cached, so drawing text that has been
+    // drawn before is a single composite.
     pub fn draw(
         &self,
         rect: WRect,
-        text: &str,
+        spans: &[(&str, Color)],
         padding: u16,
         dst_picture: Picture,
         dst_window: Window,
-        bg: Color,
-        fg: Color,
-        is_tag: bool,
+        options: TextDrawOptions,
     ) -> Result<(), FontError> {
-        let text_width = self.text_width(&text);
-        let chunks = self.encode(&text, text_width as i16 - 1);
+        if rect.w == 0 || rect.h == 0 {
+            return Ok(());
+        }
+
+        let key = TextKey {
+            spans: spans.iter().map(|(t, c)| (t.to_string(), *c)).collect(),
+            w: rect.w,
+            h: rect.h,
+            padding,
+            bg: options.bg,
+            style: options.style,
+            centered: options.centered,
+        };
 
-        let text_picture = self.conn.generate_id()?;
-        let text_pixmap = self.conn.generate_id()?;
+        let draws = self.draws.get() + 1;
+        self.draws.set(draws);
 
+        let mut cache = self.cache.borrow_mut();
+        if !cache.contains_key(&key) && cache.len() >= TEXT_CACHE_CAP {
+            self.evict_oldest(&mut cache)?;
+        }
+
+        let cached = match cache.entry(key) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                let cached = self.render(e.key(), dst_window)?;
+                e.insert(cached)
+            }
+        };
+        cached.last_used = draws;
+
+        self.conn.render_composite(
+            PictOp::SRC,
+            cached.picture,
+            NONE,
+            dst_picture,
+            0,
+            0,
+            0,
+            0,
+            rect.x,
+            rect.y,
+            rect.w,
+            rect.h,
+        )?;
+        Ok(())
+    }
+
+    fn render(&self, key: &TextKey, dst_window: Window) -> Result<CachedText, FontError> {
+        let max_width = if key.centered {
+            key.w
+        } else {
+            key.w.saturating_sub(key.padding * 2)
+        };
+        let spans: Vec<_> = key.spans.iter().map(|(t, c)| (t.as_str(), *c)).collect();
+        let (chunks, text_width) =
+            self.encode_spans_ellipsized(&spans, key.style.font, max_width as i16);
+
+        let pixmap = self.conn.generate_id()?;
         self.conn.create_pixmap(
             self.visual_info.root.depth,
-            text_pixmap,
+            pixmap,
             dst_window,
-            rect.w,
-            rect.h,
+            key.w,
+            key.h,
         )?;
 
+        let picture = self.conn.generate_id()?;
         self.conn.render_create_picture(
-            text_picture,
-            text_pixmap,
+            picture,
+            pixmap,
             self.visual_info.root.pict_format,
-            &CreatePictureAux::new().repeat(Repeat::NORMAL),
+            &CreatePictureAux::new(),
         )?;
 
-        let WRect { x, y, w, h } = rect;
-        let fg_fill_area: Rectangle = WRect::new(0, y, w, h).into();
-        let bg_fill_area: Rectangle = rect.into();
-
+        let bg_fill_area: Rectangle = WRect::new(0, 0, key.w, key.h).into();
         self.conn
-            .render_fill_rectangles(PictOp::SRC, text_picture, fg, &[fg_fill_area])?;
-        self.conn
-            .render_fill_rectangles(PictOp::SRC, dst_picture, bg, &[bg_fill_area])?;
+            .render_fill_rectangles(PictOp::SRC, picture, key.bg, &[bg_fill_area])?;
 
-        let mut x_offset = if is_tag {
-            x + ((w as i16 / 2) - (text_width as i16 / 2))
+        let mut x_offset = if key.centered {
+            (key.w as i16 / 2) - (text_width / 2)
         } else {
-            x + padding as i16
+            key.padding as i16
         };
 
-        for chunk in &chunks {
-            self.draw_glyphs(
-                x_offset,
-                y,
-                chunk.glyph_set,
-                text_picture,
-                dst_picture,
-                &chunk.glyph_ids,
-            )?;
+        // the line of the font from its ascent to its descent is centered in the rect
+        let baseline = (key.h as i16 + self.ascent() + self.descent()) / 2;
+        let decorations = self.decoration_rows(key.style.decorations, key.h, baseline);
+        // the stretches of text that share a color, which decorations are drawn along
+        let mut runs: Vec<(Color, i16, u16)> = vec![];
+
+        for (chunk, fg) in &chunks {
+            match runs.last_mut() {
+                Some((color, _, w)) if color == fg => *w += chunk.width as u16,
+                _ => runs.push((*fg, x_offset, chunk.width as u16)),
+            }
+
+            if chunk.color {
+                // composited glyphs only carry a single alpha per channel, which is not
+                // enough for premultiplied colors. whatever is behind the glyph is
+                // cut out with its alpha first and the colors are added on top.
+                let (black, white) = (self.fill(BLACK)?, self.fill(WHITE)?);
+                let (pos, ids) = (WPos::new(x_offset, baseline), &chunk.glyph_ids);
+                self.draw_glyphs(PictOp::OVER, pos, chunk.glyph_set, black, picture, ids)?;
+                self.draw_glyphs(PictOp::ADD, pos, self.color_gsid.get(), white, picture, ids)?;
+            } else {
+                self.draw_glyphs(
+                    PictOp::OVER,
+                    WPos::new(x_offset, baseline),
+                    chunk.glyph_set,
+                    self.fill(*fg)?,
+                    picture,
+                    &chunk.glyph_ids,
+                )?;
+            }
 
             x_offset += chunk.width;
         }
 
+        if !decorations.is_empty() {
+            for (fg, x, w) in runs {
+                let rects: Vec<Rectangle> = decorations
+                    .iter()
+                    .map(|(y, h)| WRect::new(x, *y, w, *h).into())
+                    .collect();
+                self.conn
+                    .render_fill_rectangles(PictOp::OVER, picture, fg, &rects)?;
+            }
+        }
+
+        Ok(CachedText {
+            pixmap,
+            picture,
+            last_used: 0,
+        })
+    }
+
+    // the y and height of every line the decorations call for
+    fn decoration_rows(
+        &self,
+        decorations: TextDecorations,
+        h: u16,
+        baseline: i16,
+    ) -> Vec<(i16, u16)> {
+        let m = self.decoration_metrics.get();
+        let row = |y: i16| {
+            let max_y = h.saturating_sub(m.thickness) as i16;
+            ((baseline - y).clamp(0, max_y), m.thickness)
+        };
+        let centered = |y: i16| row(y + m.thickness as i16 / 2);
+
+        let mut rows = vec![];
+        if decorations & TextDecorations::UNDERLINE {
+            rows.push(centered(m.underline));
+        }
+        if decorations & TextDecorations::OVERLINE {
+            rows.push(row(m.overline));
+        }
+        if decorations & TextDecorations::STRIKETHROUGH {
+            rows.push(centered(m.strikethrough));
+        }
+        rows
+    }
+
+    fn fill(&self, color: Color) -> Result<Picture, ReplyOrIdError> {
+        if let Some(picture) = self.fills.borrow().get(&color) {
+            return Ok(*picture);
+        }
+        let picture = self.conn.generate_id()?;
+        self.conn.render_create_solid_fill(picture, color)?;
+        self.fills.borrow_mut().insert(color, picture);
+        Ok(picture)
+    }
+
+    fn evict_oldest(&self, cache: &mut HashMap<TextKey, CachedText>) -> Result<(), FontError> {
+        let oldest = cache
+            .iter()
+            .min_by_key(|(_, cached)| cached.last_used)
+            .map(|(key, _)| key.clone());
+
+        if let Some(cached) = oldest.and_then(|key| cache.remove(&key)) {
+            self.conn.render_free_picture(cached.picture)?;
+            self.conn.free_pixmap(cached.pixmap)?;
+        }
         Ok(())
     }
 
     fn draw_glyphs(
         &self,
-        x: i16,
-        y: i16,
+        op: PictOp,
+        origin: WPos,
         glyphs: Glyphset,
         src: Picture,
         dst: Picture,
         glyph_ids: &[u32],
     ) -> Result<(), FontError> {
-        let mut buf = Vec::with_capacity(glyph_ids.len());
+        let mut buf = self.glyph_buf.borrow_mut();
+        buf.clear();
         let render = if glyph_ids.len() > 254 {
             &glyph_ids[..254]
         } else {
@@ -320,15 +1104,15 @@ impl<'a, C: Connection> TextRenderer<'a, C> {
 
         buf.extend_from_slice(&[render.len() as u8, 0, 0, 0]);
 
-        buf.extend_from_slice(&(x).to_ne_bytes());
-        buf.extend_from_slice(&(y).to_ne_bytes());
+        buf.extend_from_slice(&origin.x.to_ne_bytes());
+        buf.extend_from_slice(&origin.y.to_ne_bytes());
 
         for glyph in render {
             buf.extend_from_slice(&(glyph).to_ne_bytes());
         }
 
         self.conn
-            .render_composite_glyphs16(PictOp::OVER, src, dst, 0, glyphs, 0, 0, &buf)?;
+            .render_composite_glyphs16(op, src, dst, 0, glyphs, 0, 0, &buf)?;
         Ok(())
     }
 }
@@ -1,14 +1,27 @@
 use super::{color, primitives::WRect};
+use crate::text::TextStyle;
 use x11rb::protocol::render::Color;
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone)]
 pub struct WBarOptions {
     pub rect: WRect,
     pub padding: u16,
     pub section_padding: i16,
     pub tag_count: usize,
+    // the narrowest a tag gets, since tags are as wide as their label
     pub tag_width: u16,
+    pub tag_names: Vec<String>,
     pub colors: WBarColors,
+    // the style of the selected tag's label on the focused monitor
+    pub focused_tag_style: TextStyle,
+}
+
+// the label of a tag, which is its number when it has no name
+pub fn tag_label(names: &[String], tag: usize) -> String {
+    match names.get(tag) {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => (tag + 1).to_string(),
+    }
 }
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -17,15 +30,18 @@ pub struct WBarColors {
     pub bg: (u32, Color),
     pub selected_fg: (u32, Color),
     pub selected_bg: (u32, Color),
+    // the background of tags with an urgent client
+    pub urgent_bg: (u32, Color),
 }
 
 impl WBarColors {
-    pub fn new(fg: u32, bg: u32, selected_fg: u32, selected_bg: u32) -> Self {
+    pub fn new(fg: u32, bg: u32, selected_fg: u32, selected_bg: u32, urgent_bg: u32) -> Self {
         Self {
             fg: (fg, color::hex_to_rgba(fg)),
             bg: (bg, color::hex_to_rgba(bg)),
             selected_fg: (selected_fg, color::hex_to_rgba(selected_fg)),
             selected_bg: (selected_bg, color::hex_to_rgba(selected_bg)),
+            urgent_bg: (urgent_bg, color::hex_to_rgba(urgent_bg)),
         }
     }
 }
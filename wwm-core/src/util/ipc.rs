@@ -0,0 +1,28 @@
+use std::{env, path::PathBuf};
+
+// where wwm listens for `wwm-msg` and scripts, which is `$XDG_RUNTIME_DIR/wwm-<display>.sock`
+// so that the sessions on other displays keep theirs
+pub fn socket_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_RUNTIME_DIR").filter(|d| !d.is_empty())?;
+    let display = env::var("DISPLAY").ok()?;
+    Some(PathBuf::from(dir).join(socket_name(&display)))
+}
+
+// the display number of `:0.0` or `host:0`, without the screen
+fn socket_name(display: &str) -> String {
+    let number = display.rsplit(':').next().unwrap_or_default();
+    let number = number.split('.').next().unwrap_or_default();
+    format!("wwm-{number}.sock")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sockets_are_named_after_the_display() {
+        assert_eq!(socket_name(":0"), "wwm-0.sock");
+        assert_eq!(socket_name(":1.0"), "wwm-1.sock");
+        assert_eq!(socket_name("localhost:10.0"), "wwm-10.sock");
+    }
+}
@@ -0,0 +1,138 @@
+use thiserror::Error;
+use x11rb::protocol::xproto::ModMask;
+use xkbcommon::xkb::{self, keysyms as ks, Keysym};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ComboError {
+    #[error("Unknown modifier: {0}")]
+    Modifier(String),
+    #[error("Unknown key: {0}")]
+    Key(String),
+}
+
+// resolves a keysym from its name, e.g. "Return", "KP_1" or "XF86AudioMute".
+// the exact name is preferred so that "a" and "A" resolve to different keysyms.
+pub fn from_name(name: &str) -> Option<Keysym> {
+    let mut sym = xkb::keysym_from_name(name, xkb::KEYSYM_NO_FLAGS);
+    if sym == ks::KEY_NoSymbol {
+        sym = xkb::keysym_from_name(name, xkb::KEYSYM_CASE_INSENSITIVE);
+    }
+    (sym != ks::KEY_NoSymbol).then_some(alias(sym))
+}
+
+// maps keypad digits onto the regular digits so that binds work from either
+pub fn alias(sym: Keysym) -> Keysym {
+    match sym {
+        ks::KEY_KP_0..=ks::KEY_KP_9 => sym - ks::KEY_KP_0 + ks::KEY_0,
+        _ => sym,
+    }
+}
+
+// whether the key only modifies others, like "Shift_L", "Super_R" or "ISO_Level3_Shift"
+pub fn is_modifier(sym: Keysym) -> bool {
+    matches!(
+        sym,
+        ks::KEY_Shift_L..=ks::KEY_Hyper_R
+            | ks::KEY_ISO_Lock..=ks::KEY_ISO_Last_Group_Lock
+            | ks::KEY_Mode_switch
+    )
+}
+
+// parses a key combination like "Mod+Shift+Return" into its modifiers and keysym.
+// `Mod` stands for `default_mod`, the modifier most keybinds share.
+pub fn parse_combo(spec: &str, default_mod: ModMask) -> Result<(ModMask, Keysym), ComboError> {
+    let (mods, key) = split_combo(spec);
+    let mut mask = ModMask::from(0u16);
+    for name in mods {
+        let m = parse_mod(name, default_mod).ok_or_else(|| ComboError::Modifier(name.into()))?;
+        mask = mask | m;
+    }
+    let sym = from_name(key).ok_or_else(|| ComboError::Key(key.into()))?;
+    Ok((mask, sym))
+}
+
+// the modifiers and the key of a combination. "Mod++" is the plus key.
+fn split_combo(spec: &str) -> (Vec<&str>, &str) {
+    let spec = spec.trim();
+    let (mods, key) = match spec.strip_suffix("++") {
+        Some(mods) => (mods, "plus"),
+        None => spec.rsplit_once('+').unwrap_or(("", spec)),
+    };
+    let mods = mods
+        .split('+')
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .collect();
+    (mods, key.trim())
+}
+
+// a modifier by name, where `mod` stands for `default_mod`
+pub fn parse_mod(name: &str, default_mod: ModMask) -> Option<ModMask> {
+    let mask = match name.to_ascii_lowercase().as_str() {
+        "mod" => default_mod,
+        "shift" => ModMask::SHIFT,
+        "ctrl" | "control" => ModMask::CONTROL,
+        "alt" | "mod1" => ModMask::M1,
+        "mod2" => ModMask::M2,
+        "hyper" | "mod3" => ModMask::M3,
+        "super" | "mod4" => ModMask::M4,
+        "mod5" => ModMask::M5,
+        _ => return None,
+    };
+    Some(mask)
+}
+
+// swaps the modifier `from` for `to` in `mods`, leaving masks without it alone
+pub fn replace_mod(mods: ModMask, from: ModMask, to: ModMask) -> ModMask {
+    let (mods, from) = (u16::from(mods), u16::from(from));
+    if from == 0 || mods & from != from {
+        return mods.into();
+    }
+    ModMask::from(mods & !from) | to
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combos_split_into_modifiers_and_key() {
+        assert_eq!(
+            split_combo("Mod+Shift+Return"),
+            (vec!["Mod", "Shift"], "Return")
+        );
+        assert_eq!(split_combo(" Print "), (vec![], "Print"));
+        assert_eq!(split_combo("Ctrl + Mod++"), (vec!["Ctrl", "Mod"], "plus"));
+    }
+
+    #[test]
+    fn mod_stands_for_the_default_modifier() {
+        assert_eq!(parse_mod("Mod", ModMask::M4), Some(ModMask::M4));
+        assert_eq!(parse_mod("ctrl", ModMask::M4), Some(ModMask::CONTROL));
+        assert_eq!(parse_mod("Hyper", ModMask::M4), Some(ModMask::M3));
+        assert_eq!(parse_mod("Meta", ModMask::M4), None);
+    }
+
+    #[test]
+    fn modifier_keys() {
+        assert!(is_modifier(ks::KEY_Alt_L));
+        assert!(is_modifier(ks::KEY_Super_R));
+        assert!(is_modifier(ks::KEY_ISO_Level3_Shift));
+        assert!(!is_modifier(ks::KEY_Tab));
+        assert!(!is_modifier(ks::KEY_a));
+    }
+
+    #[test]
+    fn replacing_the_default_modifier() {
+        let alt_shift = ModMask::M1 | ModMask::SHIFT;
+        let super_shift = ModMask::M4 | ModMask::SHIFT;
+        assert_eq!(
+            replace_mod(alt_shift, ModMask::M1, ModMask::M4),
+            super_shift
+        );
+        assert_eq!(
+            replace_mod(ModMask::CONTROL, ModMask::M1, ModMask::M4),
+            ModMask::CONTROL
+        );
+    }
+}
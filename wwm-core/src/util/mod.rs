@@ -1,6 +1,8 @@
 pub mod bar;
 pub mod cmd;
 pub mod color;
+pub mod ipc;
+pub mod keysym;
 pub mod primitives;
 
 use x11rb::protocol::xproto::ConfigWindow;
@@ -9,14 +11,47 @@ use x11rb::protocol::xproto::ConfigWindow;
 pub enum WLayout {
     #[default]
     MainStack,
+    // the main area on top and the stack below it
+    BottomStack,
     Column,
+    // every client takes up the whole area, with a tab for each above them
+    Tabbed,
+    // nothing is tiled and every client keeps its own geometry, like dwm's NULL layout
+    Floating,
+}
+
+impl WLayout {
+    // how the layout is called in the config, the session file and over ipc
+    pub fn name(self) -> &'static str {
+        match self {
+            WLayout::MainStack => "main-stack",
+            WLayout::BottomStack => "bottom-stack",
+            WLayout::Column => "column",
+            WLayout::Tabbed => "tabbed",
+            WLayout::Floating => "floating",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "main-stack" => Some(WLayout::MainStack),
+            "bottom-stack" => Some(WLayout::BottomStack),
+            "column" => Some(WLayout::Column),
+            "tabbed" => Some(WLayout::Tabbed),
+            "floating" => Some(WLayout::Floating),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for WLayout {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let symbol = match self {
             WLayout::MainStack => "[]=",
+            WLayout::BottomStack => "TTT",
             WLayout::Column => "|||",
+            WLayout::Tabbed => "[T]",
+            WLayout::Floating => "><>",
         };
         write!(f, "{symbol}")
     }
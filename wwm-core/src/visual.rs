@@ -28,6 +28,8 @@ pub struct RenderVisualInfo {
     pub screen_root: u32,
     pub root: VisualInfo,
     pub render: VisualInfo,
+    // 8 bit alpha, which is all glyphs need
+    pub glyph_format: Pictformat,
 }
 
 #[derive(Debug)]
@@ -48,9 +50,20 @@ impl RenderVisualInfo {
                 Some(screen.root_visual),
             )?,
             render: VisualInfo::find_appropriate_visual(conn, 32, None)?,
+            glyph_format: Self::find_alpha_format(conn)?,
         };
         Ok(rvi)
     }
+
+    fn find_alpha_format<C: Connection>(conn: &C) -> Result<Pictformat, VisualError> {
+        let formats = query_pict_formats(conn)?.reply()?;
+        formats
+            .formats
+            .into_iter()
+            .find(|f| f.type_ == PictType::DIRECT && f.depth == 8 && f.direct.alpha_mask == 0xff)
+            .map(|f| f.id)
+            .ok_or(VisualError::NoAppropriateVisual)
+    }
 }
 
 impl VisualInfo {
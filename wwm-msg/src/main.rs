@@ -0,0 +1,134 @@
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    process,
+};
+
+use serde_json::{json, Value};
+use wwm_core::util::ipc::socket_path;
+
+const USAGE: &str = "\
+usage: wwm-msg [--socket <path>] <command> [args...]
+       wwm-msg [--socket <path>] query monitors|tags|clients|version
+       wwm-msg [--socket <path>] subscribe focus|tag|client|layout|title...
+
+sends a command to wwm, like the ones of the [keybinds] table in wwm.toml:
+  wwm-msg focus next
+  wwm-msg tag 3
+  wwm-msg layout column
+  wwm-msg spawn alacritty -e htop
+
+subscribing prints the events as they happen, one json object on each line.
+
+  --socket <path>   the socket of wwm, $XDG_RUNTIME_DIR/wwm-<display>.sock by default";
+
+// shorter names for the commands used most from scripts
+const ALIASES: &[(&str, &str)] = &[
+    ("focus", "focus-client"),
+    ("move", "move-client"),
+    ("tag", "select-tag"),
+    ("move-to-tag", "move-client-to-tag"),
+    ("monitor", "focus-monitor"),
+];
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut socket = None;
+    if let Some(i) = args.iter().position(|a| a == "--socket" || a == "-s") {
+        if i + 1 >= args.len() {
+            fail("`--socket` needs a path");
+        }
+        socket = Some(PathBuf::from(args.remove(i + 1)));
+        args.remove(i);
+    }
+    if args.is_empty() || args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{USAGE}");
+        return;
+    }
+    let Some(socket) = socket.or_else(socket_path) else {
+        fail("$XDG_RUNTIME_DIR or $DISPLAY isn't set, pass the socket with --socket");
+    };
+
+    let (response, reader) = match send(&socket, &request(&args)) {
+        Ok(reply) => reply,
+        Err(e) => fail(&format!("{}: {e}", socket.display())),
+    };
+    if response["success"] != json!(true) {
+        let error = response["error"].as_str().unwrap_or("the request failed");
+        fail(error);
+    }
+    if let Some(data) = response.get("data") {
+        let text = serde_json::to_string_pretty(data).unwrap_or_default();
+        println!("{text}");
+    }
+    if args[0] == "subscribe" {
+        for line in reader.lines() {
+            match line {
+                Ok(line) => println!("{line}"),
+                Err(e) => fail(&e.to_string()),
+            }
+        }
+    }
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("wwm-msg: {msg}");
+    process::exit(1);
+}
+
+fn request(args: &[String]) -> Value {
+    let (name, rest) = args.split_first().expect("there is a command");
+    if name == "query" {
+        return json!({ "query": rest.join(" ") });
+    }
+    if name == "subscribe" {
+        return json!({ "subscribe": rest });
+    }
+    let name = ALIASES
+        .iter()
+        .find(|(alias, _)| alias == name)
+        .map_or(name.as_str(), |(_, command)| command);
+    json!({ "command": name, "args": rest })
+}
+
+// sends the request and waits for the reply to it. what is sent after the reply, like
+// subscribed events, is left in the returned reader.
+fn send(socket: &Path, request: &Value) -> io::Result<(Value, BufReader<UnixStream>)> {
+    let mut stream = UnixStream::connect(socket)?;
+    writeln!(stream, "{request}")?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    Ok((serde_json::from_str(&line)?, reader))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn aliases_and_arguments() {
+        let expected = json!({ "command": "select-tag", "args": ["3"] });
+        assert_eq!(request(&args(&["tag", "3"])), expected);
+        let expected = json!({ "command": "spawn", "args": ["st", "-e", "top"] });
+        assert_eq!(request(&args(&["spawn", "st", "-e", "top"])), expected);
+        assert_eq!(
+            request(&args(&["query", "clients"])),
+            json!({ "query": "clients" })
+        );
+        assert_eq!(
+            request(&args(&["query", "version"])),
+            json!({ "query": "version" })
+        );
+        assert_eq!(
+            request(&args(&["subscribe", "focus", "tag"])),
+            json!({ "subscribe": ["focus", "tag"] })
+        );
+    }
+}